@@ -178,17 +178,17 @@ pub fn test_mpc_alpha() {
     //链下：player1计算
     let player1_param = mpc_common_paramters_custom_generator::<Bls12>(&list[0], player1);
     //链上：验证player1，成功则添加
-    list = ParamterListExcute::<Bls12>(list, player1_param.unwrap());
+    list = ParamterListExcute::<Bls12>(list, player1_param.unwrap()).unwrap();
     //链下：player1计算
     let player2_param = mpc_common_paramters_custom_generator::<Bls12>(&list[1], player2);
     //链上：验证player2，成功则添加
-    list = ParamterListExcute::<Bls12>(list, player2_param.unwrap());
+    list = ParamterListExcute::<Bls12>(list, player2_param.unwrap()).unwrap();
     //链下：player3计算
     let player3_param = mpc_common_paramters_custom_generator::<Bls12>(&list[2], player3);
     //捣乱的
     //let badman3_params = mpc_bad_paramters_custom::<Bls12>(&list[2], player3);
     //链上：验证player3，成功则添加
-    list = ParamterListExcute::<Bls12>(list, player3_param.unwrap());
+    list = ParamterListExcute::<Bls12>(list, player3_param.unwrap()).unwrap();
     //list = ParamterListExcute::<Bls12>(list, badman3_params.unwrap());
     //验证list[3]与6*g1是否相等'
     let g1 = bls12_381::G1Affine::generator();
@@ -211,20 +211,20 @@ pub fn test_mpc_tau() {
 
     println!("player0 create custom done");
     //链上：验证player1，成功则添加
-    list = TauParamterListExcute::<Bls12>(list, player0_param.unwrap());
+    list = TauParamterListExcute::<Bls12>(list, player0_param.unwrap()).unwrap();
     println!("player0 verify done");
 
     //链下：player1计算
     let player1_param = mpc_common_tauparamters_custom_generator::<Bls12>(&list[1], player1);
     println!("player1 create custom done");
     //链上：验证player1，成功则添加
-    list = TauParamterListExcute::<Bls12>(list, player1_param.unwrap());
+    list = TauParamterListExcute::<Bls12>(list, player1_param.unwrap()).unwrap();
     println!("player1 verify done");
     //链下：player1计算
     let player2_param = mpc_common_tauparamters_custom_generator::<Bls12>(&list[2], player2);
     println!("player2 create custom done");
     //链上：验证player2，成功则添加
-    list = TauParamterListExcute::<Bls12>(list, player2_param.unwrap());
+    list = TauParamterListExcute::<Bls12>(list, player2_param.unwrap()).unwrap();
     println!("player2 verify done");
     //链下：player3计算
     let player3_param = mpc_common_tauparamters_custom_generator::<Bls12>(&list[3], player3);
@@ -232,7 +232,7 @@ pub fn test_mpc_tau() {
     //捣乱的
     //let badman3_params = mpc_bad_paramters_custom::<Bls12>(&list[2], player3);
     //链上：验证player3，成功则添加
-    list = TauParamterListExcute::<Bls12>(list, player3_param.unwrap());
+    list = TauParamterListExcute::<Bls12>(list, player3_param.unwrap()).unwrap();
     println!("player3 verify done");
     //list = ParamterListExcute::<Bls12>(list, badman3_params.unwrap());
     let g1 = bls12_381::G1Affine::generator();
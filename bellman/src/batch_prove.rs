@@ -0,0 +1,80 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use bls12_381::Bls12;
+use ff::Field;
+use rand::thread_rng;
+
+use crate::groth16::{create_proofs_batch, create_random_proof, generate_random_parameters};
+
+use crate::mimc_mod::*;
+
+fn bench_batch_prove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Batch Proving");
+    group.sample_size(10);
+
+    let mut rng = thread_rng();
+
+    // Generate the MiMC round constants
+    let constants = (0..MIMC_ROUNDS)
+        .map(|_| bls12_381::Scalar::random(&mut rng))
+        .collect::<Vec<_>>();
+
+    // Create parameters for our circuit
+    let params = {
+        let c = MiMCDemo {
+            xl: None,
+            xr: None,
+            constants: &constants,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    for &n in [4usize, 8, 16, 32].iter() {
+        group.throughput(Throughput::Elements(n as u64));
+
+        let preimages = (0..n)
+            .map(|_| {
+                (
+                    bls12_381::Scalar::random(&mut rng),
+                    bls12_381::Scalar::random(&mut rng),
+                )
+            })
+            .collect::<Vec<_>>();
+        let circuits = |preimages: &[(bls12_381::Scalar, bls12_381::Scalar)]| {
+            preimages
+                .iter()
+                .map(|&(xl, xr)| MiMCDemo {
+                    xl: Some(xl),
+                    xr: Some(xr),
+                    constants: &constants,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        group.bench_with_input(
+            BenchmarkId::new("Unbatched proving", n),
+            &preimages,
+            |b, preimages| {
+                b.iter(|| {
+                    for c in circuits(preimages) {
+                        create_random_proof(c, &params, &mut rng).unwrap();
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("Batched proving", n),
+            &preimages,
+            |b, preimages| {
+                b.iter(|| create_proofs_batch(circuits(preimages), &params, &mut rng).unwrap())
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_prove);
+criterion_main!(benches);
@@ -12,7 +12,7 @@
 //! [Groth16]: https://eprint.iacr.org/2016/260
 
 use ff::PrimeField;
-use group::cofactor::CofactorCurve;
+use std::sync::Arc;
 
 use super::SynthesisError;
 
@@ -25,6 +25,97 @@ pub struct EvaluationDomain<S: PrimeField, G: Group<S>> {
     omegainv: S,
     geninv: S,
     minv: S,
+    tables: Option<Arc<DomainTables<S>>>,
+}
+
+/// Powers of the roots of unity and of the coset generator of an evaluation
+/// domain, computed once and shared by every [`EvaluationDomain`] of that
+/// size, e.g. when proving many instances of the same circuit.
+pub struct DomainTables<S: PrimeField> {
+    exp: u32,
+    // omega^i and omega^-i for i < m / 2
+    roots: Vec<S>,
+    inv_roots: Vec<S>,
+    // g^i and g^-i for i < m
+    coset_powers: Vec<S>,
+    inv_coset_powers: Vec<S>,
+    // z(g)^-1
+    z_inv: S,
+}
+
+impl<S: PrimeField> DomainTables<S> {
+    /// Computes the tables for the domain that polynomials with `len`
+    /// coefficients are evaluated over.
+    pub fn new(worker: &Worker, len: usize) -> Result<Self, SynthesisError> {
+        let (m, exp) = domain_size::<S>(len)?;
+        let omega = root_of_unity::<S>(exp);
+        let geninv = S::multiplicative_generator().invert().unwrap();
+
+        let mut z_inv = S::multiplicative_generator().pow_vartime(&[m as u64]);
+        z_inv.sub_assign(&S::one());
+
+        Ok(DomainTables {
+            exp,
+            roots: powers(worker, omega, m / 2),
+            inv_roots: powers(worker, omega.invert().unwrap(), m / 2),
+            coset_powers: powers(worker, S::multiplicative_generator(), m),
+            inv_coset_powers: powers(worker, geninv, m),
+            z_inv: z_inv.invert().unwrap(),
+        })
+    }
+
+    /// The size of the domain.
+    pub fn size(&self) -> usize {
+        1 << self.exp
+    }
+}
+
+/// Returns the size of the smallest radix-2 domain holding `len`
+/// coefficients, and its base-2 logarithm.
+fn domain_size<S: PrimeField>(len: usize) -> Result<(usize, u32), SynthesisError> {
+    let mut m = 1;
+    let mut exp = 0;
+    while m < len {
+        m *= 2;
+        exp += 1;
+
+        // The pairing-friendly curve may not be able to support
+        // large enough (radix2) evaluation domains.
+        if exp >= S::S {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+    }
+
+    Ok((m, exp))
+}
+
+/// Computes omega, the 2^exp primitive root of unity.
+fn root_of_unity<S: PrimeField>(exp: u32) -> S {
+    let mut omega = S::root_of_unity();
+    for _ in exp..S::S {
+        omega = omega.square();
+    }
+    omega
+}
+
+/// Computes `g^i` for `i < n`.
+fn powers<S: PrimeField>(worker: &Worker, g: S, n: usize) -> Vec<S> {
+    let mut powers = vec![S::zero(); n];
+    if n == 0 {
+        return powers;
+    }
+    worker.scope(n, |scope, chunk| {
+        for (i, v) in powers.chunks_mut(chunk).enumerate() {
+            scope.spawn(move |_scope| {
+                let mut u = g.pow_vartime(&[(i * chunk) as u64]);
+                for v in v.iter_mut() {
+                    *v = u;
+                    u.mul_assign(&g);
+                }
+            });
+        }
+    });
+    powers
 }
 
 impl<S: PrimeField, G: Group<S>> AsRef<[G]> for EvaluationDomain<S, G> {
@@ -46,24 +137,10 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
 
     pub fn from_coeffs(mut coeffs: Vec<G>) -> Result<EvaluationDomain<S, G>, SynthesisError> {
         // Compute the size of our evaluation domain
-        let mut m = 1;
-        let mut exp = 0;
-        while m < coeffs.len() {
-            m *= 2;
-            exp += 1;
-
-            // The pairing-friendly curve may not be able to support
-            // large enough (radix2) evaluation domains.
-            if exp >= S::S {
-                return Err(SynthesisError::PolynomialDegreeTooLarge);
-            }
-        }
+        let (m, exp) = domain_size::<S>(coeffs.len())?;
 
         // Compute omega, the 2^exp primitive root of unity
-        let mut omega = S::root_of_unity();
-        for _ in exp..S::S {
-            omega = omega.square();
-        }
+        let omega = root_of_unity::<S>(exp);
 
         // Extend the coeffs vector with zeroes if necessary
         coeffs.resize(m, G::group_zero());
@@ -75,15 +152,32 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
             omegainv: omega.invert().unwrap(),
             geninv: S::multiplicative_generator().invert().unwrap(),
             minv: S::from(m as u64).invert().unwrap(),
+            tables: None,
         })
     }
 
+    /// Like [`EvaluationDomain::from_coeffs`], but the FFTs and the coset
+    /// operations look their constants up in `tables` rather than computing
+    /// them. `coeffs` must belong to the domain of `tables`.
+    pub fn from_coeffs_with_tables(
+        coeffs: Vec<G>,
+        tables: Arc<DomainTables<S>>,
+    ) -> Result<EvaluationDomain<S, G>, SynthesisError> {
+        let mut domain = Self::from_coeffs(coeffs)?;
+        assert_eq!(domain.exp, tables.exp);
+        domain.tables = Some(tables);
+
+        Ok(domain)
+    }
+
     pub fn fft(&mut self, worker: &Worker) {
-        best_fft(&mut self.coeffs, worker, &self.omega, self.exp);
+        let roots = self.tables.as_ref().map(|t| &t.roots[..]);
+        best_fft(&mut self.coeffs, worker, &self.omega, self.exp, roots);
     }
 
     pub fn ifft(&mut self, worker: &Worker) {
-        best_fft(&mut self.coeffs, worker, &self.omegainv, self.exp);
+        let roots = self.tables.as_ref().map(|t| &t.inv_roots[..]);
+        best_fft(&mut self.coeffs, worker, &self.omegainv, self.exp, roots);
 
         worker.scope(self.coeffs.len(), |scope, chunk| {
             let minv = self.minv;
@@ -112,8 +206,24 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
         });
     }
 
+    /// Multiplies the `i`-th coefficient by `powers[i]`.
+    fn distribute_table(&mut self, worker: &Worker, powers: &[S]) {
+        worker.scope(self.coeffs.len(), |scope, chunk| {
+            for (v, p) in self.coeffs.chunks_mut(chunk).zip(powers.chunks(chunk)) {
+                scope.spawn(move |_scope| {
+                    for (v, p) in v.iter_mut().zip(p.iter()) {
+                        v.group_mul_assign(p);
+                    }
+                });
+            }
+        });
+    }
+
     pub fn coset_fft(&mut self, worker: &Worker) {
-        self.distribute_powers(worker, S::multiplicative_generator());
+        match self.tables.clone() {
+            Some(tables) => self.distribute_table(worker, &tables.coset_powers),
+            None => self.distribute_powers(worker, S::multiplicative_generator()),
+        }
         self.fft(worker);
     }
 
@@ -121,7 +231,10 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
         let geninv = self.geninv;
 
         self.ifft(worker);
-        self.distribute_powers(worker, geninv);
+        match self.tables.clone() {
+            Some(tables) => self.distribute_table(worker, &tables.inv_coset_powers),
+            None => self.distribute_powers(worker, geninv),
+        }
     }
 
     /// This evaluates t(tau) for this domain, which is
@@ -137,7 +250,10 @@ impl<S: PrimeField, G: Group<S>> EvaluationDomain<S, G> {
     /// evaluation domain, so we must perform division over
     /// a coset.
     pub fn divide_by_z_on_coset(&mut self, worker: &Worker) {
-        let i = self.z(&S::multiplicative_generator()).invert().unwrap();
+        let i = match self.tables {
+            Some(ref tables) => tables.z_inv,
+            None => self.z(&S::multiplicative_generator()).invert().unwrap(),
+        };
 
         worker.scope(self.coeffs.len(), |scope, chunk| {
             for v in self.coeffs.chunks_mut(chunk) {
@@ -196,23 +312,23 @@ pub trait Group<Scalar: PrimeField>: Sized + Copy + Clone + Send + Sync {
     fn group_sub_assign(&mut self, other: &Self);
 }
 
-pub struct Point<G: CofactorCurve>(pub G);
+pub struct Point<G: group::Group>(pub G);
 
-impl<G: CofactorCurve> PartialEq for Point<G> {
+impl<G: group::Group> PartialEq for Point<G> {
     fn eq(&self, other: &Point<G>) -> bool {
         self.0 == other.0
     }
 }
 
-impl<G: CofactorCurve> Copy for Point<G> {}
+impl<G: group::Group> Copy for Point<G> {}
 
-impl<G: CofactorCurve> Clone for Point<G> {
+impl<G: group::Group> Clone for Point<G> {
     fn clone(&self) -> Point<G> {
         *self
     }
 }
 
-impl<G: CofactorCurve> Group<G::Scalar> for Point<G> {
+impl<G: group::Group> Group<G::Scalar> for Point<G> {
     fn group_zero() -> Self {
         Point(G::identity())
     }
@@ -258,18 +374,32 @@ impl<S: PrimeField> Group<S> for Scalar<S> {
     }
 }
 
-fn best_fft<S: PrimeField, T: Group<S>>(a: &mut [T], worker: &Worker, omega: &S, log_n: u32) {
+/// `roots`, if given, holds `omega^i` for `i < 2^(log_n - 1)`.
+fn best_fft<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    worker: &Worker,
+    omega: &S,
+    log_n: u32,
+    roots: Option<&[S]>,
+) {
     let log_cpus = worker.log_num_threads();
 
     if log_n <= log_cpus {
-        serial_fft(a, omega, log_n);
+        serial_fft(a, omega, log_n, roots.map(|roots| (roots, 1)));
     } else {
-        parallel_fft(a, worker, omega, log_n, log_cpus);
+        parallel_fft(a, worker, omega, log_n, log_cpus, roots);
     }
 }
 
+/// `roots`, if given, is a table of powers of a root of unity of which
+/// `omega` is the `stride`-th power.
 #[allow(clippy::many_single_char_names)]
-fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], omega: &S, log_n: u32) {
+fn serial_fft<S: PrimeField, T: Group<S>>(
+    a: &mut [T],
+    omega: &S,
+    log_n: u32,
+    roots: Option<(&[S], usize)>,
+) {
     fn bitreverse(mut n: u32, l: u32) -> u32 {
         let mut r = 0;
         for _ in 0..l {
@@ -291,19 +421,26 @@ fn serial_fft<S: PrimeField, T: Group<S>>(a: &mut [T], omega: &S, log_n: u32) {
 
     let mut m = 1;
     for _ in 0..log_n {
-        let w_m = omega.pow_vartime(&[u64::from(n / (2 * m))]);
+        let step = n / (2 * m);
+        let w_m = omega.pow_vartime(&[u64::from(step)]);
 
         let mut k = 0;
         while k < n {
             let mut w = S::one();
             for j in 0..m {
+                if let Some((roots, stride)) = roots {
+                    w = roots[(j * step) as usize * stride];
+                }
+
                 let mut t = a[(k + j + m) as usize];
                 t.group_mul_assign(&w);
                 let mut tmp = a[(k + j) as usize];
                 tmp.group_sub_assign(&t);
                 a[(k + j + m) as usize] = tmp;
                 a[(k + j) as usize].group_add_assign(&t);
-                w.mul_assign(&w_m);
+                if roots.is_none() {
+                    w.mul_assign(&w_m);
+                }
             }
 
             k += 2 * m;
@@ -319,6 +456,7 @@ fn parallel_fft<S: PrimeField, T: Group<S>>(
     omega: &S,
     log_n: u32,
     log_cpus: u32,
+    roots: Option<&[S]>,
 ) {
     assert!(log_n >= log_cpus);
 
@@ -349,7 +487,12 @@ fn parallel_fft<S: PrimeField, T: Group<S>>(
                 }
 
                 // Perform sub-FFT
-                serial_fft(tmp, &new_omega, log_new_n);
+                serial_fft(
+                    tmp,
+                    &new_omega,
+                    log_new_n,
+                    roots.map(|roots| (roots, num_cpus)),
+                );
             });
         }
     });
@@ -483,8 +626,8 @@ fn parallel_fft_consistency() {
                 let mut v2 = EvaluationDomain::from_coeffs(v1.coeffs.clone()).unwrap();
 
                 for log_cpus in log_d..min(log_d + 1, 3) {
-                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus);
-                    serial_fft(&mut v2.coeffs, &v2.omega, log_d);
+                    parallel_fft(&mut v1.coeffs, &worker, &v1.omega, log_d, log_cpus, None);
+                    serial_fft(&mut v2.coeffs, &v2.omega, log_d, None);
 
                     assert!(v1.coeffs == v2.coeffs);
                 }
@@ -496,3 +639,58 @@ fn parallel_fft_consistency() {
 
     test_consistency::<Fr, _>(rng);
 }
+
+#[cfg(feature = "pairing")]
+#[test]
+fn fft_with_tables() {
+    use bls12_381::Scalar as Fr;
+    use rand_core::RngCore;
+
+    fn test_tables<S: PrimeField, R: RngCore>(mut rng: &mut R) {
+        let worker = Worker::new();
+
+        for log_d in 0..10 {
+            let d = 1 << log_d;
+            let tables = Arc::new(DomainTables::new(&worker, d).unwrap());
+            assert_eq!(tables.size(), d);
+
+            let v = (0..d)
+                .map(|_| Scalar::<S>(S::random(&mut rng)))
+                .collect::<Vec<_>>();
+            let mut plain = EvaluationDomain::from_coeffs(v.clone()).unwrap();
+            let mut tabled = EvaluationDomain::from_coeffs_with_tables(v, tables.clone()).unwrap();
+
+            plain.ifft(&worker);
+            tabled.ifft(&worker);
+            assert!(plain.coeffs == tabled.coeffs);
+            plain.coset_fft(&worker);
+            tabled.coset_fft(&worker);
+            assert!(plain.coeffs == tabled.coeffs);
+            plain.divide_by_z_on_coset(&worker);
+            tabled.divide_by_z_on_coset(&worker);
+            assert!(plain.coeffs == tabled.coeffs);
+            plain.icoset_fft(&worker);
+            tabled.icoset_fft(&worker);
+            assert!(plain.coeffs == tabled.coeffs);
+
+            for log_cpus in 0..log_d.min(3) + 1 {
+                let mut v1 = plain.coeffs.clone();
+                let mut v2 = plain.coeffs.clone();
+                parallel_fft(&mut v1, &worker, &plain.omega, log_d, log_cpus, None);
+                parallel_fft(
+                    &mut v2,
+                    &worker,
+                    &plain.omega,
+                    log_d,
+                    log_cpus,
+                    Some(&tables.roots),
+                );
+                assert!(v1 == v2);
+            }
+        }
+    }
+
+    let rng = &mut rand::thread_rng();
+
+    test_tables::<Fr, _>(rng);
+}
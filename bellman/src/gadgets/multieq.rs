@@ -118,4 +118,8 @@ impl<Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Scalar>
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        self.cs.commit(variable)
+    }
 }
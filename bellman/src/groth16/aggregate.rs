@@ -0,0 +1,691 @@
+//! Aggregation of many Groth16 proofs under one verifying key, following
+//! [SnarkPack].
+//!
+//! An [`AggregateProof`] for `n` proofs has `O(log n)` size. It consists of
+//! pairing-based commitments to the proofs' `A`, `B` and `C` elements, a
+//! random linear combination of the Groth16 equations, and inner-pairing-
+//! product arguments (TIPP for `A` and `B`, MIPP for `C`) showing that the
+//! combination was computed from the committed proofs. The commitment keys
+//! are opened with KZG proofs against an [`AggregationSrs`], which can be
+//! taken from the tau powers of two independent common ceremonies.
+//!
+//! The number of proofs is padded to a power of two by repeating the last
+//! proof; the verifier pads the public inputs the same way. The Fiat-Shamir
+//! transcript starts with the verifying key and every proof's public inputs,
+//! so the prover needs them too.
+//!
+//! Target group elements are absorbed through their `Debug` output, which is
+//! the only view `bls12_381` gives of their coefficients, so aggregation is
+//! only available over `bls12_381`. Should a `bls12_381` release print them
+//! differently, aggregate proofs made before the upgrade no longer verify.
+//!
+//! [SnarkPack]: https://eprint.iacr.org/2021/529
+
+use std::error::Error;
+use std::fmt;
+
+use bls12_381::Gt;
+use ff::{Field, PrimeField};
+use group::{prime::PrimeCurveAffine, Curve, Group};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+
+use super::{PreparedVerifyingKey, Proof};
+use crate::VerificationError;
+
+use self::transcript::Transcript;
+
+mod srs;
+mod transcript;
+
+pub use self::srs::{AggregationSrs, AggregationVerifierKey};
+
+/// Errors that can occur while aggregating proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregationError {
+    /// There were no proofs to aggregate.
+    NoProofs,
+    /// The SRS has too few powers for the number of proofs.
+    SrsTooSmall,
+    /// The powers passed to [`AggregationSrs`] don't form a usable SRS.
+    MalformedSrs,
+    /// A proof carries a commitment, which the aggregated equation doesn't
+    /// account for.
+    CommittedProof,
+    /// The public inputs don't match the proofs or the verifying key.
+    MismatchedInputs,
+}
+
+impl fmt::Display for AggregationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            AggregationError::NoProofs => write!(f, "no proofs to aggregate"),
+            AggregationError::SrsTooSmall => write!(f, "aggregation SRS is too small"),
+            AggregationError::MalformedSrs => write!(f, "malformed aggregation SRS"),
+            AggregationError::CommittedProof => {
+                write!(f, "proofs carrying a commitment can't be aggregated")
+            }
+            AggregationError::MismatchedInputs => {
+                write!(f, "public inputs don't match the proofs or verifying key")
+            }
+        }
+    }
+}
+
+impl Error for AggregationError {}
+
+/// The messages of one round of the inner-pairing-product arguments.
+#[derive(Clone, Debug)]
+pub struct GipaRound<E: Engine> {
+    pub com_ab_l: (E::Gt, E::Gt),
+    pub com_ab_r: (E::Gt, E::Gt),
+    pub z_ab_l: E::Gt,
+    pub z_ab_r: E::Gt,
+    pub com_c_l: (E::Gt, E::Gt),
+    pub com_c_r: (E::Gt, E::Gt),
+    pub z_c_l: E::G1Affine,
+    pub z_c_r: E::G1Affine,
+}
+
+#[derive(Clone, Debug)]
+pub struct AggregateProof<E: Engine> {
+    /// Commitment to the `A` and `B` elements of all proofs.
+    pub com_ab: (E::Gt, E::Gt),
+    /// Commitment to the `C` elements of all proofs.
+    pub com_c: (E::Gt, E::Gt),
+    /// `prod e(A_i, B_i)^(r^i)`.
+    pub z_ab: E::Gt,
+    /// `sum r^i C_i`.
+    pub z_c: E::G1Affine,
+    pub rounds: Vec<GipaRound<E>>,
+    pub final_a: E::G1Affine,
+    pub final_b: E::G2Affine,
+    pub final_c: E::G1Affine,
+    /// The folded commitment keys in G2, for the powers of `a` and `b`.
+    pub final_v: (E::G2Affine, E::G2Affine),
+    /// The folded commitment keys in G1, for the powers of `a` and `b`.
+    pub final_w: (E::G1Affine, E::G1Affine),
+    /// KZG openings of `final_v`.
+    pub opening_v: (E::G2Affine, E::G2Affine),
+    /// KZG openings of `final_w`.
+    pub opening_w: (E::G1Affine, E::G1Affine),
+}
+
+/// Aggregates `proofs`, which must all have been created under the verifying
+/// key `pvk` and must not carry a commitment. The `i`-th proof has the public
+/// inputs `public_inputs[i]`.
+pub fn aggregate_proofs<E: MultiMillerLoop<Gt = Gt>>(
+    srs: &AggregationSrs<E>,
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[Proof<E>],
+    public_inputs: &[Vec<E::Fr>],
+) -> Result<AggregateProof<E>, AggregationError> {
+    let last = proofs.last().ok_or(AggregationError::NoProofs)?;
+    if public_inputs.len() != proofs.len()
+        || public_inputs
+            .iter()
+            .any(|inputs| inputs.len() + 1 != pvk.ic.len())
+    {
+        return Err(AggregationError::MismatchedInputs);
+    }
+    if proofs
+        .iter()
+        .any(|proof| !bool::from(proof.d.is_identity() & proof.d_pok.is_identity()))
+    {
+        return Err(AggregationError::CommittedProof);
+    }
+    let n = proofs.len().next_power_of_two();
+    if n > srs.max_proofs() {
+        return Err(AggregationError::SrsTooSmall);
+    }
+
+    let padded = proofs.iter().chain((proofs.len()..n).map(|_| last));
+    let mut a = Vec::with_capacity(n);
+    let mut b = Vec::with_capacity(n);
+    let mut c = Vec::with_capacity(n);
+    for proof in padded {
+        a.push(proof.a);
+        b.push(proof.b);
+        c.push(proof.c);
+    }
+
+    let mut v1 = srs.h_a[..n].to_vec();
+    let mut v2 = srs.h_b[..n].to_vec();
+    let w1 = &srs.g_a[n..2 * n];
+    let w2 = &srs.g_b[n..2 * n];
+
+    let com_ab = (
+        pairing_product::<E>(&a, &v1) + pairing_product::<E>(w1, &b),
+        pairing_product::<E>(&a, &v2) + pairing_product::<E>(w2, &b),
+    );
+    let com_c = (pairing_product::<E>(&c, &v1), pairing_product::<E>(&c, &v2));
+
+    let mut transcript = Transcript::new();
+    append_statement(&mut transcript, pvk, public_inputs);
+    append_pair(&mut transcript, &com_ab);
+    append_pair(&mut transcript, &com_c);
+    let r: E::Fr = transcript.challenge();
+    let r_inv = r.invert().unwrap();
+
+    // Scale B by the powers of r, and the G1 keys by the inverse powers so
+    // that the commitment to A and B is unchanged.
+    let r_powers = powers(r, n);
+    let r_inv_powers = powers(r_inv, n);
+    let mut b = scale_g2::<E>(&b, &r_powers);
+    let mut w1 = scale_g1::<E>(w1, &r_inv_powers);
+    let mut w2 = scale_g1::<E>(w2, &r_inv_powers);
+    let mut s = r_powers;
+
+    let z_ab = pairing_product::<E>(&a, &b);
+    let z_c = inner_product::<E>(&c, &s).to_affine();
+    transcript.append_gt(&z_ab);
+    transcript.append_point(&z_c);
+
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+    while a.len() > 1 {
+        let split = a.len() / 2;
+        let (a_l, a_r) = a.split_at(split);
+        let (b_l, b_r) = b.split_at(split);
+        let (c_l, c_r) = c.split_at(split);
+        let (v1_l, v1_r) = v1.split_at(split);
+        let (v2_l, v2_r) = v2.split_at(split);
+        let (w1_l, w1_r) = w1.split_at(split);
+        let (w2_l, w2_r) = w2.split_at(split);
+        let (s_l, s_r) = s.split_at(split);
+
+        let round = GipaRound {
+            com_ab_l: (
+                pairing_product::<E>(a_r, v1_l) + pairing_product::<E>(w1_r, b_l),
+                pairing_product::<E>(a_r, v2_l) + pairing_product::<E>(w2_r, b_l),
+            ),
+            com_ab_r: (
+                pairing_product::<E>(a_l, v1_r) + pairing_product::<E>(w1_l, b_r),
+                pairing_product::<E>(a_l, v2_r) + pairing_product::<E>(w2_l, b_r),
+            ),
+            z_ab_l: pairing_product::<E>(a_r, b_l),
+            z_ab_r: pairing_product::<E>(a_l, b_r),
+            com_c_l: (
+                pairing_product::<E>(c_r, v1_l),
+                pairing_product::<E>(c_r, v2_l),
+            ),
+            com_c_r: (
+                pairing_product::<E>(c_l, v1_r),
+                pairing_product::<E>(c_l, v2_r),
+            ),
+            z_c_l: inner_product::<E>(c_r, s_l).to_affine(),
+            z_c_r: inner_product::<E>(c_l, s_r).to_affine(),
+        };
+        append_round(&mut transcript, &round);
+        let x: E::Fr = transcript.challenge();
+        let x_inv = x.invert().unwrap();
+
+        a = fold_g1::<E>(&a, x);
+        b = fold_g2::<E>(&b, x_inv);
+        c = fold_g1::<E>(&c, x);
+        v1 = fold_g2::<E>(&v1, x_inv);
+        v2 = fold_g2::<E>(&v2, x_inv);
+        w1 = fold_g1::<E>(&w1, x);
+        w2 = fold_g1::<E>(&w2, x);
+        s = s_l.iter().zip(s_r).map(|(l, r)| *l + *r * x_inv).collect();
+
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let final_v = (v1[0], v2[0]);
+    let final_w = (w1[0], w2[0]);
+    append_final::<E>(&mut transcript, (a[0], b[0], c[0]), final_v, final_w);
+    let z: E::Fr = transcript.challenge();
+
+    // The folded keys are commitments to polynomials determined by the
+    // challenges; open them at z.
+    let (v_coeffs, w_coeffs) = key_polynomials(&challenges, r_inv, n);
+    let v_quotient = kzg_quotient(&v_coeffs, z);
+    let w_quotient = kzg_quotient(&w_coeffs, z);
+    let opening_v = (
+        inner_product_g2::<E>(&srs.h_a, &v_quotient).to_affine(),
+        inner_product_g2::<E>(&srs.h_b, &v_quotient).to_affine(),
+    );
+    let opening_w = (
+        inner_product::<E>(&srs.g_a, &w_quotient).to_affine(),
+        inner_product::<E>(&srs.g_b, &w_quotient).to_affine(),
+    );
+
+    Ok(AggregateProof {
+        com_ab,
+        com_c,
+        z_ab,
+        z_c,
+        rounds,
+        final_a: a[0],
+        final_b: b[0],
+        final_c: c[0],
+        final_v,
+        final_w,
+        opening_v,
+        opening_w,
+    })
+}
+
+/// Verifies an aggregate of `public_inputs.len()` proofs, the `i`-th of
+/// which has the public inputs `public_inputs[i]`.
+pub fn verify_aggregate_proof<E: MultiMillerLoop<Gt = Gt>>(
+    srs_vk: &AggregationVerifierKey<E>,
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &AggregateProof<E>,
+    public_inputs: &[Vec<E::Fr>],
+) -> Result<(), VerificationError> {
+    if public_inputs
+        .iter()
+        .any(|inputs| inputs.len() + 1 != pvk.ic.len())
+    {
+        return Err(VerificationError::InvalidVerifyingKey);
+    }
+    let last = public_inputs
+        .last()
+        .ok_or(VerificationError::InvalidProof)?;
+    let n = public_inputs.len().next_power_of_two();
+    if proof.rounds.len() != n.trailing_zeros() as usize {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    let mut transcript = Transcript::new();
+    append_statement(&mut transcript, pvk, public_inputs);
+    append_pair(&mut transcript, &proof.com_ab);
+    append_pair(&mut transcript, &proof.com_c);
+    let r: E::Fr = transcript.challenge();
+    let r_inv = r.invert().unwrap();
+    transcript.append_gt(&proof.z_ab);
+    transcript.append_point(&proof.z_c);
+
+    // The random linear combination of the Groth16 equations:
+    // z_ab = e(alpha, beta)^(sum r^i) * e(sum r^i acc_i, gamma) * e(z_c, delta)
+    let r_powers = powers(r, n);
+    let r_sum = r_powers.iter().fold(E::Fr::zero(), |acc, r| acc + r);
+    let padded = public_inputs
+        .iter()
+        .chain((public_inputs.len()..n).map(|_| last));
+    let mut weights = vec![E::Fr::zero(); last.len()];
+    for (inputs, r_i) in padded.zip(&r_powers) {
+        for (weight, input) in weights.iter_mut().zip(inputs) {
+            *weight += *input * r_i;
+        }
+    }
+    let acc = pvk.ic[0] * r_sum + inner_product::<E>(&pvk.ic[1..], &weights);
+    let combined = E::multi_miller_loop(&[
+        (&acc.to_affine(), &pvk.neg_gamma_g2),
+        (&proof.z_c, &pvk.neg_delta_g2),
+    ])
+    .final_exponentiation();
+    if combined + proof.z_ab != scale_gt::<E>(pvk.alpha_g1_beta_g2, r_sum) {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    let mut com_ab = proof.com_ab;
+    let mut com_c = proof.com_c;
+    let mut z_ab = proof.z_ab;
+    let mut z_c = proof.z_c.to_curve();
+    let mut challenges = Vec::with_capacity(proof.rounds.len());
+    for round in &proof.rounds {
+        append_round(&mut transcript, round);
+        let x: E::Fr = transcript.challenge();
+        let x_inv = x.invert().unwrap();
+
+        com_ab = fold_pair::<E>(com_ab, round.com_ab_l, round.com_ab_r, x, x_inv);
+        com_c = fold_pair::<E>(com_c, round.com_c_l, round.com_c_r, x, x_inv);
+        z_ab = z_ab + scale_gt::<E>(round.z_ab_l, x) + scale_gt::<E>(round.z_ab_r, x_inv);
+        z_c = z_c + round.z_c_l * x + round.z_c_r * x_inv;
+        challenges.push(x);
+    }
+
+    let (a, b, c) = (proof.final_a, proof.final_b, proof.final_c);
+    append_final::<E>(&mut transcript, (a, b, c), proof.final_v, proof.final_w);
+    let z: E::Fr = transcript.challenge();
+
+    let (v1, v2) = proof.final_v;
+    let (w1, w2) = proof.final_w;
+    // The scalars r^i fold like the G2 keys.
+    let s = evaluate_folding_polynomial(&v_factors(&challenges), r, n);
+    let gipa_ok = com_ab.0 == E::pairing(&a, &v1) + E::pairing(&w1, &b)
+        && com_ab.1 == E::pairing(&a, &v2) + E::pairing(&w2, &b)
+        && com_c.0 == E::pairing(&c, &v1)
+        && com_c.1 == E::pairing(&c, &v2)
+        && z_ab == E::pairing(&a, &b)
+        && z_c == c * s;
+    if !gipa_ok {
+        return Err(VerificationError::InvalidProof);
+    }
+
+    let (v_at_z, w_at_z) = evaluate_key_polynomials(&challenges, r_inv, n, z);
+    let g = srs_vk.g;
+    let h = srs_vk.h;
+    let g_z = (g * z).to_affine();
+    let h_z = (h * z).to_affine();
+    let open_v = |key: E::G2Affine, power: E::G1Affine, opening: E::G2Affine| {
+        E::pairing(&g, &(key.to_curve() - h * v_at_z).to_affine())
+            == E::pairing(&(power.to_curve() - g_z).to_affine(), &opening)
+    };
+    let open_w = |key: E::G1Affine, power: E::G2Affine, opening: E::G1Affine| {
+        E::pairing(&(key.to_curve() - g * w_at_z).to_affine(), &h)
+            == E::pairing(&opening, &(power.to_curve() - h_z).to_affine())
+    };
+    if open_v(v1, srs_vk.g_a, proof.opening_v.0)
+        && open_v(v2, srs_vk.g_b, proof.opening_v.1)
+        && open_w(w1, srs_vk.h_a, proof.opening_w.0)
+        && open_w(w2, srs_vk.h_b, proof.opening_w.1)
+    {
+        Ok(())
+    } else {
+        Err(VerificationError::InvalidProof)
+    }
+}
+
+fn pairing_product<E: MultiMillerLoop>(g1: &[E::G1Affine], g2: &[E::G2Affine]) -> E::Gt {
+    let prepared: Vec<E::G2Prepared> = g2.iter().map(|p| (*p).into()).collect();
+    let terms: Vec<_> = g1.iter().zip(prepared.iter()).collect();
+    E::multi_miller_loop(&terms).final_exponentiation()
+}
+
+fn inner_product<E: Engine>(bases: &[E::G1Affine], scalars: &[E::Fr]) -> E::G1 {
+    bases
+        .iter()
+        .zip(scalars)
+        .fold(E::G1::identity(), |acc, (base, scalar)| {
+            acc + *base * *scalar
+        })
+}
+
+fn inner_product_g2<E: Engine>(bases: &[E::G2Affine], scalars: &[E::Fr]) -> E::G2 {
+    bases
+        .iter()
+        .zip(scalars)
+        .fold(E::G2::identity(), |acc, (base, scalar)| {
+            acc + *base * *scalar
+        })
+}
+
+fn powers<S: Field>(x: S, n: usize) -> Vec<S> {
+    let mut powers = Vec::with_capacity(n);
+    let mut cur = S::one();
+    for _ in 0..n {
+        powers.push(cur);
+        cur *= x;
+    }
+    powers
+}
+
+fn scale_g1<E: Engine>(points: &[E::G1Affine], scalars: &[E::Fr]) -> Vec<E::G1Affine> {
+    let proj: Vec<E::G1> = points.iter().zip(scalars).map(|(p, s)| *p * *s).collect();
+    let mut affine = vec![E::G1Affine::identity(); proj.len()];
+    E::G1::batch_normalize(&proj, &mut affine);
+    affine
+}
+
+fn scale_g2<E: Engine>(points: &[E::G2Affine], scalars: &[E::Fr]) -> Vec<E::G2Affine> {
+    let proj: Vec<E::G2> = points.iter().zip(scalars).map(|(p, s)| *p * *s).collect();
+    let mut affine = vec![E::G2Affine::identity(); proj.len()];
+    E::G2::batch_normalize(&proj, &mut affine);
+    affine
+}
+
+/// Returns `left + x * right` for the two halves of `points`.
+fn fold_g1<E: Engine>(points: &[E::G1Affine], x: E::Fr) -> Vec<E::G1Affine> {
+    let (left, right) = points.split_at(points.len() / 2);
+    let proj: Vec<E::G1> = left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| *r * x + l.to_curve())
+        .collect();
+    let mut affine = vec![E::G1Affine::identity(); proj.len()];
+    E::G1::batch_normalize(&proj, &mut affine);
+    affine
+}
+
+/// Returns `left + x * right` for the two halves of `points`.
+fn fold_g2<E: Engine>(points: &[E::G2Affine], x: E::Fr) -> Vec<E::G2Affine> {
+    let (left, right) = points.split_at(points.len() / 2);
+    let proj: Vec<E::G2> = left
+        .iter()
+        .zip(right)
+        .map(|(l, r)| *r * x + l.to_curve())
+        .collect();
+    let mut affine = vec![E::G2Affine::identity(); proj.len()];
+    E::G2::batch_normalize(&proj, &mut affine);
+    affine
+}
+
+/// `g * s`, spelled through `E::Gt` so the scalar multiplication of
+/// `Group<Scalar = E::Fr>` applies where `E::Gt` is pinned to `Gt`.
+fn scale_gt<E: Engine>(g: E::Gt, s: E::Fr) -> E::Gt {
+    g * s
+}
+
+fn fold_pair<E: Engine>(
+    cur: (E::Gt, E::Gt),
+    left: (E::Gt, E::Gt),
+    right: (E::Gt, E::Gt),
+    x: E::Fr,
+    x_inv: E::Fr,
+) -> (E::Gt, E::Gt) {
+    (
+        cur.0 + left.0 * x + right.0 * x_inv,
+        cur.1 + left.1 * x + right.1 * x_inv,
+    )
+}
+
+/// Coefficients of `prod_j (1 + c_j X^(n / 2^(j + 1)))`, where `c_j` is the
+/// factor of round `j`.
+fn folding_polynomial<S: PrimeField>(factors: &[S], n: usize) -> Vec<S> {
+    let mut coeffs = vec![S::one(); n];
+    for (j, factor) in factors.iter().enumerate() {
+        let degree = n >> (j + 1);
+        for (k, coeff) in coeffs.iter_mut().enumerate() {
+            if k & degree != 0 {
+                *coeff *= factor;
+            }
+        }
+    }
+    coeffs
+}
+
+fn evaluate_folding_polynomial<S: PrimeField>(factors: &[S], z: S, n: usize) -> S {
+    factors
+        .iter()
+        .enumerate()
+        .fold(S::one(), |acc, (j, factor)| {
+            let degree = (n >> (j + 1)) as u64;
+            acc * (S::one() + *factor * z.pow_vartime(&[degree]))
+        })
+}
+
+/// The G2 keys fold to `h^f_v(a)` and the G1 keys to `g^f_w(a)`, with
+///
+/// - `f_v(X) = prod_j (1 + x_j^-1 X^d_j)`
+/// - `f_w(X) = X^n prod_j (1 + x_j r^-d_j X^d_j)`
+///
+/// where `d_j = n / 2^(j + 1)`.
+fn w_factors<S: PrimeField>(challenges: &[S], r_inv: S, n: usize) -> Vec<S> {
+    challenges
+        .iter()
+        .enumerate()
+        .map(|(j, x)| *x * r_inv.pow_vartime(&[(n >> (j + 1)) as u64]))
+        .collect()
+}
+
+fn v_factors<S: PrimeField>(challenges: &[S]) -> Vec<S> {
+    challenges.iter().map(|x| x.invert().unwrap()).collect()
+}
+
+fn key_polynomials<S: PrimeField>(challenges: &[S], r_inv: S, n: usize) -> (Vec<S>, Vec<S>) {
+    let v = folding_polynomial(&v_factors(challenges), n);
+    let mut w = vec![S::zero(); n];
+    w.extend(folding_polynomial(&w_factors(challenges, r_inv, n), n));
+    (v, w)
+}
+
+fn evaluate_key_polynomials<S: PrimeField>(challenges: &[S], r_inv: S, n: usize, z: S) -> (S, S) {
+    let v = evaluate_folding_polynomial(&v_factors(challenges), z, n);
+    let w = z.pow_vartime(&[n as u64])
+        * evaluate_folding_polynomial(&w_factors(challenges, r_inv, n), z, n);
+    (v, w)
+}
+
+/// Returns the coefficients of `(f(X) - f(z)) / (X - z)`.
+fn kzg_quotient<S: PrimeField>(coeffs: &[S], z: S) -> Vec<S> {
+    let mut quotient = vec![S::zero(); coeffs.len().saturating_sub(1)];
+    let mut acc = S::zero();
+    for i in (1..coeffs.len()).rev() {
+        acc = acc * z + coeffs[i];
+        quotient[i - 1] = acc;
+    }
+    quotient
+}
+
+/// Absorbs the verifying key and the public inputs of every proof, which the
+/// random linear combination of the Groth16 equations depends on.
+fn append_statement<E: MultiMillerLoop<Gt = Gt>>(
+    transcript: &mut Transcript,
+    pvk: &PreparedVerifyingKey<E>,
+    public_inputs: &[Vec<E::Fr>],
+) {
+    transcript.append_gt(&pvk.alpha_g1_beta_g2);
+    transcript.append_point(&pvk.gamma_g2);
+    transcript.append_point(&pvk.delta_g2);
+    transcript.append_bytes(&(pvk.ic.len() as u64).to_le_bytes());
+    for ic in &pvk.ic {
+        transcript.append_point(ic);
+    }
+    transcript.append_bytes(&(public_inputs.len() as u64).to_le_bytes());
+    for input in public_inputs.iter().flatten() {
+        transcript.append_scalar(input);
+    }
+}
+
+fn append_pair(transcript: &mut Transcript, pair: &(Gt, Gt)) {
+    transcript.append_gt(&pair.0);
+    transcript.append_gt(&pair.1);
+}
+
+fn append_round<E: Engine<Gt = Gt>>(transcript: &mut Transcript, round: &GipaRound<E>) {
+    append_pair(transcript, &round.com_ab_l);
+    append_pair(transcript, &round.com_ab_r);
+    transcript.append_gt(&round.z_ab_l);
+    transcript.append_gt(&round.z_ab_r);
+    append_pair(transcript, &round.com_c_l);
+    append_pair(transcript, &round.com_c_r);
+    transcript.append_point(&round.z_c_l);
+    transcript.append_point(&round.z_c_r);
+}
+
+fn append_final<E: Engine>(
+    transcript: &mut Transcript,
+    (a, b, c): (E::G1Affine, E::G2Affine, E::G1Affine),
+    v: (E::G2Affine, E::G2Affine),
+    w: (E::G1Affine, E::G1Affine),
+) {
+    transcript.append_point(&a);
+    transcript.append_point(&b);
+    transcript.append_point(&c);
+    transcript.append_point(&v.0);
+    transcript.append_point(&v.1);
+    transcript.append_point(&w.0);
+    transcript.append_point(&w.1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::mpc::{
+        initial_common_paramters, mpc_common_paramters_generator, verify_common_paramter,
+        CommonParamterInStorage,
+    };
+    use crate::groth16::tests::MulCircuit;
+    use crate::groth16::{create_proof, generate_random_parameters, prepare_verifying_key};
+
+    use bls12_381::{Bls12, Scalar};
+    use rand::thread_rng;
+
+    fn ceremony(secret: (u64, u64, u64)) -> CommonParamterInStorage<Bls12> {
+        let initial = initial_common_paramters::<Bls12>(16);
+        let contribution = mpc_common_paramters_generator(&initial, secret);
+        verify_common_paramter(&initial, &contribution)
+    }
+
+    #[test]
+    fn aggregates_proofs() {
+        let mut rng = thread_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let srs =
+            AggregationSrs::from_ceremonies(&ceremony((2, 3, 5)), &ceremony((7, 11, 13))).unwrap();
+        assert_eq!(srs.max_proofs(), 8);
+        let srs_vk = srs.verifier_key();
+
+        let mut proofs = vec![];
+        let mut inputs = vec![];
+        for _ in 0..5 {
+            let a = Scalar::random(&mut rng);
+            let b = Scalar::random(&mut rng);
+            let circuit = MulCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            let r = Scalar::random(&mut rng);
+            let s = Scalar::random(&mut rng);
+            proofs.push(create_proof(circuit, &params, r, s).unwrap());
+            inputs.push(vec![a * b]);
+        }
+
+        let aggregate = aggregate_proofs(&srs, &pvk, &proofs, &inputs).unwrap();
+        assert_eq!(aggregate.rounds.len(), 3);
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &aggregate, &inputs).is_ok());
+
+        // A single proof needs no rounds.
+        let single = aggregate_proofs(&srs, &pvk, &proofs[..1], &inputs[..1]).unwrap();
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &single, &inputs[..1]).is_ok());
+
+        let mut wrong_inputs = inputs.clone();
+        wrong_inputs.swap(0, 1);
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &aggregate, &wrong_inputs).is_err());
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &aggregate, &inputs[..4]).is_err());
+
+        let mut tampered = aggregate.clone();
+        tampered.final_c = tampered.final_a;
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &tampered, &inputs).is_err());
+
+        let mut tampered = aggregate.clone();
+        tampered.opening_w.0 = tampered.opening_w.1;
+        assert!(verify_aggregate_proof(&srs_vk, &pvk, &tampered, &inputs).is_err());
+
+        // The transcript binds the verifying key.
+        let mut mixed_pvk = prepare_verifying_key(&params.vk);
+        mixed_pvk.gamma_g2 = -mixed_pvk.gamma_g2;
+        assert!(verify_aggregate_proof(&srs_vk, &mixed_pvk, &aggregate, &inputs).is_err());
+
+        assert_eq!(
+            aggregate_proofs(&srs, &pvk, &proofs, &inputs[..4]).unwrap_err(),
+            AggregationError::MismatchedInputs
+        );
+        let too_many = vec![proofs[0].clone(); 9];
+        assert_eq!(
+            aggregate_proofs(&srs, &pvk, &too_many, &vec![inputs[0].clone(); 9]).unwrap_err(),
+            AggregationError::SrsTooSmall
+        );
+        assert_eq!(
+            aggregate_proofs::<Bls12>(&srs, &pvk, &[], &[]).unwrap_err(),
+            AggregationError::NoProofs
+        );
+    }
+
+    #[test]
+    fn rejects_identical_ceremonies() {
+        let state = ceremony((2, 3, 5));
+        assert_eq!(
+            AggregationSrs::<Bls12>::from_ceremonies(&state, &state).unwrap_err(),
+            AggregationError::MalformedSrs
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! The structured reference string for proof aggregation.
+
+use group::{prime::PrimeCurveAffine, Curve, WnafGroup};
+use pairing::Engine;
+
+use super::AggregationError;
+use crate::groth16::mpc::CommonParamterInStorage;
+
+/// Powers of two independent secrets `a` and `b` in both groups, used as
+/// commitment keys and for the KZG openings of an aggregate proof.
+///
+/// Aggregating `n` proofs (rounded up to a power of two) needs `2n` powers in
+/// G1 and `n` powers in G2 of each secret.
+#[derive(Clone, Debug)]
+pub struct AggregationSrs<E: Engine> {
+    pub(super) g_a: Vec<E::G1Affine>,
+    pub(super) h_a: Vec<E::G2Affine>,
+    pub(super) g_b: Vec<E::G1Affine>,
+    pub(super) h_b: Vec<E::G2Affine>,
+}
+
+/// The part of an [`AggregationSrs`] needed to verify aggregate proofs.
+#[derive(Clone, Debug)]
+pub struct AggregationVerifierKey<E: Engine> {
+    pub g: E::G1Affine,
+    pub h: E::G2Affine,
+    pub g_a: E::G1Affine,
+    pub h_a: E::G2Affine,
+    pub g_b: E::G1Affine,
+    pub h_b: E::G2Affine,
+}
+
+impl<E: Engine> AggregationSrs<E> {
+    /// Builds an SRS from the powers `g^(a^i)`, `h^(a^i)`, `g^(b^i)` and
+    /// `h^(b^i)` of two independent secrets.
+    ///
+    /// The powers are assumed to be well-formed, e.g. because they come out
+    /// of a verified ceremony; only their first elements are checked.
+    pub fn from_powers(
+        g_a: Vec<E::G1Affine>,
+        h_a: Vec<E::G2Affine>,
+        g_b: Vec<E::G1Affine>,
+        h_b: Vec<E::G2Affine>,
+    ) -> Result<Self, AggregationError> {
+        let g = E::G1Affine::generator();
+        let h = E::G2Affine::generator();
+        if g_a.len() < 2 || h_a.len() < 2 || g_a.len() != g_b.len() || h_a.len() != h_b.len() {
+            return Err(AggregationError::MalformedSrs);
+        }
+        if g_a[0] != g || g_b[0] != g || h_a[0] != h || h_b[0] != h {
+            return Err(AggregationError::MalformedSrs);
+        }
+        // The commitments are only binding if the secrets differ.
+        if g_a[1] == g_b[1] || h_a[1] == h_b[1] {
+            return Err(AggregationError::MalformedSrs);
+        }
+
+        Ok(AggregationSrs { g_a, h_a, g_b, h_b })
+    }
+
+    /// Builds an SRS from the final states of two independent common
+    /// ceremonies, using the tau powers of `first` as the powers of `a` and
+    /// those of `second` as the powers of `b`.
+    ///
+    /// Both ceremonies must have been run with independent contributions:
+    /// anyone knowing the ratio of their secrets can forge aggregates.
+    pub fn from_ceremonies(
+        first: &CommonParamterInStorage<E>,
+        second: &CommonParamterInStorage<E>,
+    ) -> Result<Self, AggregationError>
+    where
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+    {
+        let g1_affine = |powers: &[E::G1]| {
+            let mut affine = vec![E::G1Affine::identity(); powers.len()];
+            E::G1::batch_normalize(powers, &mut affine);
+            affine
+        };
+        let g2_affine = |powers: &[E::G2]| {
+            let mut affine = vec![E::G2Affine::identity(); powers.len()];
+            E::G2::batch_normalize(powers, &mut affine);
+            affine
+        };
+
+        Self::from_powers(
+            g1_affine(&first.tau_g1),
+            g2_affine(&first.tau_g2),
+            g1_affine(&second.tau_g1),
+            g2_affine(&second.tau_g2),
+        )
+    }
+
+    /// Returns the largest number of proofs this SRS can aggregate.
+    pub fn max_proofs(&self) -> usize {
+        let n = (self.g_a.len() / 2).min(self.h_a.len());
+        if n == 0 {
+            0
+        } else {
+            // usize::BITS needs a newer toolchain than the pinned one.
+            let bits = (std::mem::size_of::<usize>() * 8) as u32;
+            1 << (bits - 1 - n.leading_zeros())
+        }
+    }
+
+    pub fn verifier_key(&self) -> AggregationVerifierKey<E> {
+        AggregationVerifierKey {
+            g: self.g_a[0],
+            h: self.h_a[0],
+            g_a: self.g_a[1],
+            h_a: self.h_a[1],
+            g_b: self.g_b[1],
+            h_b: self.h_b[1],
+        }
+    }
+}
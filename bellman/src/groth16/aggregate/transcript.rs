@@ -0,0 +1,70 @@
+//! Fiat-Shamir transcript for aggregate proofs.
+
+use std::io::Write;
+
+use blake2s_simd::{Params as Blake2sParams, State};
+use ff::PrimeField;
+use group::UncompressedEncoding;
+
+pub(super) struct Transcript {
+    state: State,
+}
+
+impl Transcript {
+    pub(super) fn new() -> Self {
+        Transcript {
+            state: Blake2sParams::new()
+                .hash_length(32)
+                .personal(b"G16_aggr")
+                .to_state(),
+        }
+    }
+
+    pub(super) fn append_bytes(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    pub(super) fn append_point<G: UncompressedEncoding>(&mut self, point: &G) {
+        self.state.update(point.to_uncompressed().as_ref());
+    }
+
+    pub(super) fn append_scalar<S: PrimeField>(&mut self, scalar: &S) {
+        self.state.update(scalar.to_repr().as_ref());
+    }
+
+    /// Absorbs `gt` through its `Debug` output, the only way `bls12_381`
+    /// exposes the coefficients of a `Gt`. It prints every coefficient as
+    /// its canonical big-endian hex, so distinct elements are absorbed
+    /// differently.
+    pub(super) fn append_gt(&mut self, gt: &bls12_381::Gt) {
+        write!(self.state, "{:?}", gt).expect("hash states accept any input");
+    }
+
+    /// Derives a non-zero challenge from everything absorbed so far, and
+    /// absorbs it.
+    pub(super) fn challenge<S: PrimeField>(&mut self) -> S {
+        let mut counter = 0u32;
+        loop {
+            let digest = self.state.clone().update(&counter.to_le_bytes()).finalize();
+            counter += 1;
+
+            let mut repr = S::Repr::default();
+            for (dst, src) in repr
+                .as_mut()
+                .iter_mut()
+                .zip(digest.as_bytes().iter().cycle())
+            {
+                *dst = *src;
+            }
+
+            let challenge = S::from_repr(repr);
+            if challenge.is_some().into() {
+                let challenge = challenge.unwrap();
+                if !challenge.is_zero_vartime() {
+                    self.state.update(digest.as_bytes());
+                    return challenge;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Import and export of circom's binary `.r1cs` constraint system and `.wtns`
+//! witness formats, and of the `.sym` files naming their wires.
+//!
+//! Wires are numbered like circom does: wire 0 is the "one" input, followed by
+//! the remaining inputs and then the auxiliary variables. bellman doesn't tell
+//! public outputs from public inputs, so all inputs are exported as public
+//! inputs and no wire is marked as a private input.
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::{PrimeField, PrimeFieldBits};
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Write};
+
+use super::{R1cs, Witness};
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+const R1CS_VERSION: u32 = 1;
+const R1CS_HEADER: u32 = 1;
+const R1CS_CONSTRAINTS: u32 = 2;
+const R1CS_WIRE_TO_LABEL: u32 = 3;
+
+const WTNS_MAGIC: &[u8; 4] = b"wtns";
+const WTNS_VERSION: u32 = 2;
+const WTNS_HEADER: u32 = 1;
+const WTNS_VALUES: u32 = 2;
+
+/// Writes `r1cs` in circom's `.r1cs` format, using every wire's index as its
+/// label id.
+pub fn write_r1cs<S: PrimeFieldBits, W: Write>(r1cs: &R1cs<S>, mut writer: W) -> io::Result<()> {
+    let n8 = field_size::<S>();
+    let num_wires = r1cs.num_inputs() + r1cs.num_aux();
+    let wire = |var: &Variable| match var.get_unchecked() {
+        Index::Input(i) => i,
+        Index::Aux(i) => r1cs.num_inputs() + i,
+    };
+
+    writer.write_all(R1CS_MAGIC)?;
+    writer.write_u32::<LittleEndian>(R1CS_VERSION)?;
+    writer.write_u32::<LittleEndian>(3)?;
+
+    writer.write_u32::<LittleEndian>(R1CS_HEADER)?;
+    writer.write_u64::<LittleEndian>((4 + n8 + 4 * 4 + 8 + 4) as u64)?;
+    writer.write_u32::<LittleEndian>(n8 as u32)?;
+    writer.write_all(&modulus_bytes::<S>())?;
+    writer.write_u32::<LittleEndian>(num_wires as u32)?;
+    // Public outputs, public inputs and private inputs.
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_u32::<LittleEndian>(r1cs.num_inputs() as u32 - 1)?;
+    writer.write_u32::<LittleEndian>(0)?;
+    writer.write_u64::<LittleEndian>(num_wires as u64)?;
+    writer.write_u32::<LittleEndian>(r1cs.constraints().len() as u32)?;
+
+    let constraints_len: usize = r1cs
+        .constraints()
+        .iter()
+        .flat_map(|(a, b, c)| vec![a, b, c])
+        .map(|lc| 4 + lc.as_ref().len() * (4 + n8))
+        .sum();
+    writer.write_u32::<LittleEndian>(R1CS_CONSTRAINTS)?;
+    writer.write_u64::<LittleEndian>(constraints_len as u64)?;
+    for (a, b, c) in r1cs.constraints() {
+        for lc in [a, b, c].iter() {
+            writer.write_u32::<LittleEndian>(lc.as_ref().len() as u32)?;
+            for (var, coeff) in lc.as_ref() {
+                writer.write_u32::<LittleEndian>(wire(var) as u32)?;
+                writer.write_all(&to_bytes(coeff))?;
+            }
+        }
+    }
+
+    writer.write_u32::<LittleEndian>(R1CS_WIRE_TO_LABEL)?;
+    writer.write_u64::<LittleEndian>(8 * num_wires as u64)?;
+    for wire in 0..num_wires {
+        writer.write_u64::<LittleEndian>(wire as u64)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a constraint system in circom's `.r1cs` format. The file's field
+/// prime must be the modulus of `S`.
+pub fn read_r1cs<S: PrimeFieldBits, R: Read>(reader: R) -> io::Result<R1cs<S>> {
+    let sections = read_sections(reader, R1CS_MAGIC, R1CS_VERSION)?;
+
+    let mut header = Cursor::new(section(&sections, R1CS_HEADER)?);
+    let n8 = read_field_header::<S, _>(&mut header)?;
+    let num_wires = header.read_u32::<LittleEndian>()? as usize;
+    let num_pub_out = header.read_u32::<LittleEndian>()? as usize;
+    let num_pub_in = header.read_u32::<LittleEndian>()? as usize;
+    let _num_prv_in = header.read_u32::<LittleEndian>()?;
+    let _num_labels = header.read_u64::<LittleEndian>()?;
+    let num_constraints = header.read_u32::<LittleEndian>()? as usize;
+
+    let num_inputs = 1 + num_pub_out + num_pub_in;
+    if num_inputs > num_wires {
+        return Err(invalid_data("more public signals than wires"));
+    }
+
+    let mut constraints_section = Cursor::new(section(&sections, R1CS_CONSTRAINTS)?);
+    let mut read_lc = || -> io::Result<LinearCombination<S>> {
+        let len = constraints_section.read_u32::<LittleEndian>()?;
+        let mut lc = LinearCombination::zero();
+        for _ in 0..len {
+            let wire = constraints_section.read_u32::<LittleEndian>()? as usize;
+            let var = if wire < num_inputs {
+                Variable(Index::Input(wire))
+            } else if wire < num_wires {
+                Variable(Index::Aux(wire - num_inputs))
+            } else {
+                return Err(invalid_data("invalid wire"));
+            };
+            lc = lc + (read_element(&mut constraints_section, n8)?, var);
+        }
+
+        Ok(lc)
+    };
+
+    // num_constraints is untrusted, so the vector only grows as constraints
+    // are actually read.
+    let mut constraints = vec![];
+    for _ in 0..num_constraints {
+        let a = read_lc()?;
+        let b = read_lc()?;
+        let c = read_lc()?;
+        constraints.push((a, b, c));
+    }
+
+    Ok(R1cs::from_parts(
+        num_inputs,
+        num_wires - num_inputs,
+        constraints,
+    ))
+}
+
+/// Writes `witness` in circom's `.wtns` format.
+pub fn write_wtns<S: PrimeFieldBits, W: Write>(
+    witness: &Witness<S>,
+    mut writer: W,
+) -> io::Result<()> {
+    let n8 = field_size::<S>();
+    let values = witness
+        .input_assignment
+        .iter()
+        .chain(witness.aux_assignment.iter());
+    let num_values = witness.input_assignment.len() + witness.aux_assignment.len();
+
+    writer.write_all(WTNS_MAGIC)?;
+    writer.write_u32::<LittleEndian>(WTNS_VERSION)?;
+    writer.write_u32::<LittleEndian>(2)?;
+
+    writer.write_u32::<LittleEndian>(WTNS_HEADER)?;
+    writer.write_u64::<LittleEndian>((4 + n8 + 4) as u64)?;
+    writer.write_u32::<LittleEndian>(n8 as u32)?;
+    writer.write_all(&modulus_bytes::<S>())?;
+    writer.write_u32::<LittleEndian>(num_values as u32)?;
+
+    writer.write_u32::<LittleEndian>(WTNS_VALUES)?;
+    writer.write_u64::<LittleEndian>((n8 * num_values) as u64)?;
+    for value in values {
+        writer.write_all(&to_bytes(value))?;
+    }
+
+    Ok(())
+}
+
+/// Reads a witness for `r1cs` in circom's `.wtns` format.
+pub fn read_wtns<S: PrimeFieldBits, R: Read>(reader: R, r1cs: &R1cs<S>) -> io::Result<Witness<S>> {
+    let sections = read_sections(reader, WTNS_MAGIC, WTNS_VERSION)?;
+
+    let mut header = Cursor::new(section(&sections, WTNS_HEADER)?);
+    let n8 = read_field_header::<S, _>(&mut header)?;
+    let num_values = header.read_u32::<LittleEndian>()? as usize;
+    if num_values != r1cs.num_inputs() + r1cs.num_aux() {
+        return Err(invalid_data("witness does not match the constraint system"));
+    }
+
+    let mut values = Cursor::new(section(&sections, WTNS_VALUES)?);
+    let mut input_assignment = vec![];
+    for _ in 0..r1cs.num_inputs() {
+        input_assignment.push(read_element(&mut values, n8)?);
+    }
+    let mut aux_assignment = vec![];
+    for _ in 0..r1cs.num_aux() {
+        aux_assignment.push(read_element(&mut values, n8)?);
+    }
+    if input_assignment.first() != Some(&S::one()) {
+        return Err(invalid_data("first wire is not one"));
+    }
+
+    Ok(Witness {
+        input_assignment,
+        aux_assignment,
+    })
+}
+
+struct LabelAssembly {
+    path: Vec<String>,
+    input_labels: Vec<String>,
+    aux_labels: Vec<String>,
+}
+
+impl LabelAssembly {
+    fn label(&self, annotation: String) -> String {
+        self.path
+            .iter()
+            .chain(Some(&annotation))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for LabelAssembly {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let label = self.label(annotation().into());
+        self.aux_labels.push(label);
+
+        Ok(Variable(Index::Aux(self.aux_labels.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let label = self.label(annotation().into());
+        self.input_labels.push(label);
+
+        Ok(Variable(Index::Input(self.input_labels.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.path.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        self.path.pop();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Returns the label of every wire of `circuit`, in wire order: the
+/// annotation of the variable, prefixed by its namespaces and separated by
+/// `/`. Wire 0 is labelled `one`.
+pub fn wire_labels<S, C>(circuit: C) -> Result<Vec<String>, SynthesisError>
+where
+    S: PrimeField,
+    C: Circuit<S>,
+{
+    let mut assembly = LabelAssembly {
+        path: vec![],
+        input_labels: vec![],
+        aux_labels: vec![],
+    };
+
+    ConstraintSystem::<S>::alloc_input(&mut assembly, || "one", || Ok(S::one()))?;
+
+    circuit.synthesize(&mut assembly)?;
+
+    let mut labels = assembly.input_labels;
+    labels.extend(assembly.aux_labels);
+    Ok(labels)
+}
+
+/// Writes `labels`, as returned by [`wire_labels`], in circom's `.sym` format
+/// matching the label ids written by [`write_r1cs`].
+pub fn write_sym<W: Write>(labels: &[String], mut writer: W) -> io::Result<()> {
+    for (wire, label) in labels.iter().enumerate() {
+        writeln!(writer, "{},{},0,{}", wire, wire, label)?;
+    }
+
+    Ok(())
+}
+
+/// A circuit given by a constraint system and, when proving, its witness, e.g.
+/// as read from circom's `.r1cs` and `.wtns` files.
+#[derive(Clone)]
+pub struct CircomCircuit<S: PrimeField> {
+    pub r1cs: R1cs<S>,
+    pub witness: Option<Witness<S>>,
+}
+
+impl<S: PrimeField> Circuit<S> for CircomCircuit<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let witness = self.witness.as_ref();
+        if let Some(witness) = witness {
+            if witness.input_assignment.len() != self.r1cs.num_inputs()
+                || witness.aux_assignment.len() != self.r1cs.num_aux()
+            {
+                return Err(SynthesisError::AssignmentMissing);
+            }
+        }
+
+        let mut inputs = vec![CS::one()];
+        for i in 1..self.r1cs.num_inputs() {
+            inputs.push(cs.alloc_input(
+                || format!("input {}", i),
+                || {
+                    witness
+                        .map(|w| w.input_assignment[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?);
+        }
+        let mut aux = vec![];
+        for i in 0..self.r1cs.num_aux() {
+            aux.push(cs.alloc(
+                || format!("aux {}", i),
+                || {
+                    witness
+                        .map(|w| w.aux_assignment[i])
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?);
+        }
+
+        let map = |lc: &LinearCombination<S>, acc: LinearCombination<S>| {
+            lc.as_ref().iter().fold(acc, |acc, (var, coeff)| {
+                let var = match var.get_unchecked() {
+                    Index::Input(i) => inputs[i],
+                    Index::Aux(i) => aux[i],
+                };
+                acc + (*coeff, var)
+            })
+        };
+        for (i, (a, b, c)) in self.r1cs.constraints().iter().enumerate() {
+            cs.enforce(
+                || format!("constraint {}", i),
+                |lc| map(a, lc),
+                |lc| map(b, lc),
+                |lc| map(c, lc),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// The number of bytes circom uses for an element of `S`: the size of the
+/// modulus, rounded up to 64-bit words.
+fn field_size<S: PrimeField>() -> usize {
+    ((S::NUM_BITS as usize - 1) / 64 + 1) * 8
+}
+
+fn bits_to_bytes(bits: impl Iterator<Item = bool>, n8: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; n8];
+    for (i, bit) in bits.enumerate() {
+        if bit {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes
+}
+
+fn modulus_bytes<S: PrimeFieldBits>() -> Vec<u8> {
+    bits_to_bytes(S::char_le_bits().iter().by_val(), field_size::<S>())
+}
+
+fn to_bytes<S: PrimeFieldBits>(s: &S) -> Vec<u8> {
+    bits_to_bytes(s.to_le_bits().iter().by_val(), field_size::<S>())
+}
+
+fn read_element<S: PrimeFieldBits, R: Read>(reader: &mut R, n8: usize) -> io::Result<S> {
+    let mut bytes = vec![0u8; n8];
+    reader.read_exact(&mut bytes)?;
+
+    let element = bytes.iter().rev().fold(S::zero(), |acc, byte| {
+        acc * S::from(256) + S::from(*byte as u64)
+    });
+    if to_bytes(&element) != bytes {
+        return Err(invalid_data("non-canonical field element"));
+    }
+
+    Ok(element)
+}
+
+/// Reads the field size and prime at the start of a header section, checking
+/// that they describe `S`.
+fn read_field_header<S: PrimeFieldBits, R: Read>(reader: &mut R) -> io::Result<usize> {
+    let n8 = reader.read_u32::<LittleEndian>()? as usize;
+    let mut prime = vec![0u8; n8];
+    reader.read_exact(&mut prime)?;
+    if n8 != field_size::<S>() || prime != modulus_bytes::<S>() {
+        return Err(invalid_data("field prime does not match"));
+    }
+
+    Ok(n8)
+}
+
+/// Reads a circom binary file into its sections, keyed by type.
+fn read_sections<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+    version: u32,
+) -> io::Result<HashMap<u32, Vec<u8>>> {
+    let mut file_magic = [0u8; 4];
+    reader.read_exact(&mut file_magic)?;
+    if &file_magic != magic {
+        return Err(invalid_data("invalid magic number"));
+    }
+    if reader.read_u32::<LittleEndian>()? != version {
+        return Err(invalid_data("unsupported version"));
+    }
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let ty = reader.read_u32::<LittleEndian>()?;
+        let len = reader.read_u64::<LittleEndian>()?;
+        let mut contents = vec![];
+        reader.by_ref().take(len).read_to_end(&mut contents)?;
+        if contents.len() as u64 != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated section",
+            ));
+        }
+        if sections.insert(ty, contents).is_some() {
+            return Err(invalid_data("duplicate section"));
+        }
+    }
+
+    Ok(sections)
+}
+
+fn section(sections: &HashMap<u32, Vec<u8>>, ty: u32) -> io::Result<&[u8]> {
+    sections
+        .get(&ty)
+        .map(|contents| &contents[..])
+        .ok_or_else(|| invalid_data("missing section"))
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::tests::CubeCircuit;
+    use crate::groth16::{
+        create_proof, generate_random_parameters, generate_witness, prepare_verifying_key,
+        verify_proof,
+    };
+
+    use bls12_381::{Bls12, Scalar};
+    use ff::Field;
+    use rand::thread_rng;
+
+    #[test]
+    fn round_trips_and_proves() {
+        let mut rng = thread_rng();
+
+        let r1cs = R1cs::<Scalar>::synthesize(CubeCircuit { x: None }).unwrap();
+        let witness = generate_witness(CubeCircuit {
+            x: Some(Scalar::from(3)),
+        })
+        .unwrap();
+
+        let mut r1cs_bytes = vec![];
+        write_r1cs(&r1cs, &mut r1cs_bytes).unwrap();
+        assert_eq!(&r1cs_bytes[..4], b"r1cs");
+        let mut wtns_bytes = vec![];
+        write_wtns(&witness, &mut wtns_bytes).unwrap();
+        assert_eq!(&wtns_bytes[..4], b"wtns");
+
+        let r1cs = read_r1cs::<Scalar, _>(&r1cs_bytes[..]).unwrap();
+        assert_eq!(r1cs.num_inputs(), 2);
+        assert_eq!(r1cs.num_aux(), 3);
+        let read_witness = read_wtns(&wtns_bytes[..], &r1cs).unwrap();
+        assert_eq!(read_witness, witness);
+        assert!(r1cs.is_satisfied(&read_witness));
+
+        let labels = wire_labels::<Scalar, _>(CubeCircuit { x: None }).unwrap();
+        assert_eq!(labels, vec!["one", "out", "x", "square/x^2", "x^3"]);
+        let mut sym = vec![];
+        write_sym(&labels, &mut sym).unwrap();
+        assert!(String::from_utf8(sym)
+            .unwrap()
+            .contains("3,3,0,square/x^2\n"));
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: None,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some(read_witness),
+        };
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let proof = create_proof(circuit, &params, r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, &[Scalar::from(35)]).is_ok());
+
+        // A file for another field is rejected.
+        let mut wrong_prime = r1cs_bytes.clone();
+        wrong_prime[4 + 4 + 4 + 4 + 8 + 4] ^= 1;
+        assert!(read_r1cs::<Scalar, _>(&wrong_prime[..]).is_err());
+    }
+}
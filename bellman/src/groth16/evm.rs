@@ -0,0 +1,574 @@
+//! Encodings of BLS12-381 Groth16 proofs and verifying keys for the EVM's
+//! [EIP-2537] precompiles, and a Solidity verifier using them.
+//!
+//! EIP-2537 encodes a base field element as 64 big-endian bytes, the top 16
+//! of which are zero, a G1 point as its two coordinates (128 bytes), a G2 point
+//! as the `c0` and `c1` halves of its two coordinates (256 bytes), and the
+//! point at infinity as zeros. Scalars are 32 big-endian bytes.
+//!
+//! [EIP-2537]: https://eips.ethereum.org/EIPS/eip-2537
+
+use std::io::{self, Write};
+
+use bls12_381::{Bls12, G1Affine, G2Affine, Scalar};
+use ff::PrimeField;
+
+use super::{Proof, VerifyingKey};
+
+/// The length of an encoded G1 point.
+pub const G1_LEN: usize = 128;
+/// The length of an encoded G2 point.
+pub const G2_LEN: usize = 256;
+/// The length of an encoded scalar.
+pub const SCALAR_LEN: usize = 32;
+/// The length of an encoded [`Proof`]: `A`, `B`, `C`, `D` and `d_pok`.
+pub const PROOF_LEN: usize = 4 * G1_LEN + G2_LEN;
+
+/// The length of a base field element in the uncompressed encoding of
+/// `bls12_381`.
+const FP_LEN: usize = 48;
+/// The zero padding in front of every base field element.
+const FP_PADDING: usize = 16;
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Copies the padded base field elements of `bytes` into the big-endian
+/// encoding used by `bls12_381`, in the given order.
+fn unpad(bytes: &[u8], order: &[usize], out: &mut [u8]) -> io::Result<()> {
+    for (chunk, &i) in out.chunks_mut(FP_LEN).zip(order) {
+        let element = &bytes[i * (FP_PADDING + FP_LEN)..(i + 1) * (FP_PADDING + FP_LEN)];
+        // The top three bits of a canonical element are always zero, and
+        // would be taken as flags by `bls12_381`.
+        if element[..FP_PADDING].iter().any(|&b| b != 0) || element[FP_PADDING] & 0xe0 != 0 {
+            return Err(invalid("non-canonical base field element"));
+        }
+        chunk.copy_from_slice(&element[FP_PADDING..]);
+    }
+
+    Ok(())
+}
+
+/// Pads the big-endian base field elements of `uncompressed` into `out`, in
+/// the given order.
+fn pad(uncompressed: &[u8], order: &[usize], out: &mut [u8]) {
+    for (chunk, &i) in out.chunks_mut(FP_PADDING + FP_LEN).zip(order) {
+        chunk[FP_PADDING..].copy_from_slice(&uncompressed[i * FP_LEN..(i + 1) * FP_LEN]);
+    }
+}
+
+pub fn encode_g1(point: &G1Affine) -> [u8; G1_LEN] {
+    let mut out = [0; G1_LEN];
+    if !bool::from(point.is_identity()) {
+        pad(&point.to_uncompressed(), &[0, 1], &mut out);
+    }
+    out
+}
+
+/// Decodes a G1 point, checking that it is on the curve and in the
+/// prime-order subgroup.
+pub fn decode_g1(bytes: &[u8]) -> io::Result<G1Affine> {
+    if bytes.len() != G1_LEN {
+        return Err(invalid("invalid G1 length"));
+    }
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(G1Affine::identity());
+    }
+
+    let mut uncompressed = [0; 2 * FP_LEN];
+    unpad(bytes, &[0, 1], &mut uncompressed)?;
+    Option::from(G1Affine::from_uncompressed(&uncompressed)).ok_or_else(|| invalid("invalid G1"))
+}
+
+pub fn encode_g2(point: &G2Affine) -> [u8; G2_LEN] {
+    let mut out = [0; G2_LEN];
+    if !bool::from(point.is_identity()) {
+        // bls12_381 puts c1 before c0.
+        pad(&point.to_uncompressed(), &[1, 0, 3, 2], &mut out);
+    }
+    out
+}
+
+/// Decodes a G2 point, checking that it is on the curve and in the
+/// prime-order subgroup.
+pub fn decode_g2(bytes: &[u8]) -> io::Result<G2Affine> {
+    if bytes.len() != G2_LEN {
+        return Err(invalid("invalid G2 length"));
+    }
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(G2Affine::identity());
+    }
+
+    let mut uncompressed = [0; 4 * FP_LEN];
+    unpad(bytes, &[1, 0, 3, 2], &mut uncompressed)?;
+    Option::from(G2Affine::from_uncompressed(&uncompressed)).ok_or_else(|| invalid("invalid G2"))
+}
+
+pub fn encode_scalar(scalar: &Scalar) -> [u8; SCALAR_LEN] {
+    let mut out = scalar.to_repr();
+    out.reverse();
+    out
+}
+
+/// Decodes a scalar, which must be less than the group order.
+pub fn decode_scalar(bytes: &[u8]) -> io::Result<Scalar> {
+    if bytes.len() != SCALAR_LEN {
+        return Err(invalid("invalid scalar length"));
+    }
+
+    let mut repr = [0; SCALAR_LEN];
+    repr.copy_from_slice(bytes);
+    repr.reverse();
+    Option::from(Scalar::from_repr(repr)).ok_or_else(|| invalid("non-canonical scalar"))
+}
+
+/// Encodes `proof` as `A || B || C || D || d_pok`, the layout expected by the
+/// contract of [`write_solidity_verifier`].
+pub fn encode_proof(proof: &Proof<Bls12>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(PROOF_LEN);
+    out.extend_from_slice(&encode_g1(&proof.a));
+    out.extend_from_slice(&encode_g2(&proof.b));
+    out.extend_from_slice(&encode_g1(&proof.c));
+    out.extend_from_slice(&encode_g1(&proof.d));
+    out.extend_from_slice(&encode_g1(&proof.d_pok));
+    out
+}
+
+/// Decodes a proof encoded by [`encode_proof`]. Like [`Proof::read`], this
+/// rejects proofs whose `A`, `B` or `C` is the point at infinity.
+pub fn decode_proof(bytes: &[u8]) -> io::Result<Proof<Bls12>> {
+    if bytes.len() != PROOF_LEN {
+        return Err(invalid("invalid proof length"));
+    }
+
+    let (a, rest) = bytes.split_at(G1_LEN);
+    let (b, rest) = rest.split_at(G2_LEN);
+    let (c, rest) = rest.split_at(G1_LEN);
+    let (d, d_pok) = rest.split_at(G1_LEN);
+    let proof = Proof::<Bls12> {
+        a: decode_g1(a)?,
+        b: decode_g2(b)?,
+        c: decode_g1(c)?,
+        d: decode_g1(d)?,
+        d_pok: decode_g1(d_pok)?,
+    };
+    if bool::from(proof.a.is_identity() | proof.b.is_identity() | proof.c.is_identity()) {
+        return Err(invalid("point at infinity"));
+    }
+
+    Ok(proof)
+}
+
+pub fn encode_public_inputs(inputs: &[Scalar]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(inputs.len() * SCALAR_LEN);
+    for input in inputs {
+        out.extend_from_slice(&encode_scalar(input));
+    }
+    out
+}
+
+pub fn decode_public_inputs(bytes: &[u8]) -> io::Result<Vec<Scalar>> {
+    if bytes.len() % SCALAR_LEN != 0 {
+        return Err(invalid("invalid public inputs length"));
+    }
+
+    bytes.chunks(SCALAR_LEN).map(decode_scalar).collect()
+}
+
+/// The elements of a [`VerifyingKey`] that the EVM verifier uses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvmVerifyingKey {
+    pub alpha_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    pub gamma_g2: G2Affine,
+    pub delta_g2: G2Affine,
+    /// The point at infinity if the key has no commitment key.
+    pub sigma_g2: G2Affine,
+    pub ic: Vec<G1Affine>,
+}
+
+impl From<&VerifyingKey<Bls12>> for EvmVerifyingKey {
+    fn from(vk: &VerifyingKey<Bls12>) -> Self {
+        EvmVerifyingKey {
+            alpha_g1: vk.alpha_g1,
+            beta_g2: vk.beta_g2,
+            gamma_g2: vk.gamma_g2,
+            delta_g2: vk.delta_g2,
+            sigma_g2: vk.sigma_g2,
+            ic: vk.ic.clone(),
+        }
+    }
+}
+
+/// Encodes `vk` as `alpha_g1 || beta_g2 || gamma_g2 || delta_g2 || sigma_g2 ||
+/// ic[0] || ... || ic[n]`.
+pub fn encode_verifying_key(vk: &VerifyingKey<Bls12>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(G1_LEN + 4 * G2_LEN + vk.ic.len() * G1_LEN);
+    out.extend_from_slice(&encode_g1(&vk.alpha_g1));
+    out.extend_from_slice(&encode_g2(&vk.beta_g2));
+    out.extend_from_slice(&encode_g2(&vk.gamma_g2));
+    out.extend_from_slice(&encode_g2(&vk.delta_g2));
+    out.extend_from_slice(&encode_g2(&vk.sigma_g2));
+    for ic in &vk.ic {
+        out.extend_from_slice(&encode_g1(ic));
+    }
+    out
+}
+
+/// Decodes a verifying key encoded by [`encode_verifying_key`].
+pub fn decode_verifying_key(bytes: &[u8]) -> io::Result<EvmVerifyingKey> {
+    let fixed = G1_LEN + 4 * G2_LEN;
+    if bytes.len() < fixed + G1_LEN || (bytes.len() - fixed) % G1_LEN != 0 {
+        return Err(invalid("invalid verifying key length"));
+    }
+
+    let (alpha, rest) = bytes.split_at(G1_LEN);
+    let (beta, rest) = rest.split_at(G2_LEN);
+    let (gamma, rest) = rest.split_at(G2_LEN);
+    let (delta, rest) = rest.split_at(G2_LEN);
+    let (sigma, ic) = rest.split_at(G2_LEN);
+    Ok(EvmVerifyingKey {
+        alpha_g1: decode_g1(alpha)?,
+        beta_g2: decode_g2(beta)?,
+        gamma_g2: decode_g2(gamma)?,
+        delta_g2: decode_g2(delta)?,
+        sigma_g2: decode_g2(sigma)?,
+        ic: ic.chunks(G1_LEN).map(decode_g1).collect::<Result<_, _>>()?,
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Writes a Solidity contract that verifies proofs under `vk` with the
+/// EIP-2537 precompiles.
+///
+/// The contract's `verifyProof(bytes proof, uint256[] input)` takes a proof
+/// encoded by [`encode_proof`] and the public inputs as integers, and returns
+/// whether the proof is valid. Like [`verify_proof`](super::verify_proof), it
+/// checks the proof of knowledge of the commitment `D` if `vk` has a
+/// commitment key, and otherwise requires `D` and `d_pok` to be the point at
+/// infinity. Which values a commitment opens to still has to be checked with
+/// [`verify_commitment`](super::verify_commitment).
+pub fn write_solidity_verifier<W: Write>(
+    vk: &VerifyingKey<Bls12>,
+    mut writer: W,
+) -> io::Result<()> {
+    let num_inputs =
+        vk.ic.len().checked_sub(1).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "verifying key has no IC")
+        })?;
+    let committed = !vk.commitment_key.is_empty();
+    if committed && bool::from(vk.sigma_g2.is_identity()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "verifying key has a commitment key but no sigma",
+        ));
+    }
+    let ic = vk
+        .ic
+        .iter()
+        .map(|ic| format!("        hex\"{}\"", hex(&encode_g1(ic))))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let d_offset = 2 * G1_LEN + G2_LEN;
+    let pok_offset = d_offset + G1_LEN;
+    let (commitment_constants, commitment_check, d_term) = if committed {
+        (
+            format!(
+                r#"
+    bytes internal constant G2_GENERATOR = hex"{g2}";
+    bytes internal constant NEG_SIGMA_G2 = hex"{neg_sigma}";"#,
+                g2 = hex(&encode_g2(&G2Affine::generator())),
+                neg_sigma = hex(&encode_g2(&-vk.sigma_g2)),
+            ),
+            format!(
+                r#"
+        // e(d_pok, g2) * e(D, -sigma) == 1 proves knowledge of an opening of
+        // D under the commitment key.
+        {{
+            (bool pokOk, bytes memory pokOut) = PAIRING_CHECK.staticcall(
+                abi.encodePacked(
+                    proof[{pok_offset}:{proof_len}],
+                    G2_GENERATOR,
+                    proof[{d_offset}:{pok_offset}],
+                    NEG_SIGMA_G2
+                )
+            );
+            if (!pokOk || pokOut.length != 32 || abi.decode(pokOut, (uint256)) != 1) {{
+                return false;
+            }}
+        }}
+"#,
+                pok_offset = pok_offset,
+                proof_len = PROOF_LEN,
+                d_offset = d_offset,
+            ),
+            format!(
+                r#"
+        assembly {{
+            let dst := add(add(msm, 0x20), mul(add(NUM_INPUTS, 1), 160))
+            calldatacopy(dst, add(proof.offset, {d_offset}), 128)
+            mstore(add(dst, 0x80), 1)
+        }}"#,
+                d_offset = d_offset,
+            ),
+        )
+    } else {
+        (
+            String::new(),
+            format!(
+                r#"
+        // The key has no commitment key, so D and d_pok must be the point at
+        // infinity.
+        for (uint256 i = {d_offset}; i < PROOF_LENGTH; i += 32) {{
+            if (bytes32(proof[i:i + 32]) != bytes32(0)) {{
+                return false;
+            }}
+        }}
+"#,
+                d_offset = d_offset,
+            ),
+            String::new(),
+        )
+    };
+
+    // The equation is checked as
+    // e(A, B) * e(alpha, -beta) * e(acc, -gamma) * e(C, -delta) = 1.
+    write!(
+        writer,
+        r#"// SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.19;
+
+/// @title Groth16 verifier over BLS12-381
+/// @notice Generated by bellman for a verifying key with {num_inputs} public
+/// inputs. Requires the EIP-2537 precompiles.
+contract Groth16Verifier {{
+    /// The order of the BLS12-381 scalar field.
+    uint256 internal constant R =
+        0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001;
+
+    address internal constant G1_MSM = address(0x0c);
+    address internal constant PAIRING_CHECK = address(0x0f);
+
+    uint256 internal constant PROOF_LENGTH = {proof_len};
+    uint256 internal constant NUM_INPUTS = {num_inputs};
+
+    bytes internal constant ALPHA_G1 = hex"{alpha}";
+    bytes internal constant NEG_BETA_G2 = hex"{neg_beta}";
+    bytes internal constant NEG_GAMMA_G2 = hex"{neg_gamma}";
+    bytes internal constant NEG_DELTA_G2 = hex"{neg_delta}";{commitment_constants}
+    /// IC[0] || ... || IC[NUM_INPUTS]
+    bytes internal constant IC =
+{ic};
+
+    /// @param proof A || B || C || D || d_pok, each point padded as in
+    /// EIP-2537.
+    /// @param input The public inputs, each less than R.
+    function verifyProof(bytes calldata proof, uint256[] calldata input)
+        external
+        view
+        returns (bool)
+    {{
+        if (proof.length != PROOF_LENGTH || input.length != NUM_INPUTS) {{
+            return false;
+        }}
+{commitment_check}
+        // acc = IC[0] + sum(IC[i + 1] * input[i]){acc_d}
+        bytes memory ic = IC;
+        bytes memory msm = new bytes((NUM_INPUTS + {msm_extra}) * 160);
+        for (uint256 i = 0; i <= NUM_INPUTS; i++) {{
+            uint256 scalar = 1;
+            if (i > 0) {{
+                scalar = input[i - 1];
+                if (scalar >= R) {{
+                    return false;
+                }}
+            }}
+            assembly {{
+                let src := add(add(ic, 0x20), mul(i, 128))
+                let dst := add(add(msm, 0x20), mul(i, 160))
+                mstore(dst, mload(src))
+                mstore(add(dst, 0x20), mload(add(src, 0x20)))
+                mstore(add(dst, 0x40), mload(add(src, 0x40)))
+                mstore(add(dst, 0x60), mload(add(src, 0x60)))
+                mstore(add(dst, 0x80), scalar)
+            }}
+        }}{d_term}
+        (bool ok, bytes memory acc) = G1_MSM.staticcall(msm);
+        if (!ok || acc.length != 128) {{
+            return false;
+        }}
+
+        // e(A, B) * e(alpha, -beta) * e(acc, -gamma) * e(C, -delta) == 1
+        bytes memory pairing = abi.encodePacked(
+            proof[0:{c_offset}],
+            ALPHA_G1,
+            NEG_BETA_G2,
+            acc,
+            NEG_GAMMA_G2,
+            proof[{c_offset}:{d_offset}],
+            NEG_DELTA_G2
+        );
+        bytes memory out;
+        (ok, out) = PAIRING_CHECK.staticcall(pairing);
+        return ok && out.length == 32 && abi.decode(out, (uint256)) == 1;
+    }}
+}}
+"#,
+        num_inputs = num_inputs,
+        proof_len = PROOF_LEN,
+        alpha = hex(&encode_g1(&vk.alpha_g1)),
+        neg_beta = hex(&encode_g2(&-vk.beta_g2)),
+        neg_gamma = hex(&encode_g2(&-vk.gamma_g2)),
+        neg_delta = hex(&encode_g2(&-vk.delta_g2)),
+        commitment_constants = commitment_constants,
+        ic = ic,
+        commitment_check = commitment_check,
+        acc_d = if committed { " + D" } else { "" },
+        msm_extra = if committed { 2 } else { 1 },
+        d_term = d_term,
+        c_offset = G1_LEN + G2_LEN,
+        d_offset = d_offset,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::tests::{CommittedSquare, MulCircuit};
+    use crate::groth16::{
+        create_committed_proof, create_proof, generate_committed_parameters,
+        generate_random_parameters,
+    };
+
+    use bls12_381::{G1Projective, G2Projective};
+    use ff::Field;
+    use group::Group;
+    use rand::thread_rng;
+
+    #[test]
+    fn points_round_trip() {
+        let mut rng = thread_rng();
+        let g1 = G1Affine::from(G1Projective::random(&mut rng));
+        let g2 = G2Affine::from(G2Projective::random(&mut rng));
+
+        let encoded = encode_g1(&g1);
+        assert!(encoded[..FP_PADDING].iter().all(|&b| b == 0));
+        assert_eq!(&encoded[FP_PADDING..64], &g1.to_uncompressed()[..FP_LEN]);
+        assert_eq!(decode_g1(&encoded).unwrap(), g1);
+
+        // c0 comes first.
+        let encoded = encode_g2(&g2);
+        assert_eq!(
+            &encoded[FP_PADDING..64],
+            &g2.to_uncompressed()[FP_LEN..2 * FP_LEN]
+        );
+        assert_eq!(decode_g2(&encoded).unwrap(), g2);
+
+        assert_eq!(encode_g1(&G1Affine::identity()), [0; G1_LEN]);
+        assert_eq!(decode_g1(&[0; G1_LEN]).unwrap(), G1Affine::identity());
+        assert_eq!(decode_g2(&[0; G2_LEN]).unwrap(), G2Affine::identity());
+
+        let mut padded = encode_g1(&g1);
+        padded[0] = 1;
+        assert!(decode_g1(&padded).is_err());
+        let mut off_curve = encode_g1(&g1);
+        off_curve[G1_LEN - 1] ^= 1;
+        assert!(decode_g1(&off_curve).is_err());
+
+        let scalar = Scalar::random(&mut rng);
+        assert_eq!(decode_scalar(&encode_scalar(&scalar)).unwrap(), scalar);
+        assert_eq!(encode_scalar(&Scalar::one())[SCALAR_LEN - 1], 1);
+        assert!(decode_scalar(&[0xff; SCALAR_LEN]).is_err());
+    }
+
+    #[test]
+    fn calldata_round_trip() {
+        let mut rng = thread_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(MulCircuit { a: None, b: None }, &mut rng)
+                .unwrap();
+
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let circuit = MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let proof = create_proof(circuit, &params, r, s).unwrap();
+
+        let encoded = encode_proof(&proof);
+        assert_eq!(encoded.len(), PROOF_LEN);
+        assert_eq!(decode_proof(&encoded).unwrap(), proof);
+        assert!(decode_proof(&encoded[1..]).is_err());
+
+        let inputs = vec![a * b, a];
+        assert_eq!(
+            decode_public_inputs(&encode_public_inputs(&inputs)).unwrap(),
+            inputs
+        );
+
+        let vk = decode_verifying_key(&encode_verifying_key(&params.vk)).unwrap();
+        assert_eq!(vk, EvmVerifyingKey::from(&params.vk));
+        assert_eq!(vk.ic.len(), 2);
+
+        let mut contract = vec![];
+        write_solidity_verifier(&params.vk, &mut contract).unwrap();
+        let contract = String::from_utf8(contract).unwrap();
+        assert!(contract.contains("NUM_INPUTS = 1;"));
+        assert!(contract.contains(&hex(&encode_g2(&-params.vk.delta_g2))));
+        for ic in &params.vk.ic {
+            assert!(contract.contains(&hex(&encode_g1(ic))));
+        }
+        // Without a commitment key, the contract requires D to be zero.
+        assert!(contract.contains("D and d_pok must be the point at"));
+        assert!(!contract.contains("NEG_SIGMA_G2"));
+    }
+
+    #[test]
+    fn committed_verifier() {
+        let mut rng = thread_rng();
+        let mut toxic = || Scalar::random(&mut rng);
+        let params = generate_committed_parameters::<Bls12, _>(
+            CommittedSquare { x: None },
+            G1Projective::generator(),
+            G2Projective::generator(),
+            toxic(),
+            toxic(),
+            toxic(),
+            toxic(),
+            toxic(),
+            toxic(),
+            toxic(),
+        )
+        .unwrap();
+
+        let circuit = CommittedSquare {
+            x: Some(Scalar::from(3)),
+        };
+        let (proof, _) =
+            create_committed_proof(circuit, &params, toxic(), toxic(), toxic()).unwrap();
+        assert!(!bool::from(proof.d_pok.is_identity()));
+        assert_eq!(decode_proof(&encode_proof(&proof)).unwrap(), proof);
+
+        let vk = decode_verifying_key(&encode_verifying_key(&params.vk)).unwrap();
+        assert_eq!(vk.sigma_g2, params.vk.sigma_g2);
+
+        let mut contract = vec![];
+        write_solidity_verifier(&params.vk, &mut contract).unwrap();
+        let contract = String::from_utf8(contract).unwrap();
+        assert!(contract.contains(&hex(&encode_g2(&-params.vk.sigma_g2))));
+        assert!(contract.contains("+ D"));
+        assert!(!contract.contains("D and d_pok must be the point at"));
+
+        let mut broken = params.vk.clone();
+        broken.sigma_g2 = G2Affine::identity();
+        assert!(write_solidity_verifier(&broken, &mut vec![]).is_err());
+    }
+}
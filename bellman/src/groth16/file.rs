@@ -0,0 +1,722 @@
+//! A [`ParameterSource`] that decodes its queries from a parameter file as the
+//! prover asks for them, and an index of parameter files.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use blake2s_simd::Params as Blake2sParams;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use group::{prime::PrimeCurveAffine, UncompressedEncoding};
+use pairing::Engine;
+
+use super::{ParameterSection, ParameterSource, VerifyingKey};
+use crate::multicore::Worker;
+use crate::SynthesisError;
+
+/// The location of a query in a parameter file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SectionLocation {
+    /// Offset of the first element, just after the length prefix.
+    pub offset: u64,
+    /// Number of elements.
+    pub len: usize,
+}
+
+/// Parameters in the layout written by [`Parameters::write`], kept on disk.
+///
+/// Opening the file only reads the verifying key and the length of every
+/// query. The `h`, `l`, `a`, `b_g1` and `b_g2` queries are decoded each time
+/// the prover asks for them and dropped once the proof is done, so several
+/// large parameter sets can be kept around without holding their queries in
+/// memory.
+///
+/// [`Parameters::write`]: super::Parameters::write
+pub struct ParameterFile<E: Engine> {
+    path: PathBuf,
+    checked: bool,
+    vk: VerifyingKey<E>,
+    h: SectionLocation,
+    l: SectionLocation,
+    a: SectionLocation,
+    b_g1: SectionLocation,
+    b_g2: SectionLocation,
+}
+
+impl<E: Engine> ParameterFile<E> {
+    /// Opens the parameter file at `path`.
+    ///
+    /// If `checked` is set, every point is checked to be on the curve and in
+    /// the prime-order subgroup as it is decoded, as in [`Parameters::read`].
+    ///
+    /// [`Parameters::read`]: super::Parameters::read
+    pub fn open<P: AsRef<Path>>(path: P, checked: bool) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = File::open(&path)?;
+        let file_len = file.metadata()?.len();
+
+        let vk = VerifyingKey::<E>::read(&mut file)?;
+
+        let mut locate = |size: usize| -> io::Result<SectionLocation> {
+            let len = file.read_u32::<BigEndian>()? as usize;
+            let offset = file.seek(SeekFrom::Current(0))?;
+            let end = offset + (len * size) as u64;
+            if end > file_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "parameter file is truncated",
+                ));
+            }
+            file.seek(SeekFrom::Start(end))?;
+
+            Ok(SectionLocation { offset, len })
+        };
+
+        let g1_size = encoded_len::<E::G1Affine>();
+        let g2_size = encoded_len::<E::G2Affine>();
+        let h = locate(g1_size)?;
+        let l = locate(g1_size)?;
+        let a = locate(g1_size)?;
+        let b_g1 = locate(g1_size)?;
+        let b_g2 = locate(g2_size)?;
+
+        Ok(ParameterFile {
+            path,
+            checked,
+            vk,
+            h,
+            l,
+            a,
+            b_g1,
+            b_g2,
+        })
+    }
+
+    /// Opens the parameter file at `path` using the section locations and
+    /// verifying key recorded in `index`, without reading the file.
+    ///
+    /// The file is only checked to have the length the index expects, and
+    /// every section to lie within it; use [`ParameterIndex::verify`] to check
+    /// its contents.
+    pub fn open_indexed<P: AsRef<Path>>(
+        path: P,
+        index: &ParameterIndex<E>,
+        checked: bool,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file_len = std::fs::metadata(&path)?.len();
+        if file_len != index.file_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "parameter file does not have the indexed length",
+            ));
+        }
+
+        // A section is decoded into a buffer of its claimed length, so a
+        // corrupted index must not claim more elements than the file holds.
+        let g1_size = encoded_len::<E::G1Affine>() as u64;
+        let g2_size = encoded_len::<E::G2Affine>() as u64;
+        let sizes = [g1_size, g1_size, g1_size, g1_size, g2_size];
+        for (section, &size) in index.sections.iter().zip(sizes.iter()) {
+            let location = section.location;
+            let end = (location.len as u64)
+                .checked_mul(size)
+                .and_then(|len| len.checked_add(location.offset));
+            if end.map_or(true, |end| end > file_len) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "indexed section lies outside the parameter file",
+                ));
+            }
+        }
+
+        let [h, l, a, b_g1, b_g2] = index.sections;
+        Ok(ParameterFile {
+            path,
+            checked,
+            vk: index.vk.clone(),
+            h: h.location,
+            l: l.location,
+            a: a.location,
+            b_g1: b_g1.location,
+            b_g2: b_g2.location,
+        })
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    /// Returns the locations of the `h`, `l`, `a`, `b_g1` and `b_g2` queries.
+    pub fn sections(&self) -> [SectionLocation; 5] {
+        [self.h, self.l, self.a, self.b_g1, self.b_g2]
+    }
+
+    /// Reads and decodes the elements at `location`.
+    ///
+    /// The section is read [`DECODE_BATCH`] elements at a time, and each batch
+    /// is decoded in parallel chunks straight into the result, so only one
+    /// batch of encoded bytes is held besides the decoded points.
+    fn read_section<G>(&self, location: SectionLocation) -> io::Result<Arc<Vec<G>>>
+    where
+        G: PrimeCurveAffine + UncompressedEncoding,
+    {
+        if location.len == 0 {
+            return Ok(Arc::new(vec![]));
+        }
+
+        let size = encoded_len::<G>();
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(location.offset))?;
+
+        let worker = Worker::new();
+        let mut bytes = vec![0u8; location.len.min(DECODE_BATCH) * size];
+        let mut points = vec![G::identity(); location.len];
+        for points in points.chunks_mut(DECODE_BATCH) {
+            let bytes = &mut bytes[..points.len() * size];
+            file.read_exact(bytes)?;
+            decode_batch(&worker, points, bytes, self.checked)?;
+        }
+
+        Ok(Arc::new(points))
+    }
+}
+
+/// Number of elements [`ParameterFile`] reads from disk at once.
+const DECODE_BATCH: usize = 1 << 16;
+
+/// Decodes `bytes` into `points`, in parallel chunks.
+fn decode_batch<G>(worker: &Worker, points: &mut [G], bytes: &[u8], checked: bool) -> io::Result<()>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+{
+    let size = encoded_len::<G>();
+    let error = Mutex::new(None);
+    worker.scope(points.len(), |scope, chunk| {
+        for (points, bytes) in points.chunks_mut(chunk).zip(bytes.chunks(chunk * size)) {
+            let error = &error;
+            scope.spawn(move |_scope| {
+                for (point, bytes) in points.iter_mut().zip(bytes.chunks(size)) {
+                    match decode::<G>(bytes, checked) {
+                        Ok(p) => *point = p,
+                        Err(e) => {
+                            *error.lock().unwrap() = Some(e);
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    match error.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// A reader that counts the bytes read through it.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+const INDEX_MAGIC: &[u8; 8] = b"G16INDX2";
+
+/// The queries of a parameter file, in file order.
+const INDEXED_SECTIONS: [ParameterSection; 5] = [
+    ParameterSection::H,
+    ParameterSection::L,
+    ParameterSection::A,
+    ParameterSection::BG1,
+    ParameterSection::BG2,
+];
+
+/// A query of a parameter file, as recorded in a [`ParameterIndex`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexedSection {
+    pub location: SectionLocation,
+    /// BLAKE2s digest of the query's encoded elements.
+    pub digest: [u8; 32],
+}
+
+/// An index of a parameter file in the layout written by
+/// [`Parameters::write`].
+///
+/// The index holds a copy of the verifying key and the location and digest
+/// of every query, so that a verifier can load the key, and anyone can check
+/// the file for corruption, without decoding any of the queries. It is
+/// usually written next to the parameter file.
+///
+/// [`Parameters::write`]: super::Parameters::write
+#[derive(Clone)]
+pub struct ParameterIndex<E: Engine> {
+    vk: VerifyingKey<E>,
+    vk_bytes: Vec<u8>,
+    sections: [IndexedSection; 5],
+}
+
+impl<E: Engine> ParameterIndex<E> {
+    /// Indexes the parameter file read from `reader`, hashing every query
+    /// without decoding its elements.
+    pub fn build<R: Read>(mut reader: R) -> io::Result<Self> {
+        // The key may be in an older layout than the one it is written in
+        // below, so the queries start where reading it stopped.
+        let mut counted = CountingReader {
+            inner: &mut reader,
+            count: 0,
+        };
+        let vk = VerifyingKey::<E>::read(&mut counted)?;
+        let mut position = counted.count;
+        let mut vk_bytes = vec![];
+        vk.write(&mut vk_bytes)?;
+
+        let g1_size = encoded_len::<E::G1Affine>();
+        let g2_size = encoded_len::<E::G2Affine>();
+        let mut buf = vec![0u8; 1 << 16];
+        let mut index = |size: usize| -> io::Result<IndexedSection> {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            let offset = position + 4;
+
+            let mut state = Blake2sParams::new()
+                .hash_length(32)
+                .personal(b"G16_sect")
+                .to_state();
+            let mut remaining = len * size;
+            while remaining > 0 {
+                let n = remaining.min(buf.len());
+                reader.read_exact(&mut buf[..n])?;
+                state.update(&buf[..n]);
+                remaining -= n;
+            }
+            position = offset + (len * size) as u64;
+
+            let mut digest = [0u8; 32];
+            digest.copy_from_slice(state.finalize().as_bytes());
+            Ok(IndexedSection {
+                location: SectionLocation { offset, len },
+                digest,
+            })
+        };
+
+        let sections = [
+            index(g1_size)?,
+            index(g1_size)?,
+            index(g1_size)?,
+            index(g1_size)?,
+            index(g2_size)?,
+        ];
+
+        Ok(ParameterIndex {
+            vk,
+            vk_bytes,
+            sections,
+        })
+    }
+
+    pub fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+
+    /// Returns the verifying key as written by [`VerifyingKey::write`].
+    pub fn vk_bytes(&self) -> &[u8] {
+        &self.vk_bytes
+    }
+
+    /// Returns the `h`, `l`, `a`, `b_g1` and `b_g2` queries.
+    pub fn sections(&self) -> &[IndexedSection; 5] {
+        &self.sections
+    }
+
+    /// Returns the length of the indexed parameter file.
+    pub fn file_len(&self) -> u64 {
+        let b_g2 = self.sections[4].location;
+        b_g2.offset
+            .saturating_add((b_g2.len as u64).saturating_mul(encoded_len::<E::G2Affine>() as u64))
+    }
+
+    /// Checks that the parameter file read from `reader` is the one this
+    /// index was built from.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] naming the first section
+    /// that differs.
+    pub fn verify<R: Read>(&self, reader: R) -> io::Result<()> {
+        let mismatch = |what: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} does not match the index", what),
+            )
+        };
+
+        let other = ParameterIndex::<E>::build(reader)?;
+        if other.vk_bytes != self.vk_bytes {
+            return Err(mismatch("verifying key".to_string()));
+        }
+        for (section, (ours, theirs)) in INDEXED_SECTIONS
+            .iter()
+            .zip(self.sections.iter().zip(other.sections.iter()))
+        {
+            if ours != theirs {
+                return Err(mismatch(format!("{:?} query", section)));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut bytes = vec![];
+        bytes.write_all(INDEX_MAGIC)?;
+        bytes.write_u32::<BigEndian>(self.vk_bytes.len() as u32)?;
+        bytes.write_all(&self.vk_bytes)?;
+        for section in &self.sections {
+            bytes.write_u64::<BigEndian>(section.location.offset)?;
+            bytes.write_u32::<BigEndian>(section.location.len as u32)?;
+            bytes.write_all(&section.digest)?;
+        }
+
+        writer.write_all(&bytes)?;
+        writer.write_all(&index_digest(&bytes))
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+        let mut bytes = vec![0u8; INDEX_MAGIC.len() + 4];
+        reader.read_exact(&mut bytes)?;
+        if &bytes[..INDEX_MAGIC.len()] != INDEX_MAGIC {
+            return Err(invalid("not a parameter index"));
+        }
+        let vk_len = (&bytes[INDEX_MAGIC.len()..]).read_u32::<BigEndian>()? as usize;
+
+        // The key, then an offset, length and digest for each section. vk_len
+        // is untrusted, so the buffer only grows as bytes are actually read.
+        let start = bytes.len();
+        let body_len = vk_len + INDEXED_SECTIONS.len() * (8 + 4 + 32);
+        if reader
+            .by_ref()
+            .take(body_len as u64)
+            .read_to_end(&mut bytes)?
+            != body_len
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated parameter index",
+            ));
+        }
+
+        let mut digest = [0u8; 32];
+        reader.read_exact(&mut digest)?;
+        if digest != index_digest(&bytes) {
+            return Err(invalid("parameter index is corrupted"));
+        }
+
+        let mut rest = &bytes[start..];
+        let vk_bytes = rest[..vk_len].to_vec();
+        let vk = VerifyingKey::<E>::read(&vk_bytes[..])?;
+        rest = &rest[vk_len..];
+
+        let mut section = || -> io::Result<IndexedSection> {
+            let offset = rest.read_u64::<BigEndian>()?;
+            let len = rest.read_u32::<BigEndian>()? as usize;
+            let mut digest = [0u8; 32];
+            rest.read_exact(&mut digest)?;
+            Ok(IndexedSection {
+                location: SectionLocation { offset, len },
+                digest,
+            })
+        };
+        let sections = [section()?, section()?, section()?, section()?, section()?];
+
+        Ok(ParameterIndex {
+            vk,
+            vk_bytes,
+            sections,
+        })
+    }
+}
+
+fn index_digest(bytes: &[u8]) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(
+        Blake2sParams::new()
+            .hash_length(32)
+            .personal(b"G16_indx")
+            .hash(bytes)
+            .as_bytes(),
+    );
+    digest
+}
+
+fn encoded_len<G: UncompressedEncoding>() -> usize {
+    G::Uncompressed::default().as_ref().len()
+}
+
+fn decode<G>(bytes: &[u8], checked: bool) -> io::Result<G>
+where
+    G: PrimeCurveAffine + UncompressedEncoding,
+{
+    let mut repr = G::Uncompressed::default();
+    repr.as_mut().copy_from_slice(bytes);
+
+    let affine = if checked {
+        G::from_uncompressed(&repr)
+    } else {
+        G::from_uncompressed_unchecked(&repr)
+    };
+
+    let affine = if affine.is_some().into() {
+        Ok(affine.unwrap())
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "invalid point"))
+    };
+
+    affine.and_then(|e| {
+        if e.is_identity().into() {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "point at infinity",
+            ))
+        } else {
+            Ok(e)
+        }
+    })
+}
+
+impl<'a, E: Engine> ParameterSource<E> for &'a ParameterFile<E> {
+    type G1Builder = (Arc<Vec<E::G1Affine>>, usize);
+    type G2Builder = (Arc<Vec<E::G2Affine>>, usize);
+
+    fn get_vk(&mut self, _: usize) -> Result<VerifyingKey<E>, SynthesisError> {
+        Ok(self.vk.clone())
+    }
+
+    fn get_h(&mut self, _: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok((self.read_section(self.h)?, 0))
+    }
+
+    fn get_l(&mut self, _: usize) -> Result<Self::G1Builder, SynthesisError> {
+        Ok((self.read_section(self.l)?, 0))
+    }
+
+    fn get_a(
+        &mut self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let a = self.read_section(self.a)?;
+        Ok(((a.clone(), 0), (a, num_inputs)))
+    }
+
+    fn get_b_g1(
+        &mut self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G1Builder, Self::G1Builder), SynthesisError> {
+        let b_g1 = self.read_section(self.b_g1)?;
+        Ok(((b_g1.clone(), 0), (b_g1, num_inputs)))
+    }
+
+    fn get_b_g2(
+        &mut self,
+        num_inputs: usize,
+        _: usize,
+    ) -> Result<(Self::G2Builder, Self::G2Builder), SynthesisError> {
+        let b_g2 = self.read_section(self.b_g2)?;
+        Ok(((b_g2.clone(), 0), (b_g2, num_inputs)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::tests::MulCircuit;
+    use crate::groth16::{create_proof, generate_random_parameters, Parameters};
+
+    use bls12_381::{Bls12, Scalar};
+    use ff::Field;
+    use rand::thread_rng;
+    use std::fs;
+
+    fn write_parameters(name: &str) -> (Parameters<Bls12>, Vec<u8>, PathBuf) {
+        let params = generate_random_parameters::<Bls12, _, _>(
+            MulCircuit { a: None, b: None },
+            &mut thread_rng(),
+        )
+        .unwrap();
+
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+        let path = std::env::temp_dir().join(format!("bellman-{}-{}", name, std::process::id()));
+        fs::write(&path, &bytes).unwrap();
+
+        (params, bytes, path)
+    }
+
+    #[test]
+    fn proves_like_in_memory_parameters() {
+        let mut rng = thread_rng();
+        let (params, bytes, path) = write_parameters("parameter-file");
+
+        let file = ParameterFile::<Bls12>::open(&path, true).unwrap();
+        assert!(file.vk() == &params.vk);
+        let lens: Vec<_> = file.sections().iter().map(|s| s.len).collect();
+        assert_eq!(
+            lens,
+            vec![
+                params.h.len(),
+                params.l.len(),
+                params.a.len(),
+                params.b_g1.len(),
+                params.b_g2.len()
+            ]
+        );
+
+        let a = Scalar::random(&mut rng);
+        let b = Scalar::random(&mut rng);
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let circuit = || MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let expected = create_proof::<Bls12, _, _>(circuit(), &params, r, s).unwrap();
+        let proof = create_proof::<Bls12, _, _>(circuit(), &file, r, s).unwrap();
+        assert!(proof == expected);
+
+        // A corrupted point is only noticed once its query is decoded.
+        let mut corrupted = bytes.clone();
+        corrupted[file.sections()[0].offset as usize + 10] ^= 0xff;
+        fs::write(&path, &corrupted).unwrap();
+        let file = ParameterFile::<Bls12>::open(&path, true).unwrap();
+        assert!(create_proof::<Bls12, _, _>(circuit(), &file, r, s).is_err());
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(ParameterFile::<Bls12>::open(&path, true).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn index_checks_parameter_file() {
+        let (params, bytes, path) = write_parameters("parameter-index");
+
+        let index = ParameterIndex::<Bls12>::build(&bytes[..]).unwrap();
+        assert!(index.vk() == &params.vk);
+        assert_eq!(index.file_len(), bytes.len() as u64);
+        let mut vk_bytes = vec![];
+        params.vk.write(&mut vk_bytes).unwrap();
+        assert_eq!(index.vk_bytes(), &vk_bytes[..]);
+
+        let file = ParameterFile::<Bls12>::open(&path, true).unwrap();
+        let locations: Vec<_> = index.sections().iter().map(|s| s.location).collect();
+        assert_eq!(locations, file.sections().to_vec());
+
+        let mut written = vec![];
+        index.write(&mut written).unwrap();
+        let index = ParameterIndex::<Bls12>::read(&written[..]).unwrap();
+        assert!(index.vk() == &params.vk);
+        assert!(index.verify(&bytes[..]).is_ok());
+
+        let mut tampered = written.clone();
+        tampered[20] ^= 1;
+        assert!(ParameterIndex::<Bls12>::read(&tampered[..]).is_err());
+
+        // Flip a coordinate bit in the first element of the L query.
+        let mut corrupted = bytes.clone();
+        corrupted[index.sections()[1].location.offset as usize + 40] ^= 1;
+        let err = index.verify(&corrupted[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("L query"));
+
+        let file = ParameterFile::open_indexed(&path, &index, true).unwrap();
+        let a = Scalar::random(&mut thread_rng());
+        let b = Scalar::random(&mut thread_rng());
+        let circuit = || MulCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let r = Scalar::random(&mut thread_rng());
+        let s = Scalar::random(&mut thread_rng());
+        assert!(
+            create_proof::<Bls12, _, _>(circuit(), &file, r, s).unwrap()
+                == create_proof::<Bls12, _, _>(circuit(), &params, r, s).unwrap()
+        );
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(ParameterFile::open_indexed(&path, &index, true).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let (_, bytes, path) = write_parameters("truncated-file");
+        let file = ParameterFile::<Bls12>::open(&path, true).unwrap();
+
+        // Cut the file inside the key, inside a length prefix, inside a query
+        // and just before its end.
+        let h = file.sections()[0];
+        let b_g1 = file.sections()[3];
+        for &len in &[
+            10,
+            h.offset as usize - 2,
+            b_g1.offset as usize + 50,
+            bytes.len() - 1,
+        ] {
+            let truncated = &bytes[..len];
+            fs::write(&path, truncated).unwrap();
+            assert!(ParameterFile::<Bls12>::open(&path, true).is_err());
+            let err = ParameterIndex::<Bls12>::build(truncated).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_wrong_section_lengths() {
+        let (_, bytes, path) = write_parameters("section-length");
+        let file = ParameterFile::<Bls12>::open(&path, true).unwrap();
+
+        // Claim u32::MAX elements in each query in turn. Allocating that many
+        // points would abort the test, so this also checks that nothing is
+        // sized by the claimed length before it is checked against the file.
+        for section in file.sections().iter() {
+            let prefix = section.offset as usize - 4;
+            let mut corrupted = bytes.clone();
+            corrupted[prefix..prefix + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+            fs::write(&path, &corrupted).unwrap();
+
+            let err = ParameterFile::<Bls12>::open(&path, true).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            let err = ParameterIndex::<Bls12>::build(&corrupted[..])
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        // Likewise for an index claiming too many elements, with the file
+        // still of the expected length.
+        fs::write(&path, &bytes).unwrap();
+        let index = ParameterIndex::<Bls12>::build(&bytes[..]).unwrap();
+        for i in 0..index.sections().len() {
+            let mut corrupted = index.clone();
+            corrupted.sections[i].location.len = u32::MAX as usize;
+            let err = ParameterFile::open_indexed(&path, &corrupted, true)
+                .err()
+                .unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,18 @@
 use rand_core::RngCore;
+use std::error::Error;
+use std::fmt;
 use std::ops::{AddAssign, MulAssign};
 use std::sync::Arc;
 
-use ff::{Field, PrimeField};
+use ff::{Field, PrimeField, PrimeFieldBits};
 use group::{prime::PrimeCurveAffine, Curve, Group, Wnaf, WnafGroup};
-use pairing::Engine;
+use pairing::{Engine, MultiMillerLoop};
 
-use super::{Parameters, VerifyingKey};
+use super::prover::prove_witness;
+use super::witness::record_commitment;
+use super::{
+    prepare_verifying_key, verify_commitment, verify_proof, Parameters, R1cs, VerifyingKey, Witness,
+};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
@@ -36,7 +42,11 @@ where
     let gamma = E::Fr::from(6);
     let delta = E::Fr::from(24);
     let tau = E::Fr::from(2);
-    generate_parameters::<E, C>(circuit, g1, g2, alpha, beta, gamma, delta, tau)
+    let eta = E::Fr::random(&mut rng);
+    let sigma = E::Fr::random(&mut rng);
+    generate_committed_parameters::<E, C>(
+        circuit, g1, g2, alpha, beta, gamma, delta, tau, eta, sigma,
+    )
 }
 
 /// This is our assembly structure that we'll use to synthesize the
@@ -51,6 +61,7 @@ pub struct KeypairAssembly<Scalar: PrimeField> {
     at_aux: Vec<Vec<(Scalar, usize)>>,
     bt_aux: Vec<Vec<(Scalar, usize)>>,
     ct_aux: Vec<Vec<(Scalar, usize)>>,
+    committed: Vec<usize>,
 }
 
 impl<Scalar: PrimeField> ConstraintSystem<Scalar> for KeypairAssembly<Scalar> {
@@ -153,12 +164,82 @@ impl<Scalar: PrimeField> ConstraintSystem<Scalar> for KeypairAssembly<Scalar> {
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        record_commitment(&mut self.committed, self.num_aux, variable)
+    }
 }
 
 use crate::groth16::mpc::{
-    mpc_common_paramters_custom_all, mpc_uncommon_paramters_custom_all, CommonParamter,
-    UnCommonParamter,
+    initial_common_paramters, initial_uncommon_paramters, mpc_common_paramters_custom_all,
+    mpc_common_paramters_generator, mpc_uncommon_paramters_custom_all,
+    mpc_uncommon_paramters_generator, verify_common_paramter, verify_uncommon_paramter,
+    CommonParamter, CommonParamterInStorage, CommonParamterMatrix, UnCommonParamter,
+    UnCommonParamterInStorage,
 };
+
+impl<Scalar: PrimeField> KeypairAssembly<Scalar> {
+    fn new() -> Self {
+        KeypairAssembly {
+            num_inputs: 0,
+            num_aux: 0,
+            num_constraints: 0,
+            at_inputs: vec![],
+            bt_inputs: vec![],
+            ct_inputs: vec![],
+            at_aux: vec![],
+            bt_aux: vec![],
+            ct_aux: vec![],
+            committed: vec![],
+        }
+    }
+
+    /// Synthesizes `circuit` together with the "one" input variable and the
+    /// input constraints that keep the IC query fully dense.
+    fn synthesize<C: Circuit<Scalar>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut assembly = KeypairAssembly::new();
+
+        // Allocate the "one" input variable
+        assembly.alloc_input(|| "", || Ok(Scalar::one()))?;
+
+        circuit.synthesize(&mut assembly)?;
+        assembly.enforce_inputs();
+
+        Ok(assembly)
+    }
+
+    fn enforce_inputs(&mut self) {
+        for i in 0..self.num_inputs {
+            self.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+        }
+    }
+}
+
+/// Applies the QAP matrices of `circuit` to the common ceremony state,
+/// producing the input of the circuit-specific ceremony.
+pub fn circuit_common_paramter_matrix<E, C>(
+    circuit: C,
+    common: &CommonParamterInStorage<E>,
+) -> Result<CommonParamterMatrix<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+{
+    let assembly = KeypairAssembly::<E::Fr>::synthesize(circuit)?;
+
+    common.matrix(
+        &assembly.at_inputs,
+        &assembly.bt_inputs,
+        &assembly.ct_inputs,
+        &assembly.at_aux,
+        &assembly.bt_aux,
+        &assembly.ct_aux,
+        assembly.num_constraints,
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn generate_parameters_mpc<E, C>(
     circuit: C,
@@ -171,72 +252,914 @@ where
     E::G2: WnafGroup,
     C: Circuit<E::Fr>,
 {
-    let mut assembly = KeypairAssembly {
-        num_inputs: 0,
-        num_aux: 0,
-        num_constraints: 0,
-        at_inputs: vec![],
-        bt_inputs: vec![],
-        ct_inputs: vec![],
-        at_aux: vec![],
-        bt_aux: vec![],
-        ct_aux: vec![],
-    };
-
-    // Allocate the "one" input variable
-    assembly.alloc_input(|| "", || Ok(E::Fr::one()))?;
-
-    circuit.synthesize(&mut assembly)?;
-    for i in 0..assembly.num_inputs {
-        assembly.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
-    }
-    let mut a = vec![E::G1Affine::identity(); assembly.num_inputs + assembly.num_aux];
-    let mut b_g1 = vec![E::G1Affine::identity(); assembly.num_inputs + assembly.num_aux];
-    let mut b_g2 = vec![E::G2Affine::identity(); assembly.num_inputs + assembly.num_aux];
+    let assembly = KeypairAssembly::<E::Fr>::synthesize(circuit)?;
 
     let cp = mpc_common_paramters_custom_all::<E>();
     let cp_m = cp.matrix(
+        &assembly.at_inputs,
+        &assembly.bt_inputs,
+        &assembly.ct_inputs,
         &assembly.at_aux,
         &assembly.bt_aux,
         &assembly.ct_aux,
-        assembly.num_inputs,
-        assembly.num_aux,
         assembly.num_constraints,
-    );
+    )?;
     let ucp = mpc_uncommon_paramters_custom_all::<E>(&cp_m);
+
+    Ok(ceremony_parameters(&cp, &cp_m, &ucp))
+}
+
+/// Assembles [`Parameters`] from the final common and circuit-specific
+/// ceremony states, and the ceremony matrix the latter was started from.
+///
+/// The A and B queries are taken from the matrix. The ceremony treats
+/// committed variables like any other, so the commitment key is empty.
+fn ceremony_parameters<E>(
+    phase1_state: &CommonParamterInStorage<E>,
+    matrix: &CommonParamterMatrix<E>,
+    phase2_state: &UnCommonParamterInStorage<E>,
+) -> Parameters<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
     let vk = VerifyingKey::<E> {
-        alpha_g1: cp.alpha_g1.to_affine(),
-        beta_g1: cp.beta_g1.to_affine(),
-        beta_g2: cp.beta_g2.to_affine(),
-        gamma_g2: ucp.gamma_g2.to_affine(),
-        delta_g1: ucp.delta_g1.to_affine(),
-        delta_g2: ucp.delta_g2.to_affine(),
-        ic: vec_to_list::<E>(&ucp.kin_g1),
+        alpha_g1: phase1_state.alpha_g1.to_affine(),
+        beta_g1: phase1_state.beta_g1.to_affine(),
+        beta_g2: phase1_state.beta_g2.to_affine(),
+        gamma_g2: phase2_state.gamma_g2.to_affine(),
+        delta_g1: phase2_state.delta_g1.to_affine(),
+        delta_g2: phase2_state.delta_g2.to_affine(),
+        ic: vec_to_list::<E>(&phase2_state.kin_g1),
+        commitment_key: vec![],
+        eta_gamma_inv_g1: E::G1Affine::identity(),
+        eta_delta_inv_g1: E::G1Affine::identity(),
+        commitment_pok_key: vec![],
+        sigma_eta_gamma_inv_g1: E::G1Affine::identity(),
+        sigma_g2: E::G2Affine::identity(),
     };
-    Ok(Parameters {
+    Parameters {
         vk,
-        h: Arc::new(vec_to_list::<E>(&ucp.h_g1)),
-        l: Arc::new(vec_to_list::<E>(&ucp.kout_g1)),
-        // Filter points at infinity away from A/B queries
-        a: Arc::new(
-            a.into_iter()
-                .filter(|e| bool::from(!e.is_identity()))
-                .collect(),
+        h: Arc::new(vec_to_list::<E>(&phase2_state.h_g1)),
+        l: Arc::new(vec_to_list::<E>(&phase2_state.kout_g1)),
+        a: Arc::new(non_identity(&matrix.a_g1)),
+        b_g1: Arc::new(non_identity(&matrix.b_g1)),
+        b_g2: Arc::new(non_identity(&matrix.b_g2)),
+    }
+}
+
+/// Converts the elements of a ceremony query to affine, filtering points at
+/// infinity away like the A and B queries of [`generate_parameters`].
+fn non_identity<G: Curve>(query: &[G]) -> Vec<G::AffineRepr> {
+    query
+        .iter()
+        .filter(|e| bool::from(!e.is_identity()))
+        .map(|e| e.to_affine())
+        .collect()
+}
+
+/// A section of [`Parameters`] that failed a consistency check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterSection {
+    AlphaG1,
+    BetaG1,
+    BetaG2,
+    GammaG2,
+    DeltaG1,
+    DeltaG2,
+    Ic,
+    H,
+    L,
+    A,
+    BG1,
+    BG2,
+    CommitmentKey,
+}
+
+/// An error while checking [`Parameters`] against the circuit they are for.
+#[derive(Debug)]
+pub enum ParameterCheckError {
+    /// The circuit could not be synthesized.
+    Synthesis(SynthesisError),
+    /// A section has a different number of elements than expected.
+    Length(ParameterSection),
+    /// A section does not match what it should have been derived from.
+    Inconsistent(ParameterSection),
+    /// A proof of the circuit's assignment, created with the parameters,
+    /// didn't verify. The error lies in one of the given sections, whose
+    /// elements couldn't all be checked otherwise.
+    ProofRejected(Vec<ParameterSection>),
+}
+
+impl From<SynthesisError> for ParameterCheckError {
+    fn from(e: SynthesisError) -> ParameterCheckError {
+        ParameterCheckError::Synthesis(e)
+    }
+}
+
+impl fmt::Display for ParameterCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            ParameterCheckError::Synthesis(ref e) => write!(f, "synthesis failed: {:?}", e),
+            ParameterCheckError::Length(section) => {
+                write!(f, "{:?} has an unexpected number of elements", section)
+            }
+            ParameterCheckError::Inconsistent(section) => {
+                write!(f, "{:?} is inconsistent", section)
+            }
+            ParameterCheckError::ProofRejected(ref sections) => write!(
+                f,
+                "a proof created with the parameters was rejected; one of {:?} is wrong",
+                sections
+            ),
+        }
+    }
+}
+
+impl Error for ParameterCheckError {}
+
+/// Checks that `params` for `circuit` are the output of a ceremony whose
+/// common (phase-1) state is `phase1_state` and whose circuit-specific
+/// (phase-2) state is `phase2_state`, or were generated directly with the
+/// toxic waste of that ceremony.
+///
+/// The circuit is synthesized again to recompute the ceremony matrix from the
+/// Lagrange basis of the phase-1 powers of tau. The A and B queries must equal
+/// those of the matrix, and every IC, L and H element is checked against it
+/// by a pairing with gamma or delta respectively.
+///
+/// The ceremony keeps committed variables in L, so if the circuit commits to
+/// variables, `params` may either do the same or, like
+/// [`generate_committed_parameters`], carry a commitment key for them. The
+/// key is then checked against the matrix by a pairing with gamma. The
+/// ceremony does not cover `eta` and `sigma`, so they are only checked for
+/// being the same, nonzero, throughout the verifying key.
+pub fn verify_parameters_against_ceremony<E, C>(
+    params: &Parameters<E>,
+    circuit: C,
+    phase1_state: &CommonParamterInStorage<E>,
+    phase2_state: &UnCommonParamterInStorage<E>,
+) -> Result<(), ParameterCheckError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+{
+    let vk = &params.vk;
+    let checks = [
+        (
+            vk.alpha_g1 == phase1_state.alpha_g1.to_affine(),
+            ParameterSection::AlphaG1,
         ),
-        b_g1: Arc::new(
-            b_g1.into_iter()
-                .filter(|e| bool::from(!e.is_identity()))
-                .collect(),
+        (
+            vk.beta_g1 == phase1_state.beta_g1.to_affine(),
+            ParameterSection::BetaG1,
         ),
-        b_g2: Arc::new(
-            b_g2.into_iter()
-                .filter(|e| bool::from(!e.is_identity()))
-                .collect(),
+        (
+            vk.beta_g2 == phase1_state.beta_g2.to_affine(),
+            ParameterSection::BetaG2,
         ),
-    })
+        (
+            vk.gamma_g2 == phase2_state.gamma_g2.to_affine(),
+            ParameterSection::GammaG2,
+        ),
+        (
+            vk.delta_g1 == phase2_state.delta_g1.to_affine(),
+            ParameterSection::DeltaG1,
+        ),
+        (
+            vk.delta_g2 == phase2_state.delta_g2.to_affine(),
+            ParameterSection::DeltaG2,
+        ),
+    ];
+    if let Some(&(_, section)) = checks.iter().find(|(ok, _)| !ok) {
+        return Err(ParameterCheckError::Inconsistent(section));
+    }
+
+    let assembly = KeypairAssembly::<E::Fr>::synthesize(circuit)?;
+    let matrix = phase1_state.matrix(
+        &assembly.at_inputs,
+        &assembly.bt_inputs,
+        &assembly.ct_inputs,
+        &assembly.at_aux,
+        &assembly.bt_aux,
+        &assembly.ct_aux,
+        assembly.num_constraints,
+    )?;
+
+    let a = non_identity(&matrix.a_g1);
+    let b_g1 = non_identity(&matrix.b_g1);
+    let b_g2 = non_identity(&matrix.b_g2);
+    let queries = [
+        (
+            params.a.len() == a.len(),
+            params.a[..] == a[..],
+            ParameterSection::A,
+        ),
+        (
+            params.b_g1.len() == b_g1.len(),
+            params.b_g1[..] == b_g1[..],
+            ParameterSection::BG1,
+        ),
+        (
+            params.b_g2.len() == b_g2.len(),
+            params.b_g2[..] == b_g2[..],
+            ParameterSection::BG2,
+        ),
+    ];
+    for &(length, equal, section) in queries.iter() {
+        if !length {
+            return Err(ParameterCheckError::Length(section));
+        }
+        if !equal {
+            return Err(ParameterCheckError::Inconsistent(section));
+        }
+    }
+
+    // Split the back of the matrix into the L query and the commitment key,
+    // if the parameters have one.
+    let mut committed = vec![false; assembly.num_aux];
+    if !vk.commitment_key.is_empty() || !vk.commitment_pok_key.is_empty() {
+        for &i in &assembly.committed {
+            committed[i] = true;
+        }
+    }
+    let (key, l): (Vec<_>, Vec<_>) = matrix
+        .matrixed_g1_back
+        .iter()
+        .zip(committed)
+        .partition(|(_, committed)| *committed);
+    let key: Vec<_> = key.into_iter().map(|(e, _)| *e).collect();
+    let l: Vec<_> = l.into_iter().map(|(e, _)| *e).collect();
+
+    let g2 = E::G2Affine::generator();
+
+    // Each element e of a section must satisfy e(e, divisor) = e(m, g2) for
+    // the corresponding ceremony matrix element m.
+    let sections = [
+        (
+            &vk.ic[..],
+            &matrix.matrixed_g1_front[..],
+            vk.gamma_g2,
+            ParameterSection::Ic,
+        ),
+        (&params.l[..], &l[..], vk.delta_g2, ParameterSection::L),
+        (
+            &vk.commitment_key[..],
+            &key[..],
+            vk.gamma_g2,
+            ParameterSection::CommitmentKey,
+        ),
+        (
+            &params.h[..],
+            &matrix.matrixed_h_g1[..],
+            vk.delta_g2,
+            ParameterSection::H,
+        ),
+    ];
+    for (elements, expected, divisor, section) in sections.iter() {
+        if elements.len() != expected.len() {
+            return Err(ParameterCheckError::Length(*section));
+        }
+
+        for (e, m) in elements.iter().zip(expected.iter()) {
+            if E::pairing(e, divisor) != E::pairing(&m.to_affine(), &g2) {
+                return Err(ParameterCheckError::Inconsistent(*section));
+            }
+        }
+    }
+
+    if vk.commitment_pok_key.len() != vk.commitment_key.len() {
+        return Err(ParameterCheckError::Length(ParameterSection::CommitmentKey));
+    }
+    if !vk.commitment_key.is_empty() {
+        // eta / gamma and eta / delta must encode the same nonzero eta, and
+        // every sigma-scaled element must be scaled by the nonzero sigma of
+        // sigma_g2.
+        let consistent = !bool::from(vk.eta_gamma_inv_g1.is_identity())
+            && !bool::from(vk.sigma_g2.is_identity())
+            && E::pairing(&vk.eta_gamma_inv_g1, &vk.gamma_g2)
+                == E::pairing(&vk.eta_delta_inv_g1, &vk.delta_g2)
+            && E::pairing(&vk.sigma_eta_gamma_inv_g1, &g2)
+                == E::pairing(&vk.eta_gamma_inv_g1, &vk.sigma_g2)
+            && vk
+                .commitment_key
+                .iter()
+                .zip(vk.commitment_pok_key.iter())
+                .all(|(e, pok)| E::pairing(pok, &g2) == E::pairing(e, &vk.sigma_g2));
+        if !consistent {
+            return Err(ParameterCheckError::Inconsistent(
+                ParameterSection::CommitmentKey,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Synthesizes a circuit for [`validate_parameters`], recording its QAP
+/// columns like [`KeypairAssembly`] along with its constraints and whatever
+/// assignment it carries.
+struct ValidationAssembly<S: PrimeField> {
+    keypair: KeypairAssembly<S>,
+    r1cs: R1cs<S>,
+    witness: Witness<S>,
+    complete: bool,
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for ValidationAssembly<S> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match f() {
+            Ok(value) => self.witness.aux_assignment.push(value),
+            Err(_) => self.complete = false,
+        }
+        self.r1cs.alloc(|| "", || Ok(S::zero()))?;
+        self.keypair.alloc(annotation, || Ok(S::zero()))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        match f() {
+            Ok(value) => self.witness.input_assignment.push(value),
+            Err(_) => self.complete = false,
+        }
+        self.r1cs.alloc_input(|| "", || Ok(S::zero()))?;
+        self.keypair.alloc_input(annotation, || Ok(S::zero()))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        let a = a(LinearCombination::zero());
+        let b = b(LinearCombination::zero());
+        let c = c(LinearCombination::zero());
+
+        self.keypair
+            .enforce(|| "", |_| a.clone(), |_| b.clone(), |_| c.clone());
+        self.r1cs.enforce(|| "", |_| a, |_| b, |_| c);
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        self.r1cs.commit(variable)?;
+        self.keypair.commit(variable)
+    }
+}
+
+impl<S: PrimeField> ValidationAssembly<S> {
+    fn synthesize<C: Circuit<S>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut assembly = ValidationAssembly {
+            keypair: KeypairAssembly::new(),
+            r1cs: R1cs::from_parts(0, 0, vec![]),
+            witness: Witness {
+                input_assignment: vec![],
+                aux_assignment: vec![],
+            },
+            complete: true,
+        };
+
+        // Allocate the "one" input variable
+        assembly.alloc_input(|| "", || Ok(S::one()))?;
+
+        circuit.synthesize(&mut assembly)?;
+
+        // Only the QAP gets the input constraints; the prover adds them to
+        // the R1CS itself.
+        assembly.keypair.enforce_inputs();
+
+        Ok(assembly)
+    }
+}
+
+/// Checks that `params` are consistent with `circuit`, without knowing the
+/// toxic waste they were generated with.
+///
+/// The circuit is synthesized again to check the length of every section.
+/// The sections are then checked against each other by randomized pairing
+/// checks, each combining a whole section with scalars drawn from `rng`:
+///
+/// - `delta_g1` and `delta_g2` must encode the same scalar;
+/// - `b_g1` and `b_g2` must encode the same evaluations of the B polynomials
+///   (a mismatch is reported as [`ParameterSection::BG2`]);
+/// - the IC, commitment key and L elements of variables that don't appear in
+///   the C matrix must be `(beta * A + alpha * B) / gamma`, `/ gamma` and
+///   `/ delta` respectively, which also covers the A query of those
+///   variables;
+/// - if the circuit commits to variables, `eta_gamma_inv_g1` and
+///   `eta_delta_inv_g1` must encode the same nonzero `eta`, and the
+///   sigma-scaled commitment key must be the commitment key and
+///   `eta_gamma_inv_g1` scaled by the nonzero `sigma` of `sigma_g2`.
+///
+/// The H query, and the IC, commitment key, L, A and B elements of variables
+/// appearing in C, can't be checked without `tau`, so they are only checked
+/// for their length. The sections holding such elements are returned, in the
+/// order of [`ParameterSection`]; an empty list means every element was
+/// checked.
+///
+/// If `circuit` carries an assignment that satisfies it, a proof of it is
+/// also created with `params` and verified. This catches many errors in the
+/// returned sections, and a proof that doesn't verify is reported as
+/// [`ParameterCheckError::ProofRejected`], but a single proof doesn't check
+/// every element, so the sections are returned all the same.
+pub fn validate_parameters<E, C, R>(
+    params: &Parameters<E>,
+    circuit: C,
+    rng: &mut R,
+) -> Result<Vec<ParameterSection>, ParameterCheckError>
+where
+    E: MultiMillerLoop,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    R: RngCore,
+{
+    let assembly = ValidationAssembly::synthesize(circuit)?;
+    let keypair = &assembly.keypair;
+    let vk = &params.vk;
+
+    let h_len = EvaluationDomain::from_coeffs(vec![
+        Scalar::<E::Fr>(E::Fr::zero());
+        keypair.num_constraints
+    ])?
+    .as_ref()
+    .len()
+        - 1;
+    let density = |inputs: &[Vec<(E::Fr, usize)>], aux: &[Vec<(E::Fr, usize)>]| {
+        inputs
+            .iter()
+            .chain(aux.iter())
+            .filter(|t| !t.is_empty())
+            .count()
+    };
+    let a_len = density(&keypair.at_inputs, &keypair.at_aux);
+    let b_len = density(&keypair.bt_inputs, &keypair.bt_aux);
+
+    let num_committed = keypair.committed.len();
+    let lengths = [
+        (vk.ic.len() == keypair.num_inputs, ParameterSection::Ic),
+        (
+            vk.commitment_key.len() == num_committed,
+            ParameterSection::CommitmentKey,
+        ),
+        (
+            params.l.len() == keypair.num_aux - num_committed,
+            ParameterSection::L,
+        ),
+        (params.h.len() == h_len, ParameterSection::H),
+        (params.a.len() == a_len, ParameterSection::A),
+        (params.b_g1.len() == b_len, ParameterSection::BG1),
+        (params.b_g2.len() == b_len, ParameterSection::BG2),
+    ];
+    if let Some(&(_, section)) = lengths.iter().find(|(ok, _)| !ok) {
+        return Err(ParameterCheckError::Length(section));
+    }
+
+    if E::pairing(&vk.delta_g1, &vk.beta_g2) != E::pairing(&vk.beta_g1, &vk.delta_g2) {
+        return Err(ParameterCheckError::Inconsistent(ParameterSection::DeltaG1));
+    }
+
+    if num_committed > 0
+        && (bool::from(vk.eta_gamma_inv_g1.is_identity())
+            || E::pairing(&vk.eta_gamma_inv_g1, &vk.gamma_g2)
+                != E::pairing(&vk.eta_delta_inv_g1, &vk.delta_g2))
+    {
+        return Err(ParameterCheckError::Inconsistent(
+            ParameterSection::CommitmentKey,
+        ));
+    }
+
+    // The sigma-scaled key must be the commitment key and eta / gamma scaled
+    // by the same nonzero sigma.
+    if vk.commitment_pok_key.len() != num_committed {
+        return Err(ParameterCheckError::Length(ParameterSection::CommitmentKey));
+    }
+    if num_committed > 0 {
+        let r = E::Fr::random(&mut *rng);
+        let mut key = vk.eta_gamma_inv_g1 * r;
+        let mut pok_key = vk.sigma_eta_gamma_inv_g1 * r;
+        for (e, pok) in vk.commitment_key.iter().zip(vk.commitment_pok_key.iter()) {
+            let r = E::Fr::random(&mut *rng);
+            key += *e * r;
+            pok_key += *pok * r;
+        }
+
+        if bool::from(vk.sigma_g2.is_identity())
+            || E::pairing(&pok_key.to_affine(), &E::G2Affine::generator())
+                != E::pairing(&key.to_affine(), &vk.sigma_g2)
+        {
+            return Err(ParameterCheckError::Inconsistent(
+                ParameterSection::CommitmentKey,
+            ));
+        }
+    }
+
+    // Random combinations of the B query in both groups, and of the IC (at
+    // index 0), commitment key (at index 1) and L (at index 2) elements of
+    // the variables that aren't in C together with their A and B elements.
+    let mut b_g1 = E::G1::identity();
+    let mut b_g2 = E::G2::identity();
+    let mut unscaled = [(E::G1::identity(), E::G1::identity(), E::G2::identity()); 3];
+
+    let columns = keypair
+        .at_inputs
+        .iter()
+        .zip(keypair.bt_inputs.iter())
+        .zip(keypair.ct_inputs.iter())
+        .chain(
+            keypair
+                .at_aux
+                .iter()
+                .zip(keypair.bt_aux.iter())
+                .zip(keypair.ct_aux.iter()),
+        );
+    let mut commitment_index = vec![None; keypair.num_aux];
+    for (k, &i) in keypair.committed.iter().enumerate() {
+        commitment_index[i] = Some(k);
+    }
+    let mut l = params.l.iter();
+    let scaled = vk
+        .ic
+        .iter()
+        .map(|e| (e, 0))
+        .chain(commitment_index.iter().map(|k| match k {
+            Some(k) => (&vk.commitment_key[*k], 1),
+            None => (l.next().unwrap(), 2),
+        }));
+    let (mut a_index, mut b_index) = (0, 0);
+    // Whether the IC, commitment key, L, A and B sections have an element
+    // that the combinations can't check.
+    let mut unchecked_scaled = [false; 3];
+    let (mut unchecked_a, mut unchecked_b) = (false, false);
+    for (((at, bt), ct), (scaled, section)) in columns.zip(scaled) {
+        let r = E::Fr::random(&mut *rng);
+
+        let mut a = E::G1::identity();
+        if !at.is_empty() {
+            a = params.a[a_index] * r;
+            a_index += 1;
+        }
+        let mut b = E::G2::identity();
+        if !bt.is_empty() {
+            b_g1 += params.b_g1[b_index] * r;
+            b = params.b_g2[b_index] * r;
+            b_index += 1;
+        }
+        b_g2 += b;
+
+        if ct.is_empty() {
+            let acc = &mut unscaled[section];
+            acc.0 += *scaled * r;
+            acc.1 += a;
+            acc.2 += b;
+        } else {
+            unchecked_scaled[section] = true;
+            unchecked_a |= !at.is_empty();
+            unchecked_b |= !bt.is_empty();
+        }
+    }
+
+    if E::pairing(&b_g1.to_affine(), &vk.beta_g2) != E::pairing(&vk.beta_g1, &b_g2.to_affine()) {
+        return Err(ParameterCheckError::Inconsistent(ParameterSection::BG2));
+    }
+
+    let divisors = [
+        (vk.gamma_g2, ParameterSection::Ic),
+        (vk.gamma_g2, ParameterSection::CommitmentKey),
+        (vk.delta_g2, ParameterSection::L),
+    ];
+    for ((scaled, a, b), (divisor, section)) in unscaled.iter().zip(divisors.iter()) {
+        let lhs = E::pairing(&scaled.to_affine(), divisor);
+        let rhs =
+            E::pairing(&a.to_affine(), &vk.beta_g2) + E::pairing(&vk.alpha_g1, &b.to_affine());
+        if lhs != rhs {
+            return Err(ParameterCheckError::Inconsistent(*section));
+        }
+    }
+
+    let unchecked: Vec<_> = [
+        (unchecked_scaled[0], ParameterSection::Ic),
+        (!params.h.is_empty(), ParameterSection::H),
+        (unchecked_scaled[2], ParameterSection::L),
+        (unchecked_a, ParameterSection::A),
+        (unchecked_b, ParameterSection::BG1),
+        (unchecked_b, ParameterSection::BG2),
+        (unchecked_scaled[1], ParameterSection::CommitmentKey),
+    ]
+    .iter()
+    .filter(|(unchecked, _)| *unchecked)
+    .map(|&(_, section)| section)
+    .collect();
+
+    if assembly.complete && assembly.r1cs.is_satisfied(&assembly.witness) {
+        let r = E::Fr::random(&mut *rng);
+        let s = E::Fr::random(&mut *rng);
+        let v = E::Fr::random(&mut *rng);
+        let (proof, opening) =
+            prove_witness(&assembly.r1cs, &assembly.witness, params, r, s, Some(v))?;
+
+        let pvk = prepare_verifying_key(vk);
+        if verify_proof(&pvk, &proof, assembly.witness.public_inputs()).is_err()
+            || verify_commitment(vk, &proof, &opening).is_err()
+        {
+            return Err(ParameterCheckError::ProofRejected(unchecked));
+        }
+    }
+
+    Ok(unchecked)
+}
+
+/// The secrets of every party in a simulated ceremony.
+///
+/// The toxic waste of the resulting parameters is the product of the
+/// contributed secrets, e.g. `alpha` is the product of every `common[i].0`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedCeremony {
+    /// `(alpha, beta, tau)` of each party contributing to the common
+    /// (phase-1) state, in order.
+    pub common: Vec<(u64, u64, u64)>,
+    /// `(gamma, delta)` of each party contributing to the circuit-specific
+    /// (phase-2) state, in order.
+    pub uncommon: Vec<(u64, u64)>,
+}
+
+impl SimulatedCeremony {
+    /// Returns the toxic waste `(alpha, beta, gamma, delta, tau)` that the
+    /// ceremony amounts to.
+    pub fn toxic_waste<S: PrimeField>(&self) -> (S, S, S, S, S) {
+        let mut alpha = S::one();
+        let mut beta = S::one();
+        let mut tau = S::one();
+        for &(a, b, t) in &self.common {
+            alpha *= S::from(a);
+            beta *= S::from(b);
+            tau *= S::from(t);
+        }
+
+        let mut gamma = S::one();
+        let mut delta = S::one();
+        for &(g, d) in &self.uncommon {
+            gamma *= S::from(g);
+            delta *= S::from(d);
+        }
+
+        (alpha, beta, gamma, delta, tau)
+    }
+
+    /// Runs the ceremony for `circuit`, verifying every contribution, and
+    /// returns the final common and circuit-specific states.
+    ///
+    /// The common state is made just long enough for the circuit.
+    pub fn run<E, C>(
+        &self,
+        circuit: C,
+    ) -> Result<(CommonParamterInStorage<E>, UnCommonParamterInStorage<E>), SynthesisError>
+    where
+        E: Engine,
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+        C: Circuit<E::Fr>,
+    {
+        let (phase1_state, _, phase2_state) = self.run_with_matrix(circuit)?;
+        Ok((phase1_state, phase2_state))
+    }
+
+    /// Like [`SimulatedCeremony::run`], also returning the ceremony matrix
+    /// between the two phases.
+    fn run_with_matrix<E, C>(
+        &self,
+        circuit: C,
+    ) -> Result<
+        (
+            CommonParamterInStorage<E>,
+            CommonParamterMatrix<E>,
+            UnCommonParamterInStorage<E>,
+        ),
+        SynthesisError,
+    >
+    where
+        E: Engine,
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+        C: Circuit<E::Fr>,
+    {
+        let assembly = KeypairAssembly::<E::Fr>::synthesize(circuit)?;
+
+        // The H query needs tau^0 up to tau^(2m - 2) for a domain of size m,
+        // and the Lagrange basis only tau^0 up to tau^(m - 1).
+        let domain = EvaluationDomain::<E::Fr, Scalar<E::Fr>>::from_coeffs(vec![
+            Scalar(E::Fr::zero());
+            assembly.num_constraints
+        ])?;
+        let len = 2 * domain.as_ref().len() - 1;
+
+        let mut phase1_state = initial_common_paramters::<E>(len);
+        for &secret in &self.common {
+            let contribution = mpc_common_paramters_generator(&phase1_state, secret);
+            phase1_state = verify_common_paramter(&phase1_state, &contribution);
+        }
+
+        let matrix = phase1_state.matrix(
+            &assembly.at_inputs,
+            &assembly.bt_inputs,
+            &assembly.ct_inputs,
+            &assembly.at_aux,
+            &assembly.bt_aux,
+            &assembly.ct_aux,
+            assembly.num_constraints,
+        )?;
+
+        let mut phase2_state = initial_uncommon_paramters(&matrix);
+        for &secret in &self.uncommon {
+            let contribution = mpc_uncommon_paramters_generator(&phase2_state, secret);
+            phase2_state = verify_uncommon_paramter(&matrix, &phase2_state, &contribution);
+        }
+
+        Ok((phase1_state, matrix, phase2_state))
+    }
+}
+
+/// A difference between two sets of [`Parameters`] for the same circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterDifference {
+    /// The section has `direct` elements in the directly generated parameters
+    /// and `ceremony` elements in the ceremony's.
+    Length {
+        section: ParameterSection,
+        direct: usize,
+        ceremony: usize,
+    },
+    /// The element at `index` of the section differs.
+    Element {
+        section: ParameterSection,
+        index: usize,
+    },
+}
+
+/// Generates parameters for a circuit both with [`generate_parameters`] and
+/// through `ceremony`, using the same toxic waste, and returns every element
+/// in which they differ.
+///
+/// `circuit` is called once per path, as each consumes its circuit.
+pub fn compare_with_ceremony<E, C, F>(
+    circuit: F,
+    ceremony: &SimulatedCeremony,
+) -> Result<Vec<ParameterDifference>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+    F: Fn() -> C,
+{
+    let (alpha, beta, gamma, delta, tau) = ceremony.toxic_waste::<E::Fr>();
+    let direct = generate_parameters::<E, C>(
+        circuit(),
+        E::G1::generator(),
+        E::G2::generator(),
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+    )?;
+
+    let (phase1_state, matrix, phase2_state) = ceremony.run_with_matrix::<E, C>(circuit())?;
+    let from_ceremony = ceremony_parameters(&phase1_state, &matrix, &phase2_state);
+
+    fn compare<T: PartialEq>(
+        section: ParameterSection,
+        direct: &[T],
+        ceremony: &[T],
+        differences: &mut Vec<ParameterDifference>,
+    ) {
+        if direct.len() != ceremony.len() {
+            differences.push(ParameterDifference::Length {
+                section,
+                direct: direct.len(),
+                ceremony: ceremony.len(),
+            });
+        }
+
+        differences.extend(
+            direct
+                .iter()
+                .zip(ceremony.iter())
+                .enumerate()
+                .filter(|(_, (d, c))| d != c)
+                .map(|(index, _)| ParameterDifference::Element { section, index }),
+        );
+    }
+
+    let (d, c) = (&direct.vk, &from_ceremony.vk);
+    let mut differences = vec![];
+    compare(
+        ParameterSection::AlphaG1,
+        &[d.alpha_g1],
+        &[c.alpha_g1],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::BetaG1,
+        &[d.beta_g1],
+        &[c.beta_g1],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::BetaG2,
+        &[d.beta_g2],
+        &[c.beta_g2],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::GammaG2,
+        &[d.gamma_g2],
+        &[c.gamma_g2],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::DeltaG1,
+        &[d.delta_g1],
+        &[c.delta_g1],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::DeltaG2,
+        &[d.delta_g2],
+        &[c.delta_g2],
+        &mut differences,
+    );
+    compare(ParameterSection::Ic, &d.ic[..], &c.ic[..], &mut differences);
+    compare(
+        ParameterSection::H,
+        &direct.h[..],
+        &from_ceremony.h[..],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::L,
+        &direct.l[..],
+        &from_ceremony.l[..],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::A,
+        &direct.a[..],
+        &from_ceremony.a[..],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::BG1,
+        &direct.b_g1[..],
+        &from_ceremony.b_g1[..],
+        &mut differences,
+    );
+    compare(
+        ParameterSection::BG2,
+        &direct.b_g2[..],
+        &from_ceremony.b_g2[..],
+        &mut differences,
+    );
+
+    Ok(differences)
 }
 
 /// Create parameters for a circuit, given some toxic waste.
+///
+/// Circuits that commit to variables need the additional toxic waste `eta`
+/// and `sigma` of [`generate_committed_parameters`]; for them this returns
+/// `SynthesisError::UnexpectedIdentity`.
 #[allow(clippy::too_many_arguments)]
 pub fn generate_parameters<E, C>(
     circuit: C,
@@ -254,58 +1177,65 @@ where
     E::G2: WnafGroup,
     C: Circuit<E::Fr>,
 {
-    let mut assembly = KeypairAssembly {
-        num_inputs: 0,
-        num_aux: 0,
-        num_constraints: 0,
-        at_inputs: vec![],
-        bt_inputs: vec![],
-        ct_inputs: vec![],
-        at_aux: vec![],
-        bt_aux: vec![],
-        ct_aux: vec![],
-    };
-    // Allocate the "one" input variable
-    assembly.alloc_input(|| "", || Ok(E::Fr::one()))?;
-
-    // Synthesize the circuit.
-    circuit.synthesize(&mut assembly)?;
-    println!("没声明时");
-    println!("at_aux{:?}", assembly.at_aux);
-    println!("bt_aux{:?}", assembly.bt_aux);
-    println!("ct_aux{:?}", assembly.ct_aux);
-    // Input constraints to ensure full density of IC query
-    // x * 0 = 0
-    for i in 0..assembly.num_inputs {
-        assembly.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
-    }
-    println!("声明后");
-    println!("at_aux{:?}", assembly.at_aux);
-    println!("bt_aux{:?}", assembly.bt_aux);
-    println!("ct_aux{:?}", assembly.ct_aux);
-    println!("num_aux :{}", assembly.num_aux);
-    println!("num_input :{}", assembly.num_inputs);
+    generate_committed_parameters::<E, C>(
+        circuit,
+        g1,
+        g2,
+        alpha,
+        beta,
+        gamma,
+        delta,
+        tau,
+        E::Fr::zero(),
+        E::Fr::zero(),
+    )
+}
 
-    {
-        let fuck = assembly.at_aux[0][0].0;
-        let alpha_front = g1 * fuck;
-    }
+/// Create parameters for a circuit that may commit to variables, given some
+/// toxic waste.
+///
+/// As in LegoGroth16, the IC-like elements `(beta * A + alpha * B + C) /
+/// gamma` of the committed variables form the commitment key instead of
+/// being part of the L query, and `eta` blinds the commitment. As in gnark,
+/// the commitment key is also scaled by `sigma`, so that proofs can carry a
+/// proof of knowledge of the opening of their commitment, which
+/// [`verify_proof`] checks against `sigma` in G2. `eta` and `sigma` are
+/// unused, and may be zero, if the circuit commits to nothing.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_committed_parameters<E, C>(
+    circuit: C,
+    g1: E::G1,
+    g2: E::G2,
+    alpha: E::Fr,
+    beta: E::Fr,
+    gamma: E::Fr,
+    delta: E::Fr,
+    tau: E::Fr,
+    eta: E::Fr,
+    sigma: E::Fr,
+) -> Result<Parameters<E>, SynthesisError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    C: Circuit<E::Fr>,
+{
+    let assembly = KeypairAssembly::<E::Fr>::synthesize(circuit)?;
+
+    // Without committed variables, the elements derived from eta and sigma
+    // are all the point at infinity, as in keys of bellman 0.11.
+    let (eta, sigma) = if assembly.committed.is_empty() {
+        (E::Fr::zero(), E::Fr::zero())
+    } else if eta.is_zero_vartime() || sigma.is_zero_vartime() {
+        return Err(SynthesisError::UnexpectedIdentity);
+    } else {
+        (eta, sigma)
+    };
 
     // Create bases for blind evaluation of polynomials at tau
     let powers_of_tau = vec![Scalar::<E::Fr>(E::Fr::zero()); assembly.num_constraints];
 
     let mut powers_of_tau = EvaluationDomain::from_coeffs(powers_of_tau)?;
-    let cp = mpc_common_paramters_custom_all::<E>();
-    let cp_m = cp.matrix_test(
-        &assembly.at_aux,
-        &assembly.bt_aux,
-        &assembly.ct_aux,
-        assembly.num_inputs,
-        assembly.num_aux,
-        assembly.num_constraints,
-    );
-
-    let ucp = mpc_uncommon_paramters_custom_all::<E>(&cp_m);
 
     // Compute G1 window table
     let mut g1_wnaf = Wnaf::new();
@@ -570,17 +1500,7 @@ where
         &beta,
         &worker,
     );
-    assert_eq!(cp.tau_g1[1], g1 * E::Fr::from(2));
-    assert_eq!(cp.alpha_mul_tau_g1[0], g1 * E::Fr::from(6));
-    assert_eq!(cp.alpha_mul_tau_g1[1], g1 * E::Fr::from(6 * 2));
-    assert_eq!(cp.beta_mul_tau_g1[0], g1 * E::Fr::from(24));
-    assert_eq!(cp.beta_mul_tau_g1[1], g1 * E::Fr::from(24 * 2));
-    //assert_eq!(ic[1] * gamma_inverse, g1 * E::Fr::from(0)); //?
-    /*for i in 0..200 {
-        println!("第{}个是吗", i);
-        assert_ne!(ic[1], (g1 * E::Fr::from(i)).to_affine());
-    }
-    assert_eq!(ic[1], (g1 * E::Fr::from(0)).to_affine());*/
+
     // Don't allow any elements be unconstrained, so that
     // the L query is always fully dense.
     for e in l.iter() {
@@ -589,8 +1509,29 @@ where
         }
     }
 
-    assert_eq!(h[0], ucp.h_g1[0].to_affine());
-    assert_eq!(h[1], ucp.h_g1[1].to_affine());
+    // Move the elements of the committed variables from L to the commitment
+    // key, rescaling them from 1 / delta to 1 / gamma.
+    let delta_over_gamma = delta * gamma_inverse;
+    let commitment_key: Vec<_> = assembly
+        .committed
+        .iter()
+        .map(|&i| (l[i] * delta_over_gamma).to_affine())
+        .collect();
+    let commitment_pok_key = commitment_key
+        .iter()
+        .map(|&e| (e * sigma).to_affine())
+        .collect();
+    let mut committed = vec![false; l.len()];
+    for &i in &assembly.committed {
+        committed[i] = true;
+    }
+    let l: Vec<_> = l
+        .into_iter()
+        .zip(committed)
+        .filter(|(_, committed)| !committed)
+        .map(|(e, _)| e)
+        .collect();
+
     let g1 = g1.to_affine();
     let g2 = g2.to_affine();
     let vk = VerifyingKey::<E> {
@@ -601,14 +1542,14 @@ where
         delta_g1: (g1 * delta).to_affine(),
         delta_g2: (g2 * delta).to_affine(),
         ic,
+        commitment_key,
+        eta_gamma_inv_g1: (g1 * (eta * gamma_inverse)).to_affine(),
+        eta_delta_inv_g1: (g1 * (eta * delta_inverse)).to_affine(),
+        commitment_pok_key,
+        sigma_eta_gamma_inv_g1: (g1 * (sigma * eta * gamma_inverse)).to_affine(),
+        sigma_g2: (g2 * sigma).to_affine(),
     };
-    //检查vk
-    assert_eq!(vk.alpha_g1, cp.alpha_g1.to_affine());
-    assert_eq!(vk.beta_g1, cp.beta_g1.to_affine());
-    assert_eq!(vk.beta_g2, cp.beta_g2.to_affine());
-    assert_eq!(vk.gamma_g2, ucp.gamma_g2.to_affine());
-    assert_eq!(vk.delta_g1, ucp.delta_g1.to_affine());
-    assert_eq!(vk.delta_g2, ucp.delta_g2.to_affine());
+
     Ok(Parameters {
         vk,
         h: Arc::new(h),
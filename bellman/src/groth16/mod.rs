@@ -8,6 +8,7 @@ use pairing::{Engine, MultiMillerLoop};
 use crate::SynthesisError;
 
 use crate::multiexp::SourceBuilder;
+use bls12_381::{pairing, Bls12, G1Affine, G2Affine};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{self, Read, Write};
 use std::sync::Arc;
@@ -15,51 +16,127 @@ use std::sync::Arc;
 #[cfg(test)]
 mod tests;
 
+pub mod aggregate;
+pub mod circom;
+pub mod evm;
+mod file;
 mod generator;
 pub mod mpc;
 mod prover;
+#[cfg(feature = "test-only-proof-simulator")]
+mod simulator;
 mod verifier;
+mod witness;
 mod mpc_test;
 
+pub use self::file::*;
 pub use self::generator::*;
 pub use self::prover::*;
+#[cfg(feature = "test-only-proof-simulator")]
+pub use self::simulator::*;
 pub use self::verifier::*;
+pub use self::witness::*;
+
+/// The magic that starts a serialized [`Proof`], followed by its version.
+const PROOF_MAGIC: &[u8; 4] = b"G16P";
+/// The magic that starts a serialized [`VerifyingKey`], and so also
+/// serialized [`Parameters`], followed by its version.
+const VK_MAGIC: &[u8; 4] = b"G16V";
+/// The current version of the proof and verifying key layouts. Version 0 is
+/// the layout of bellman 0.11, which has no magic and no commitment.
+const FORMAT_VERSION: u8 = 1;
+
+/// Reads the magic and version that start a layout of `reader`.
+///
+/// If `reader` doesn't start with `magic`, this returns version 0, and the
+/// bytes that were read are replayed by the returned reader. For BLS12-381,
+/// the magics can't be the start of a point in either encoding, since they
+/// set the infinity flag of a point that isn't all zeros.
+fn read_version<R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+) -> io::Result<(u8, io::Chain<io::Cursor<[u8; 4]>, R>)> {
+    let mut prefix = io::Cursor::new([0u8; 4]);
+    reader.read_exact(prefix.get_mut())?;
+
+    let version = if prefix.get_ref() == magic {
+        prefix.set_position(4);
+        match reader.read_u8()? {
+            version @ 1..=FORMAT_VERSION => version,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unsupported version",
+                ))
+            }
+        }
+    } else {
+        0
+    };
+
+    Ok((version, prefix.chain(reader)))
+}
 
 #[derive(Clone, Debug)]
 pub struct Proof<E: Engine> {
     pub a: E::G1Affine,
     pub b: E::G2Affine,
     pub c: E::G1Affine,
+    // Pedersen commitment to the committed variables of the circuit, under
+    // the commitment key of the verifying key. The point at infinity if the
+    // circuit commits to nothing.
+    pub d: E::G1Affine,
+    // Proof of knowledge of the opening of d, which is d under the
+    // sigma-scaled commitment key. The point at infinity if the circuit
+    // commits to nothing.
+    pub d_pok: E::G1Affine,
 }
 
 impl<E: Engine> PartialEq for Proof<E> {
     fn eq(&self, other: &Self) -> bool {
-        self.a == other.a && self.b == other.b && self.c == other.c
+        self.a == other.a
+            && self.b == other.b
+            && self.c == other.c
+            && self.d == other.d
+            && self.d_pok == other.d_pok
     }
 }
 
 impl<E: Engine> Proof<E> {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(PROOF_MAGIC)?;
+        writer.write_u8(FORMAT_VERSION)?;
         writer.write_all(self.a.to_bytes().as_ref())?;
         writer.write_all(self.b.to_bytes().as_ref())?;
         writer.write_all(self.c.to_bytes().as_ref())?;
+        writer.write_all(self.d.to_bytes().as_ref())?;
+        writer.write_all(self.d_pok.to_bytes().as_ref())?;
 
         Ok(())
     }
 
-    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
-        let read_g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
+    /// Reads a proof written by [`Self::write`], or by bellman 0.11, in which
+    /// case the proof carries no commitment.
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        let (version, reader) = read_version(reader, PROOF_MAGIC)?;
+        Self::read_layout(reader, version)
+    }
+
+    fn read_layout<R: Read>(mut reader: R, version: u8) -> io::Result<Self> {
+        let read_g1_or_identity = |reader: &mut R| -> io::Result<E::G1Affine> {
             let mut g1_repr = <E::G1Affine as GroupEncoding>::Repr::default();
             reader.read_exact(g1_repr.as_mut())?;
 
             let affine = E::G1Affine::from_bytes(&g1_repr);
-            let affine = if affine.is_some().into() {
+            if affine.is_some().into() {
                 Ok(affine.unwrap())
             } else {
                 Err(io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))
-            };
+            }
+        };
 
-            affine.and_then(|e| {
+        let read_g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
+            read_g1_or_identity(reader).and_then(|e| {
                 if e.is_identity().into() {
                     Err(io::Error::new(
                         io::ErrorKind::InvalidData,
@@ -97,8 +174,16 @@ impl<E: Engine> Proof<E> {
         let a = read_g1(&mut reader)?;
         let b = read_g2(&mut reader)?;
         let c = read_g1(&mut reader)?;
+        let (d, d_pok) = if version == 0 {
+            (E::G1Affine::identity(), E::G1Affine::identity())
+        } else {
+            (
+                read_g1_or_identity(&mut reader)?,
+                read_g1_or_identity(&mut reader)?,
+            )
+        };
 
-        Ok(Proof { a, b, c })
+        Ok(Proof { a, b, c, d, d_pok })
     }
 }
 
@@ -128,6 +213,26 @@ pub struct VerifyingKey<E: Engine> {
     // this is the same size as the number of inputs, and never contains points
     // at infinity.
     pub ic: Vec<E::G1Affine>,
+
+    // Elements of the same form as IC for the committed auxiliary variables,
+    // in the order they were committed. Together with eta / gamma in g1 they
+    // form the key of the Pedersen commitment carried by proofs. Never
+    // contains points at infinity.
+    pub commitment_key: Vec<E::G1Affine>,
+
+    // eta / gamma in g1, which blinds the commitment, and eta / delta in g1,
+    // which the prover uses to cancel the blinding in the C element of the
+    // proof. The point at infinity if the circuit commits to nothing.
+    pub eta_gamma_inv_g1: E::G1Affine,
+    pub eta_delta_inv_g1: E::G1Affine,
+
+    // The commitment key and eta / gamma in g1 scaled by sigma, with which
+    // the prover proves knowledge of the opening of its commitment, and sigma
+    // in g2, with which the verifier checks that proof. Empty, or the point
+    // at infinity, if the circuit commits to nothing.
+    pub commitment_pok_key: Vec<E::G1Affine>,
+    pub sigma_eta_gamma_inv_g1: E::G1Affine,
+    pub sigma_g2: E::G2Affine,
 }
 
 impl<E: Engine> PartialEq for VerifyingKey<E> {
@@ -139,11 +244,19 @@ impl<E: Engine> PartialEq for VerifyingKey<E> {
             && self.delta_g1 == other.delta_g1
             && self.delta_g2 == other.delta_g2
             && self.ic == other.ic
+            && self.commitment_key == other.commitment_key
+            && self.eta_gamma_inv_g1 == other.eta_gamma_inv_g1
+            && self.eta_delta_inv_g1 == other.eta_delta_inv_g1
+            && self.commitment_pok_key == other.commitment_pok_key
+            && self.sigma_eta_gamma_inv_g1 == other.sigma_eta_gamma_inv_g1
+            && self.sigma_g2 == other.sigma_g2
     }
 }
 
 impl<E: Engine> VerifyingKey<E> {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(VK_MAGIC)?;
+        writer.write_u8(FORMAT_VERSION)?;
         writer.write_all(self.alpha_g1.to_uncompressed().as_ref())?;
         writer.write_all(self.beta_g1.to_uncompressed().as_ref())?;
         writer.write_all(self.beta_g2.to_uncompressed().as_ref())?;
@@ -154,11 +267,30 @@ impl<E: Engine> VerifyingKey<E> {
         for ic in &self.ic {
             writer.write_all(ic.to_uncompressed().as_ref())?;
         }
+        writer.write_u32::<BigEndian>(self.commitment_key.len() as u32)?;
+        for ck in &self.commitment_key {
+            writer.write_all(ck.to_uncompressed().as_ref())?;
+        }
+        writer.write_all(self.eta_gamma_inv_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.eta_delta_inv_g1.to_uncompressed().as_ref())?;
+        writer.write_u32::<BigEndian>(self.commitment_pok_key.len() as u32)?;
+        for pok in &self.commitment_pok_key {
+            writer.write_all(pok.to_uncompressed().as_ref())?;
+        }
+        writer.write_all(self.sigma_eta_gamma_inv_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.sigma_g2.to_uncompressed().as_ref())?;
 
         Ok(())
     }
 
-    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+    /// Reads a key written by [`Self::write`], or by bellman 0.11, in which
+    /// case the key has no commitment key.
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        let (version, reader) = read_version(reader, VK_MAGIC)?;
+        Self::read_layout(reader, version)
+    }
+
+    fn read_layout<R: Read>(mut reader: R, version: u8) -> io::Result<Self> {
         let read_g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
             let mut g1_repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
             reader.read_exact(g1_repr.as_mut())?;
@@ -190,26 +322,32 @@ impl<E: Engine> VerifyingKey<E> {
         let delta_g1 = read_g1(&mut reader)?;
         let delta_g2 = read_g2(&mut reader)?;
 
-        let ic_len = reader.read_u32::<BigEndian>()? as usize;
+        let read_g1_list = |reader: &mut R| -> io::Result<Vec<E::G1Affine>> {
+            let len = reader.read_u32::<BigEndian>()? as usize;
 
-        let mut ic = vec![];
+            let mut list = vec![];
 
-        for _ in 0..ic_len {
-            let g1 = read_g1(&mut reader).and_then(|e| {
-                if e.is_identity().into() {
-                    Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "point at infinity",
-                    ))
-                } else {
-                    Ok(e)
-                }
-            })?;
+            for _ in 0..len {
+                let g1 = read_g1(reader).and_then(|e| {
+                    if e.is_identity().into() {
+                        Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "point at infinity",
+                        ))
+                    } else {
+                        Ok(e)
+                    }
+                })?;
+
+                list.push(g1);
+            }
 
-            ic.push(g1);
-        }
+            Ok(list)
+        };
+
+        let ic = read_g1_list(&mut reader)?;
 
-        Ok(VerifyingKey {
+        let mut vk = VerifyingKey {
             alpha_g1,
             beta_g1,
             beta_g2,
@@ -217,7 +355,36 @@ impl<E: Engine> VerifyingKey<E> {
             delta_g1,
             delta_g2,
             ic,
-        })
+            commitment_key: vec![],
+            eta_gamma_inv_g1: E::G1Affine::identity(),
+            eta_delta_inv_g1: E::G1Affine::identity(),
+            commitment_pok_key: vec![],
+            sigma_eta_gamma_inv_g1: E::G1Affine::identity(),
+            sigma_g2: E::G2Affine::identity(),
+        };
+        if version == 0 {
+            return Ok(vk);
+        }
+
+        vk.commitment_key = read_g1_list(&mut reader)?;
+        vk.eta_gamma_inv_g1 = read_g1(&mut reader)?;
+        vk.eta_delta_inv_g1 = read_g1(&mut reader)?;
+        vk.commitment_pok_key = read_g1_list(&mut reader)?;
+        vk.sigma_eta_gamma_inv_g1 = read_g1(&mut reader)?;
+        vk.sigma_g2 = read_g2(&mut reader)?;
+
+        // A commitment is only bound to its key if its proof of knowledge is
+        // checked against a nontrivial sigma.
+        if vk.commitment_pok_key.len() != vk.commitment_key.len()
+            || (!vk.commitment_key.is_empty() && bool::from(vk.sigma_g2.is_identity()))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid commitment key",
+            ));
+        }
+
+        Ok(vk)
     }
 }
 
@@ -230,8 +397,9 @@ pub struct Parameters<E: Engine> {
     pub h: Arc<Vec<E::G1Affine>>,
 
     // Elements of the form (beta * u_i(tau) + alpha v_i(tau) + w_i(tau)) / delta
-    // for all auxiliary inputs. Variables can never be unconstrained, so this
-    // never contains points at infinity.
+    // for all auxiliary inputs that aren't committed; the elements of the
+    // committed ones are in the commitment key. Variables can never be
+    // unconstrained, so this never contains points at infinity.
     pub l: Arc<Vec<E::G1Affine>>,
 
     // QAP "A" polynomials evaluated at tau in the Lagrange basis. Never contains
@@ -258,6 +426,8 @@ impl<E: Engine> PartialEq for Parameters<E> {
 }
 
 impl<E: Engine> Parameters<E> {
+    /// Writes the parameters, starting with the verifying key, whose magic and
+    /// version also cover the layout of the queries that follow it.
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         self.vk.write(&mut writer)?;
 
@@ -289,6 +459,8 @@ impl<E: Engine> Parameters<E> {
         Ok(())
     }
 
+    /// Reads parameters written by [`Self::write`], or by bellman 0.11, in
+    /// which case the verifying key has no commitment key.
     pub fn read<R: Read>(mut reader: R, checked: bool) -> io::Result<Self> {
         let read_g1 = |reader: &mut R| -> io::Result<E::G1Affine> {
             let mut repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
@@ -403,12 +575,136 @@ impl<E: Engine> Parameters<E> {
 pub struct PreparedVerifyingKey<E: MultiMillerLoop> {
     /// Pairing result of alpha*beta
     pub alpha_g1_beta_g2: E::Gt,
+    /// alpha in G1, from which `alpha_g1_beta_g2` is computed when reading
+    /// the key.
+    pub alpha_g1: E::G1Affine,
+    /// beta in G2, from which `alpha_g1_beta_g2` is computed when reading the
+    /// key.
+    pub beta_g2: E::G2Affine,
     /// -gamma in G2
     pub neg_gamma_g2: E::G2Prepared,
     /// -delta in G2
     pub neg_delta_g2: E::G2Prepared,
+    /// gamma in G2, from which `neg_gamma_g2` is prepared when reading the key.
+    pub gamma_g2: E::G2Affine,
+    /// delta in G2, from which `neg_delta_g2` is prepared when reading the key.
+    pub delta_g2: E::G2Affine,
     /// Copy of IC from `VerifiyingKey`.
     pub ic: Vec<E::G1Affine>,
+    /// The length of the commitment key of the `VerifyingKey`. Proofs for
+    /// keys without one must not carry a commitment.
+    pub commitment_key_len: usize,
+    /// -sigma in G2, against which the commitment's proof of knowledge is
+    /// checked.
+    pub neg_sigma_g2: E::G2Prepared,
+    /// sigma in G2, from which `neg_sigma_g2` is prepared when reading the key.
+    pub sigma_g2: E::G2Affine,
+    /// Fixed-base tables for the public-input elements of IC, if built by
+    /// `prepare_verifying_key_with_tables`.
+    pub ic_tables: Option<IcTables<E>>,
+}
+
+impl PreparedVerifyingKey<Bls12> {
+    /// Writes the key. `alpha_g1_beta_g2` is written as `alpha_g1` and
+    /// `beta_g2`, and only whether the key has IC tables is written;
+    /// [`Self::read`] rebuilds both.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.alpha_g1.to_uncompressed().as_ref())?;
+        writer.write_all(self.beta_g2.to_uncompressed().as_ref())?;
+        writer.write_all(self.gamma_g2.to_uncompressed().as_ref())?;
+        writer.write_all(self.delta_g2.to_uncompressed().as_ref())?;
+        writer.write_u32::<BigEndian>(self.ic.len() as u32)?;
+        for ic in &self.ic {
+            writer.write_all(ic.to_uncompressed().as_ref())?;
+        }
+        writer.write_u32::<BigEndian>(self.commitment_key_len as u32)?;
+        writer.write_all(self.sigma_g2.to_uncompressed().as_ref())?;
+        writer.write_u8(self.ic_tables.is_some() as u8)?;
+
+        Ok(())
+    }
+
+    /// Reads a key written by [`Self::write`], checking that every element
+    /// is valid and not the identity.
+    ///
+    /// `alpha_g1_beta_g2` is not stored, as `bls12_381` can neither encode a
+    /// `Gt` nor build one from bytes. It is recomputed from `alpha_g1` and
+    /// `beta_g2` instead, so reading a key costs one pairing.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let alpha_g1 = {
+            let mut g1_repr = [0u8; 96];
+            reader.read_exact(&mut g1_repr)?;
+
+            Option::from(G1Affine::from_uncompressed(&g1_repr))
+                .filter(|e: &G1Affine| !bool::from(e.is_identity()))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))?
+        };
+
+        let read_g2 = |reader: &mut R| -> io::Result<G2Affine> {
+            let mut g2_repr = [0u8; 192];
+            reader.read_exact(&mut g2_repr)?;
+
+            Option::from(G2Affine::from_uncompressed(&g2_repr))
+                .filter(|e: &G2Affine| !bool::from(e.is_identity()))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G2"))
+        };
+
+        let beta_g2 = read_g2(&mut reader)?;
+        let gamma_g2 = read_g2(&mut reader)?;
+        let delta_g2 = read_g2(&mut reader)?;
+
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        if len == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "empty IC"));
+        }
+        let mut ic = vec![];
+        for _ in 0..len {
+            let mut g1_repr = [0u8; 96];
+            reader.read_exact(&mut g1_repr)?;
+
+            let g1 = Option::from(G1Affine::from_uncompressed(&g1_repr))
+                .filter(|e: &G1Affine| !bool::from(e.is_identity()))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))?;
+            ic.push(g1);
+        }
+
+        let commitment_key_len = reader.read_u32::<BigEndian>()? as usize;
+        let sigma_g2 = {
+            let mut g2_repr = [0u8; 192];
+            reader.read_exact(&mut g2_repr)?;
+
+            // sigma is the identity exactly when there is no commitment key.
+            Option::from(G2Affine::from_uncompressed(&g2_repr))
+                .filter(|e: &G2Affine| bool::from(e.is_identity()) == (commitment_key_len == 0))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid sigma"))?
+        };
+
+        let ic_tables = match reader.read_u8()? {
+            0 => None,
+            1 => Some(IcTables::new(&ic[1..])),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid IC tables flag",
+                ))
+            }
+        };
+
+        Ok(PreparedVerifyingKey {
+            alpha_g1_beta_g2: pairing(&alpha_g1, &beta_g2),
+            alpha_g1,
+            beta_g2,
+            neg_gamma_g2: (-gamma_g2).into(),
+            neg_delta_g2: (-delta_g2).into(),
+            gamma_g2,
+            delta_g2,
+            ic,
+            commitment_key_len,
+            neg_sigma_g2: (-sigma_g2).into(),
+            sigma_g2,
+            ic_tables,
+        })
+    }
 }
 
 pub trait ParameterSource<E: Engine> {
@@ -529,17 +825,62 @@ mod test_with_bls12_381 {
             let mut v = vec![];
 
             params.write(&mut v).unwrap();
-            assert_eq!(v.len(), 2136);
+            assert_eq!(v.len(), 2629);
 
             let de_params = Parameters::read(&v[..], true).unwrap();
             assert!(params == de_params);
 
             let de_params = Parameters::read(&v[..], false).unwrap();
             assert!(params == de_params);
+
+            // Parameters written by bellman 0.11 have neither a magic nor a
+            // commitment key.
+            let mut vk = vec![];
+            params.vk.write(&mut vk).unwrap();
+            let mut legacy = vec![];
+            legacy.extend_from_slice(&params.vk.alpha_g1.to_uncompressed());
+            legacy.extend_from_slice(&params.vk.beta_g1.to_uncompressed());
+            legacy.extend_from_slice(&params.vk.beta_g2.to_uncompressed());
+            legacy.extend_from_slice(&params.vk.gamma_g2.to_uncompressed());
+            legacy.extend_from_slice(&params.vk.delta_g1.to_uncompressed());
+            legacy.extend_from_slice(&params.vk.delta_g2.to_uncompressed());
+            legacy.extend_from_slice(&(params.vk.ic.len() as u32).to_be_bytes());
+            for ic in &params.vk.ic {
+                legacy.extend_from_slice(&ic.to_uncompressed());
+            }
+            legacy.extend_from_slice(&v[vk.len()..]);
+            assert!(Parameters::read(&legacy[..], true).unwrap() == params);
+
+            let mut unknown = v.clone();
+            unknown[4] = 2;
+            assert!(Parameters::<Bls12>::read(&unknown[..], true).is_err());
         }
 
         let pvk = prepare_verifying_key::<Bls12>(&params.vk);
 
+        let de_pvk = {
+            let pvk = prepare_verifying_key_with_tables::<Bls12>(&params.vk);
+            let mut v = vec![];
+
+            pvk.write(&mut v).unwrap();
+            assert_eq!(v.len(), 1065);
+
+            let de_pvk = PreparedVerifyingKey::<Bls12>::read(&v[..]).unwrap();
+            assert!(de_pvk.alpha_g1_beta_g2 == pvk.alpha_g1_beta_g2);
+            assert!(de_pvk.gamma_g2 == params.vk.gamma_g2);
+            assert!(de_pvk.delta_g2 == params.vk.delta_g2);
+            assert!(de_pvk.ic == params.vk.ic);
+            assert_eq!(de_pvk.commitment_key_len, 0);
+            assert!(de_pvk.ic_tables.is_some());
+
+            let mut corrupted = v.clone();
+            corrupted[0] ^= 1;
+            assert!(PreparedVerifyingKey::<Bls12>::read(&corrupted[..]).is_err());
+            assert!(PreparedVerifyingKey::<Bls12>::read(&v[..v.len() - 1]).is_err());
+
+            de_pvk
+        };
+
         for _ in 0..100 {
             let a = Scalar::random(&mut rng);
             let b = Scalar::random(&mut rng);
@@ -559,13 +900,26 @@ mod test_with_bls12_381 {
             let mut v = vec![];
             proof.write(&mut v).unwrap();
 
-            assert_eq!(v.len(), 192);
+            assert_eq!(v.len(), 293);
 
             let de_proof = Proof::read(&v[..]).unwrap();
             assert!(proof == de_proof);
 
+            // Proofs written by bellman 0.11 are A, B and C without a magic.
+            let de_proof = Proof::read(&v[5..197]).unwrap();
+            assert!(proof == de_proof);
+
             assert!(verify_proof(&pvk, &proof, &[c]).is_ok());
             assert!(verify_proof(&pvk, &proof, &[a]).is_err());
+            assert!(verify_proof(&de_pvk, &proof, &[c]).is_ok());
+            assert!(verify_proof(&de_pvk, &proof, &[a]).is_err());
+
+            // Without a commitment key, a commitment can't shift the inputs.
+            let forged = Proof {
+                d: G1Affine::from(params.vk.ic[1] * (c - a)),
+                ..proof.clone()
+            };
+            assert!(verify_proof(&pvk, &forged, &[a]).is_err());
         }
     }
 }
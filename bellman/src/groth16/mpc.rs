@@ -1,4 +1,6 @@
 use rand_core::RngCore;
+use std::error::Error;
+use std::fmt;
 use std::ops::{AddAssign, MulAssign};
 use std::sync::Arc;
 
@@ -10,11 +12,43 @@ use super::{Parameters, VerifyingKey};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
-use crate::domain::{EvaluationDomain, Scalar};
+use crate::domain::{EvaluationDomain, Point, Scalar};
 
 use crate::multicore::Worker;
 
 use bls12_381::Bls12;
+
+pub mod checkpoint;
+pub mod progress;
+
+use self::progress::{CancellationToken, Cancelled, Phase, Progress, ProgressObserver};
+
+/// Why verifying a contribution did not produce a new ceremony state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The contribution does not follow from the previous state.
+    InvalidContribution,
+    /// The verification was stopped through its [`CancellationToken`].
+    Cancelled,
+}
+
+impl Error for VerificationError {}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            VerificationError::InvalidContribution => f.write_str("invalid contribution"),
+            VerificationError::Cancelled => Cancelled.fmt(f),
+        }
+    }
+}
+
+impl From<Cancelled> for VerificationError {
+    fn from(_: Cancelled) -> VerificationError {
+        VerificationError::Cancelled
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct ParameterPair<E>
 where
@@ -40,6 +74,10 @@ where
     fn get_g2(&self) -> E::G2 {
         self.g2_result.unwrap()
     }
+    /// Whether the pair carries every element a verifier reads.
+    fn is_complete(&self) -> bool {
+        self.g1_result.is_some() && self.g2_result.is_some() && self.g2_mine.is_some()
+    }
 }
 
 pub fn initParameterList<E>() -> Vec<ParameterPair<E>>
@@ -60,7 +98,7 @@ where
 pub fn ParamterListExcute<E: Engine>(
     mut vec: Vec<ParameterPair<E>>,
     p: ParameterPair<E>,
-) -> Vec<ParameterPair<E>>
+) -> Result<Vec<ParameterPair<E>>, VerificationError>
 where
     E: Engine,
     E::G1: WnafGroup,
@@ -75,12 +113,13 @@ where
     let x = p.clone();
     let len = vec.len();
     if (len != 0) {
-        let b = verify_mpc_g1(&p, &vec);
-        assert_eq!(b, true);
+        if !verify_mpc_g1(&p, &vec) {
+            return Err(VerificationError::InvalidContribution);
+        }
         newp = x;
     }
     vec.push(newp);
-    return vec;
+    return Ok(vec);
 }
 pub fn mpc_common_paramters_custom_generator<E>(
     paramter_last: &ParameterPair<E>,
@@ -301,16 +340,17 @@ where
 pub fn TauParamterListExcute<E>(
     mut vec: Vec<TauParameterPair<E>>,
     p: TauParameterPair<E>,
-) -> Vec<TauParameterPair<E>>
+) -> Result<Vec<TauParameterPair<E>>, VerificationError>
 where
     E: Engine,
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
-    let b = verify_mpc_x(&p, &vec);
-    assert_eq!(b, true);
+    if !verify_mpc_x(&p, &vec) {
+        return Err(VerificationError::InvalidContribution);
+    }
     vec.push(p);
-    return vec;
+    return Ok(vec);
 }
 
 pub fn verify_x_pow<E>(new_xparamter: &TauParameterPair<E>) -> bool
@@ -456,6 +496,46 @@ where
     (result_g1, result_g2)
 }
 
+/// The A, B and C columns of a set of variables, as kept by the keypair
+/// assembly: for each variable, its coefficients and their constraints.
+type Columns<'a, S> = (
+    &'a [Vec<(S, usize)>],
+    &'a [Vec<(S, usize)>],
+    &'a [Vec<(S, usize)>],
+);
+
+/// Moves `tau^0, ..., tau^(m - 1)` in a group, or a multiple of them, to the
+/// Lagrange basis `L_0(tau), ..., L_(m - 1)(tau)` of the evaluation domain of
+/// size `m`.
+fn lagrange_powers<G: Group>(
+    powers: &[G],
+    m: usize,
+    worker: &Worker,
+) -> Result<Vec<G>, SynthesisError> {
+    if powers.len() < m {
+        return Err(SynthesisError::PolynomialDegreeTooLarge);
+    }
+
+    let mut domain = EvaluationDomain::<G::Scalar, Point<G>>::from_coeffs(
+        powers[..m].iter().map(|&p| Point(p)).collect(),
+    )?;
+    domain.ifft(worker);
+    Ok(domain.into_coeffs().into_iter().map(|p| p.0).collect())
+}
+
+/// Evaluates a QAP column at tau, given the Lagrange basis at tau.
+fn lagrange_eval<G: Group>(
+    lagrange: &[G],
+    column: &[(G::Scalar, usize)],
+) -> Result<G, SynthesisError> {
+    column.iter().try_fold(G::identity(), |acc, &(coeff, j)| {
+        let l = lagrange
+            .get(j)
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+        Ok(acc + *l * coeff)
+    })
+}
+
 impl<E> CommonParamterInStorage<E>
 where
     E: Engine,
@@ -471,12 +551,13 @@ where
         num_inputs: usize,
         num_aux: usize,
         num_constraints: usize,
-    ) -> CommonParamterMatrix<E>
+    ) -> Result<CommonParamterMatrix<E>, SynthesisError>
     where
         E: Engine,
         E::G1: WnafGroup,
         E::G2: WnafGroup,
     {
+        self.check_powers(num_inputs, num_aux)?;
         let g1 = E::G1::generator();
         let g2 = E::G2::generator();
         let g1_indentity = g1 * E::Fr::from(0);
@@ -522,8 +603,6 @@ where
         let mut matrixed_g2_front = vec![g2_indentity; num_aux];
         let mut matrixed_g1_back = vec![g1_indentity; num_inputs];
         let mut matrixed_g2_back = vec![g2_indentity; num_inputs];
-        let mut matrixed_h_g1 = vec![g1_indentity; num_constraints];
-        let mut matrixed_h_g2 = vec![g2_indentity; num_constraints];
         for i in 0..num_aux {
             matrixed_g1_front[i] +=
                 alpha_matrix_g1_front[i] + beta_matrix_g1_front[i] + tau_matrix_g1_front[i];
@@ -537,110 +616,193 @@ where
             matrixed_g2_back[i] +=
                 alpha_matrix_g2_back[i] + beta_matrix_g2_back[i] + tau_matrix_g2_back[i];
         }
-        //存疑,m，l，n值的选取
-        for i in 0..num_constraints {
-            assert_eq!(self.tau_g1[0].to_affine(), E::G1::generator().to_affine());
-            matrixed_h_g1[i] = self.tau_g1[num_constraints + i] - self.tau_g1[i];
-            matrixed_h_g2[i] = self.tau_g2[num_constraints + i] - self.tau_g2[i];
-        }
-        CommonParamterMatrix {
+        let (matrixed_h_g1, matrixed_h_g2) = self.h_query(num_constraints)?;
+        Ok(CommonParamterMatrix {
+            a_g1: vec![],
+            b_g1: vec![],
+            b_g2: vec![],
             matrixed_g1_front,
             matrixed_g2_front,
             matrixed_g1_back,
             matrixed_g2_back,
             matrixed_h_g1,
             matrixed_h_g2,
-        }
+        })
     }
 
-    //matrix需要改，tau列表长度一定大于num_aux和num_inputs的列数，这时候怎么乘
+    /// Evaluates the QAP polynomials of a circuit, given by the columns of its
+    /// input and auxiliary variables, at tau in the exponent.
+    ///
+    /// The powers of tau, and their alpha and beta multiples, are first moved
+    /// to the Lagrange basis of the evaluation domain for `num_constraints`
+    /// constraints, which is the basis [`generate_parameters`] evaluates the
+    /// columns in. The A and B queries and the `beta * A + alpha * B + C`
+    /// elements of the inputs (the front, for IC) and of the auxiliary
+    /// variables (the back, for L) then follow like in `generate_parameters`,
+    /// before the division by gamma or delta.
+    ///
+    /// [`generate_parameters`]: crate::groth16::generate_parameters
+    #[allow(clippy::too_many_arguments)]
     pub fn matrix(
         &self,
-        at_aux: &Vec<Vec<(E::Fr, usize)>>,
-        bt_aux: &Vec<Vec<(E::Fr, usize)>>,
-        ct_aux: &Vec<Vec<(E::Fr, usize)>>,
-        num_inputs: usize,
-        num_aux: usize,
+        at_inputs: &[Vec<(E::Fr, usize)>],
+        bt_inputs: &[Vec<(E::Fr, usize)>],
+        ct_inputs: &[Vec<(E::Fr, usize)>],
+        at_aux: &[Vec<(E::Fr, usize)>],
+        bt_aux: &[Vec<(E::Fr, usize)>],
+        ct_aux: &[Vec<(E::Fr, usize)>],
         num_constraints: usize,
-    ) -> CommonParamterMatrix<E>
+    ) -> Result<CommonParamterMatrix<E>, SynthesisError>
     where
         E: Engine,
         E::G1: WnafGroup,
         E::G2: WnafGroup,
     {
-        let (alpha_matrix_g1, alpha_matrix_g2) = list_mul_matrix::<E>(
-            &self.alpha_mul_tau_g1[0..num_aux],
-            &self.alpha_mul_tau_g2[0..num_aux],
-            bt_aux,
-        );
-        let (beta_matrix_g1, beta_matrix_g2) = list_mul_matrix::<E>(
-            &self.beta_mul_tau_g1[num_aux..num_inputs + num_aux],
-            &self.beta_mul_tau_g2[num_aux..num_inputs + num_aux],
-            at_aux,
-        );
-        let (tau_matrix_g1, tau_matrix_g2) =
-            list_mul_matrix::<E>(&self.tau_g1, &self.tau_g2, ct_aux);
-        let g1 = E::G1::generator();
-        let g2 = E::G2::generator();
-        let g1_indentity = g1 * E::Fr::from(0);
-        let g2_indentity = g2 * E::Fr::from(0);
-        let alpha_len = alpha_matrix_g1.len();
-        let beta_len = beta_matrix_g1.len();
-        let tau_len = tau_matrix_g1.len();
-        let mut matrixed_g1_front = Vec::new();
-        let mut matrixed_g2_front = Vec::new();
-        let mut matrixed_g1_back = Vec::new();
-        let mut matrixed_g2_back = Vec::new();
-        let mut matrixed_h_g1 = Vec::new();
-        let mut matrixed_h_g2 = Vec::new();
-
-        for i in 0..num_aux + num_inputs {
-            if i < num_aux {
-                matrixed_g1_front.push(g1_indentity);
-                matrixed_g2_front.push(g2_indentity);
-                if i < alpha_len {
-                    matrixed_g1_front[i] += alpha_matrix_g1[i];
-                    matrixed_g2_front[i] += alpha_matrix_g2[i];
-                }
-                if i < beta_len {
-                    matrixed_g1_front[i] += beta_matrix_g1[i];
-                    matrixed_g2_front[i] += beta_matrix_g2[i];
-                }
-                if i < tau_len {
-                    matrixed_g1_front[i] += tau_matrix_g1[i];
-                    matrixed_g2_front[i] += tau_matrix_g2[i];
-                }
+        self.matrix_inner(
+            (at_inputs, bt_inputs, ct_inputs),
+            (at_aux, bt_aux, ct_aux),
+            num_constraints,
+            &mut Progress::silent(Phase::Matrix),
+        )
+    }
+
+    /// Like [`CommonParamterInStorage::matrix`], reporting every processed
+    /// variable to `observer` and stopping once `cancel` is triggered.
+    #[allow(clippy::too_many_arguments)]
+    pub fn matrix_with_progress(
+        &self,
+        at_inputs: &[Vec<(E::Fr, usize)>],
+        bt_inputs: &[Vec<(E::Fr, usize)>],
+        ct_inputs: &[Vec<(E::Fr, usize)>],
+        at_aux: &[Vec<(E::Fr, usize)>],
+        bt_aux: &[Vec<(E::Fr, usize)>],
+        ct_aux: &[Vec<(E::Fr, usize)>],
+        num_constraints: usize,
+        observer: &mut dyn ProgressObserver,
+        cancel: &CancellationToken,
+    ) -> Result<CommonParamterMatrix<E>, SynthesisError> {
+        self.matrix_inner(
+            (at_inputs, bt_inputs, ct_inputs),
+            (at_aux, bt_aux, ct_aux),
+            num_constraints,
+            &mut Progress::new(Phase::Matrix, observer, cancel),
+        )
+    }
+
+    fn matrix_inner(
+        &self,
+        inputs: Columns<'_, E::Fr>,
+        aux: Columns<'_, E::Fr>,
+        num_constraints: usize,
+        progress: &mut Progress<'_>,
+    ) -> Result<CommonParamterMatrix<E>, SynthesisError> {
+        let (matrixed_h_g1, matrixed_h_g2) = self.h_query(num_constraints)?;
+        let m = matrixed_h_g1.len() + 1;
+        let num_inputs = inputs.0.len();
+        let num_variables = num_inputs + aux.0.len();
+        // One step for the H query and the Lagrange basis, and one per
+        // variable.
+        progress.start(num_variables + 1)?;
+
+        let worker = Worker::new();
+        let tau_g1 = lagrange_powers(&self.tau_g1, m, &worker)?;
+        let tau_g2 = lagrange_powers(&self.tau_g2, m, &worker)?;
+        let alpha_g1 = lagrange_powers(&self.alpha_mul_tau_g1, m, &worker)?;
+        let alpha_g2 = lagrange_powers(&self.alpha_mul_tau_g2, m, &worker)?;
+        let beta_g1 = lagrange_powers(&self.beta_mul_tau_g1, m, &worker)?;
+        let beta_g2 = lagrange_powers(&self.beta_mul_tau_g2, m, &worker)?;
+        progress.step(1)?;
+
+        let mut a_g1 = Vec::with_capacity(num_variables);
+        let mut b_g1 = Vec::with_capacity(num_variables);
+        let mut b_g2 = Vec::with_capacity(num_variables);
+        let mut matrixed_g1_front = Vec::with_capacity(num_inputs);
+        let mut matrixed_g2_front = Vec::with_capacity(num_inputs);
+        let mut matrixed_g1_back = Vec::with_capacity(num_variables - num_inputs);
+        let mut matrixed_g2_back = Vec::with_capacity(num_variables - num_inputs);
+
+        let columns = inputs
+            .0
+            .iter()
+            .zip(inputs.1.iter())
+            .zip(inputs.2.iter())
+            .chain(aux.0.iter().zip(aux.1.iter()).zip(aux.2.iter()));
+        for (i, ((at, bt), ct)) in columns.enumerate() {
+            a_g1.push(lagrange_eval(&tau_g1, at)?);
+            b_g1.push(lagrange_eval(&tau_g1, bt)?);
+            b_g2.push(lagrange_eval(&tau_g2, bt)?);
+
+            let k_g1 = lagrange_eval(&beta_g1, at)?
+                + lagrange_eval(&alpha_g1, bt)?
+                + lagrange_eval(&tau_g1, ct)?;
+            let k_g2 = lagrange_eval(&beta_g2, at)?
+                + lagrange_eval(&alpha_g2, bt)?
+                + lagrange_eval(&tau_g2, ct)?;
+            if i < num_inputs {
+                matrixed_g1_front.push(k_g1);
+                matrixed_g2_front.push(k_g2);
             } else {
-                matrixed_g1_back.push(g1_indentity);
-                matrixed_g2_back.push(g2_indentity);
-                if i < alpha_len {
-                    matrixed_g1_back[i - num_aux] += alpha_matrix_g1[i];
-                    matrixed_g2_back[i - num_aux] += alpha_matrix_g2[i];
-                }
-                if i < beta_len {
-                    matrixed_g1_back[i - num_aux] += beta_matrix_g1[i];
-                    matrixed_g2_back[i - num_aux] += beta_matrix_g2[i];
-                }
-                if i < tau_len {
-                    matrixed_g1_back[i - num_aux] += tau_matrix_g1[i];
-                    matrixed_g2_back[i - num_aux] += tau_matrix_g2[i];
-                }
+                matrixed_g1_back.push(k_g1);
+                matrixed_g2_back.push(k_g2);
             }
+            progress.step(1)?;
         }
-        //存疑,m，l，n值的选取
-        for i in 0..num_constraints {
-            assert_eq!(self.tau_g1[0].to_affine(), E::G1::generator().to_affine());
-            matrixed_h_g1.push(self.tau_g1[num_constraints + i] - self.tau_g1[i]);
-            matrixed_h_g2.push(self.tau_g2[num_constraints + i] - self.tau_g2[i]);
-        }
-        CommonParamterMatrix {
+
+        Ok(CommonParamterMatrix {
+            a_g1,
+            b_g1,
+            b_g2,
             matrixed_g1_front,
             matrixed_g2_front,
             matrixed_g1_back,
             matrixed_g2_back,
             matrixed_h_g1,
             matrixed_h_g2,
+        })
+    }
+
+    /// Checks that there are enough alpha and beta multiples of tau for
+    /// `num_inputs + num_aux` variables.
+    fn check_powers(&self, num_inputs: usize, num_aux: usize) -> Result<(), SynthesisError> {
+        let len = num_inputs + num_aux;
+        if self.alpha_mul_tau_g1.len() < len
+            || self.alpha_mul_tau_g2.len() < len
+            || self.beta_mul_tau_g1.len() < len
+            || self.beta_mul_tau_g2.len() < len
+        {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
         }
+
+        Ok(())
+    }
+
+    /// Computes the H query `tau^i * t(tau)` for `i` in `0..m - 1`, where `m`
+    /// is the size of the evaluation domain for `num_constraints` constraints
+    /// and `t(x) = x^m - 1` is its vanishing polynomial. This is the same
+    /// domain `EvaluationDomain` picks when proving, so the query lines up
+    /// with the quotient polynomial computed by the prover.
+    fn h_query(&self, num_constraints: usize) -> Result<(Vec<E::G1>, Vec<E::G2>), SynthesisError> {
+        let coeffs = vec![Scalar(E::Fr::zero()); num_constraints];
+        let domain = EvaluationDomain::<E::Fr, Scalar<E::Fr>>::from_coeffs(coeffs)?;
+        let m = domain.as_ref().len();
+
+        // tau^i * (tau^m - 1) = tau^(m + i) - tau^i needs powers up to
+        // tau^(2m - 2).
+        if self.tau_g1.len() < 2 * m - 1 || self.tau_g2.len() < 2 * m - 1 {
+            return Err(SynthesisError::PolynomialDegreeTooLarge);
+        }
+        if self.tau_g1[0] != E::G1::generator() || self.tau_g2[0] != E::G2::generator() {
+            return Err(SynthesisError::MalformedCeremonyState);
+        }
+
+        let h_g1 = (0..m - 1)
+            .map(|i| self.tau_g1[m + i] - self.tau_g1[i])
+            .collect();
+        let h_g2 = (0..m - 1)
+            .map(|i| self.tau_g2[m + i] - self.tau_g2[i])
+            .collect();
+
+        Ok((h_g1, h_g2))
     }
 }
 
@@ -681,6 +843,30 @@ pub fn make_new_tau_paramter<E>(
     pointg2_list: &Vec<E::G2>,
     invert: bool,
 ) -> TauParameterPair<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    make_new_tau_paramter_inner(
+        a,
+        x,
+        pointg1_list,
+        pointg2_list,
+        invert,
+        &mut Progress::silent(Phase::CommonContribution),
+    )
+    .expect("silent progress is never cancelled")
+}
+
+fn make_new_tau_paramter_inner<E>(
+    a: &u64,
+    x: &u64,
+    pointg1_list: &Vec<E::G1>,
+    pointg2_list: &Vec<E::G2>,
+    invert: bool,
+    progress: &mut Progress<'_>,
+) -> Result<TauParameterPair<E>, Cancelled>
 where
     E: Engine,
     E::G1: WnafGroup,
@@ -701,8 +887,9 @@ where
             &base_g2,
             invert,
         ));
+        progress.step(1)?;
     }
-    TauParameterPair { list }
+    Ok(TauParameterPair { list })
 }
 
 pub fn initial_common_paramters<E>(len: usize) -> CommonParamterInStorage<E>
@@ -729,13 +916,54 @@ where
 
 pub fn mpc_common_paramters_generator<E>(
     storage: &CommonParamterInStorage<E>,
-    (alpha, beta, tau): (u64, u64, u64),
+    secret: (u64, u64, u64),
 ) -> CommonParamter<E>
 where
     E: Engine,
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
+    mpc_common_paramters_generator_inner(
+        storage,
+        secret,
+        &mut Progress::silent(Phase::CommonContribution),
+    )
+    .expect("silent progress is never cancelled")
+}
+
+/// Like [`mpc_common_paramters_generator`], reporting every contributed
+/// element to `observer` and stopping once `cancel` is triggered.
+pub fn mpc_common_paramters_generator_with_progress<E>(
+    storage: &CommonParamterInStorage<E>,
+    secret: (u64, u64, u64),
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> Result<CommonParamter<E>, Cancelled>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    mpc_common_paramters_generator_inner(
+        storage,
+        secret,
+        &mut Progress::new(Phase::CommonContribution, observer, cancel),
+    )
+}
+
+fn mpc_common_paramters_generator_inner<E>(
+    storage: &CommonParamterInStorage<E>,
+    (alpha, beta, tau): (u64, u64, u64),
+    progress: &mut Progress<'_>,
+) -> Result<CommonParamter<E>, Cancelled>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    progress.start(
+        2 + storage.tau_g1.len() + storage.alpha_mul_tau_g1.len() + storage.beta_mul_tau_g1.len(),
+    )?;
     let g1 = E::G1::generator();
     let g2 = E::G2::generator();
     //从common里拿出alpha数据，计算本次alpha
@@ -747,62 +975,80 @@ where
         &g2,
         false,
     );
+    progress.step(1)?;
 
     //从common里拿出alpha数据，计算本次beta
     let new_beta =
         make_new_paramter::<E>(&beta, &storage.beta_g1, &storage.beta_g2, &g1, &g2, false);
+    progress.step(1)?;
 
     //从common里拿出x数据，计算本次x[]
-    let new_tau = make_new_tau_paramter::<E>(&1, &tau, &storage.tau_g1, &storage.tau_g2, false);
+    let new_tau = make_new_tau_paramter_inner::<E>(
+        &1,
+        &tau,
+        &storage.tau_g1,
+        &storage.tau_g2,
+        false,
+        progress,
+    )?;
 
     //计算alpha*x[]
-    let new_alpha_mul_tau = make_new_tau_paramter::<E>(
+    let new_alpha_mul_tau = make_new_tau_paramter_inner::<E>(
         //&(alpha * tau),
         &alpha,
         &tau,
         &storage.alpha_mul_tau_g1,
         &storage.alpha_mul_tau_g2,
         false,
-    );
+        progress,
+    )?;
     //计算beta*x[]
 
-    let new_beta_mul_tau = make_new_tau_paramter::<E>(
+    let new_beta_mul_tau = make_new_tau_paramter_inner::<E>(
         //&(beta * tau),
         &beta,
         &tau,
         &storage.beta_mul_tau_g1,
         &storage.beta_mul_tau_g2,
         false,
-    );
+        progress,
+    )?;
     //return
-    CommonParamter {
+    Ok(CommonParamter {
         alpha: new_alpha,
         beta: new_beta,
         tau: new_tau,
         alpha_mul_tau: new_alpha_mul_tau,
         beta_mul_tau: new_beta_mul_tau,
-    }
+    })
 }
 
+/// Returns `false` if `paramter` is missing one of the elements it is checked
+/// through.
 pub fn verify_new_paramter<E>(paramter: &ParameterPair<E>, baseg1: &E::G1, baseg2: &E::G2) -> bool
 where
     E: Engine,
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
-    E::pairing(
-        &paramter.g1_result.unwrap().to_affine(),
-        &E::G2Affine::generator(),
-    ) == E::pairing(&baseg1.to_affine(), &paramter.g2_mine.unwrap().to_affine())
-        && E::pairing(
-            &paramter.g1_result.unwrap().to_affine(),
-            &E::G2Affine::generator(),
-        ) == E::pairing(
-            &E::G1Affine::generator(),
-            &paramter.g2_result.unwrap().to_affine(),
-        )
+    let (g1_result, g2_result, g2_mine) =
+        match (paramter.g1_result, paramter.g2_result, paramter.g2_mine) {
+            (Some(g1_result), Some(g2_result), Some(g2_mine)) => (g1_result, g2_result, g2_mine),
+            _ => return false,
+        };
+    E::pairing(&g1_result.to_affine(), &E::G2Affine::generator())
+        == E::pairing(&baseg1.to_affine(), &g2_mine.to_affine())
+        && E::pairing(&g1_result.to_affine(), &E::G2Affine::generator())
+            == E::pairing(&E::G1Affine::generator(), &g2_result.to_affine())
 }
 
+/// Verifies `new_paramter` against `storage` and returns the new state.
+///
+/// # Panics
+///
+/// Panics if the contribution is invalid; use
+/// [`verify_common_paramter_with_progress`] to get a [`VerificationError`]
+/// instead.
 pub fn verify_common_paramter<E>(
     storage: &CommonParamterInStorage<E>,
     new_paramter: &CommonParamter<E>,
@@ -812,15 +1058,78 @@ where
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
-    let len = new_paramter.tau.list.len();
-    let mut result = (len == new_paramter.alpha_mul_tau.list.len())
-        && (new_paramter.beta_mul_tau.list.len() == len);
+    verify_common_paramter_inner(
+        storage,
+        new_paramter,
+        &mut Progress::silent(Phase::CommonVerification),
+    )
+    .expect("contribution failed verification")
+}
+
+/// Like [`verify_common_paramter`], reporting every verified element to
+/// `observer` and stopping once `cancel` is triggered.
+///
+/// Unlike the plain variant, which panics, an invalid contribution is
+/// reported as [`VerificationError::InvalidContribution`].
+pub fn verify_common_paramter_with_progress<E>(
+    storage: &CommonParamterInStorage<E>,
+    new_paramter: &CommonParamter<E>,
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> Result<CommonParamterInStorage<E>, VerificationError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    verify_common_paramter_inner(
+        storage,
+        new_paramter,
+        &mut Progress::new(Phase::CommonVerification, observer, cancel),
+    )
+}
+
+fn verify_common_paramter_inner<E>(
+    storage: &CommonParamterInStorage<E>,
+    new_paramter: &CommonParamter<E>,
+    progress: &mut Progress<'_>,
+) -> Result<CommonParamterInStorage<E>, VerificationError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    // Every list has to match the state it extends, and every pair has to be
+    // complete, before anything is indexed or unwrapped.
+    let len = storage.tau_g1.len();
+    let lists = [
+        &new_paramter.tau,
+        &new_paramter.alpha_mul_tau,
+        &new_paramter.beta_mul_tau,
+    ];
+    let well_formed = storage.tau_g2.len() == len
+        && storage.alpha_mul_tau_g1.len() == len
+        && storage.alpha_mul_tau_g2.len() == len
+        && storage.beta_mul_tau_g1.len() == len
+        && storage.beta_mul_tau_g2.len() == len
+        && new_paramter.alpha.is_complete()
+        && new_paramter.beta.is_complete()
+        && lists
+            .iter()
+            .all(|list| list.list.len() == len && list.list.iter().all(ParameterPair::is_complete));
+    if !well_formed {
+        return Err(VerificationError::InvalidContribution);
+    }
+
+    progress.start(2 + len)?;
     //从stoarge和新参数验证alpha
     let result_alpha =
         verify_new_paramter(&new_paramter.alpha, &storage.alpha_g1, &storage.alpha_g2);
+    progress.step(1)?;
 
     //从stoarge和新参数验证beta
     let result_beta = verify_new_paramter(&new_paramter.beta, &storage.beta_g1, &storage.beta_g2);
+    progress.step(1)?;
 
     let mut result_tau = true;
     let mut result_alpha_tau = true;
@@ -852,13 +1161,13 @@ where
                 &storage.beta_mul_tau_g1[i],
                 &storage.beta_mul_tau_g2[i],
             );
+        progress.step(1)?;
     }
 
-    assert_eq!(
-        true,
-        result && result_alpha && result_beta && result_tau && result_alpha_tau && result_beta_tau
-    );
-    new_paramter.to_storage_format()
+    if !(result_alpha && result_beta && result_tau && result_alpha_tau && result_beta_tau) {
+        return Err(VerificationError::InvalidContribution);
+    }
+    Ok(new_paramter.to_storage_format())
 }
 
 pub fn mpc_common_paramters_custom_all<E>() -> CommonParamterInStorage<E>
@@ -940,6 +1249,12 @@ where
     pub h_g1: Vec<E::G1>,
     pub h_g2: Vec<E::G2>,
 }
+/// A circuit's QAP polynomials evaluated at tau in the exponent, as
+/// computed by [`CommonParamterInStorage::matrix`].
+///
+/// The A and B queries hold one element per variable, inputs first, and are
+/// not filtered of the identity. The front holds `beta * A + alpha * B + C`
+/// of every input and the back that of every auxiliary variable.
 #[derive(Debug, Clone)]
 pub struct CommonParamterMatrix<E>
 where
@@ -947,6 +1262,9 @@ where
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
+    pub a_g1: Vec<E::G1>,
+    pub b_g1: Vec<E::G1>,
+    pub b_g2: Vec<E::G2>,
     pub matrixed_g1_front: Vec<E::G1>,
     pub matrixed_g2_front: Vec<E::G2>,
     pub matrixed_g1_back: Vec<E::G1>,
@@ -1016,13 +1334,52 @@ where
 
 pub fn mpc_uncommon_paramters_generator<E>(
     storage: &UnCommonParamterInStorage<E>,
-    (gamma, delta): (u64, u64),
+    secret: (u64, u64),
 ) -> UnCommonParamter<E>
 where
     E: Engine,
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
+    mpc_uncommon_paramters_generator_inner(
+        storage,
+        secret,
+        &mut Progress::silent(Phase::UncommonContribution),
+    )
+    .expect("silent progress is never cancelled")
+}
+
+/// Like [`mpc_uncommon_paramters_generator`], reporting every contributed
+/// element to `observer` and stopping once `cancel` is triggered.
+pub fn mpc_uncommon_paramters_generator_with_progress<E>(
+    storage: &UnCommonParamterInStorage<E>,
+    secret: (u64, u64),
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> Result<UnCommonParamter<E>, Cancelled>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    mpc_uncommon_paramters_generator_inner(
+        storage,
+        secret,
+        &mut Progress::new(Phase::UncommonContribution, observer, cancel),
+    )
+}
+
+fn mpc_uncommon_paramters_generator_inner<E>(
+    storage: &UnCommonParamterInStorage<E>,
+    (gamma, delta): (u64, u64),
+    progress: &mut Progress<'_>,
+) -> Result<UnCommonParamter<E>, Cancelled>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    progress.start(2 + storage.kin_g1.len() + storage.kout_g1.len() + storage.h_g1.len())?;
     let g1 = E::G1::generator();
     let g2 = E::G2::generator();
     //从uncommon里拿出delta数据，计算本次delta
@@ -1034,6 +1391,7 @@ where
         &g2,
         false,
     );
+    progress.step(1)?;
 
     //从uncommon里拿出gamma数据，计算本次gamma
     let new_gamma = make_new_paramter::<E>(
@@ -1044,24 +1402,47 @@ where
         &g2,
         false,
     );
+    progress.step(1)?;
 
     //从uncommon里拿出Kin数据，计算本次Kin
-    let new_kin = make_new_tau_paramter::<E>(&gamma, &1, &storage.kin_g1, &storage.kin_g2, true);
+    let new_kin = make_new_tau_paramter_inner::<E>(
+        &gamma,
+        &1,
+        &storage.kin_g1,
+        &storage.kin_g2,
+        true,
+        progress,
+    )?;
 
     //从uncommon里拿出Kin数据，计算本次Kin
-    let new_kout = make_new_tau_paramter::<E>(&delta, &1, &storage.kout_g1, &storage.kout_g2, true);
+    let new_kout = make_new_tau_paramter_inner::<E>(
+        &delta,
+        &1,
+        &storage.kout_g1,
+        &storage.kout_g2,
+        true,
+        progress,
+    )?;
 
-    let new_h = make_new_tau_paramter::<E>(&delta, &1, &storage.h_g1, &storage.h_g2, true);
+    let new_h =
+        make_new_tau_paramter_inner::<E>(&delta, &1, &storage.h_g1, &storage.h_g2, true, progress)?;
 
-    UnCommonParamter {
+    Ok(UnCommonParamter {
         gamma: new_gamma,
         delta: new_delta,
         ic: new_kin,
         l: new_kout,
         h: new_h,
-    }
+    })
 }
 
+/// Verifies `new_paramter` against `storage` and returns the new state.
+///
+/// # Panics
+///
+/// Panics if the contribution is invalid; use
+/// [`verify_uncommon_paramter_with_progress`] to get a [`VerificationError`]
+/// instead.
 pub fn verify_uncommon_paramter<E>(
     common_paramter_matrix: &CommonParamterMatrix<E>,
     storage: &UnCommonParamterInStorage<E>,
@@ -1072,6 +1453,87 @@ where
     E::G1: WnafGroup,
     E::G2: WnafGroup,
 {
+    verify_uncommon_paramter_inner(
+        common_paramter_matrix,
+        storage,
+        new_paramter,
+        &mut Progress::silent(Phase::UncommonVerification),
+    )
+    .expect("contribution failed verification")
+}
+
+/// Like [`verify_uncommon_paramter`], reporting every verified element to
+/// `observer` and stopping once `cancel` is triggered.
+///
+/// Unlike the plain variant, which panics, an invalid contribution is
+/// reported as [`VerificationError::InvalidContribution`].
+pub fn verify_uncommon_paramter_with_progress<E>(
+    common_paramter_matrix: &CommonParamterMatrix<E>,
+    storage: &UnCommonParamterInStorage<E>,
+    new_paramter: &UnCommonParamter<E>,
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> Result<UnCommonParamterInStorage<E>, VerificationError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    verify_uncommon_paramter_inner(
+        common_paramter_matrix,
+        storage,
+        new_paramter,
+        &mut Progress::new(Phase::UncommonVerification, observer, cancel),
+    )
+}
+
+fn verify_uncommon_paramter_inner<E>(
+    common_paramter_matrix: &CommonParamterMatrix<E>,
+    storage: &UnCommonParamterInStorage<E>,
+    new_paramter: &UnCommonParamter<E>,
+    progress: &mut Progress<'_>,
+) -> Result<UnCommonParamterInStorage<E>, VerificationError>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    // Every list has to match the state it extends and the circuit matrix,
+    // and every pair has to be complete, before anything is indexed or
+    // unwrapped.
+    let sections = [
+        (
+            &new_paramter.ic,
+            storage.kin_g1.len(),
+            storage.kin_g2.len(),
+            common_paramter_matrix.matrixed_g1_front.len(),
+        ),
+        (
+            &new_paramter.l,
+            storage.kout_g1.len(),
+            storage.kout_g2.len(),
+            common_paramter_matrix.matrixed_g1_back.len(),
+        ),
+        (
+            &new_paramter.h,
+            storage.h_g1.len(),
+            storage.h_g2.len(),
+            common_paramter_matrix.matrixed_h_g1.len(),
+        ),
+    ];
+    let well_formed = new_paramter.delta.is_complete()
+        && new_paramter.gamma.is_complete()
+        && sections.iter().all(|(list, g1_len, g2_len, matrix_len)| {
+            list.list.len() == *g1_len
+                && *g2_len == *g1_len
+                && *matrix_len == *g1_len
+                && list.list.iter().all(ParameterPair::is_complete)
+        });
+    if !well_formed {
+        return Err(VerificationError::InvalidContribution);
+    }
+
+    progress.start(2 + storage.kin_g1.len() + storage.kout_g1.len() + storage.h_g1.len())?;
     let mut result_kin = true;
     let mut result_kout = true;
     let mut result_h = true;
@@ -1081,9 +1543,11 @@ where
     //验证gamma正确性
     let result_delta =
         verify_new_paramter(&new_paramter.delta, &storage.delta_g1, &storage.delta_g2);
+    progress.step(1)?;
 
     let result_gamma =
         verify_new_paramter(&new_paramter.gamma, &storage.gamma_g1, &storage.gamma_g2);
+    progress.step(1)?;
 
     //验证kin正确性
     for i in 0..storage.kin_g1.len() {
@@ -1095,6 +1559,7 @@ where
                 &common_paramter_matrix.matrixed_g1_front[i].to_affine(),
                 &E::G2Affine::generator(),
             );
+        progress.step(1)?;
     }
 
     //验证kout正确性
@@ -1107,11 +1572,11 @@ where
                 &common_paramter_matrix.matrixed_g1_back[i].to_affine(),
                 &E::G2Affine::generator(),
             );
+        progress.step(1)?;
     }
 
     //验证h正确性
     for i in 0..storage.h_g1.len() {
-        assert_eq!(result_h, true);
         result_h = result_h
             && E::pairing(
                 &new_paramter.h.list[i].g1_result.unwrap().to_affine(),
@@ -1120,12 +1585,12 @@ where
                 &common_paramter_matrix.matrixed_h_g1[i].to_affine(),
                 &E::G2Affine::generator(),
             );
+        progress.step(1)?;
     }
 
-    assert_eq!(
-        result_kin && result_kout && result_h && result_delta && result_gamma,
-        true
-    );
+    if !(result_kin && result_kout && result_h && result_delta && result_gamma) {
+        return Err(VerificationError::InvalidContribution);
+    }
 
-    new_paramter.to_storage_format()
+    Ok(new_paramter.to_storage_format())
 }
@@ -0,0 +1,743 @@
+//! Checkpointing for contributions to the common (phase-1) ceremony state.
+//!
+//! A contribution through [`mpc_common_paramters_generator`] touches every
+//! element of the `tau`, `alpha * tau` and `beta * tau` vectors, which for a
+//! 2^22-sized state takes hours. This module runs the same computation in
+//! slices and periodically hands a [`ContributionCheckpoint`] to the caller,
+//! so that a crashed contributor can pick up from the last saved index rather
+//! than starting over.
+//!
+//! Every checkpoint is bound to the state it was computed from by a BLAKE2s
+//! digest of that state, and the whole file is covered by a trailing digest.
+//! The contributor's secret is never written in plain form: it is either left
+//! out entirely (and re-supplied on resume, where it is checked against the
+//! public `g1_mine` elements already in the checkpoint), or encrypted under a
+//! key derived from a passphrase.
+//!
+//! [`mpc_common_paramters_generator`]: super::mpc_common_paramters_generator
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use blake2s_simd::Params as Blake2sParams;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve, Group, UncompressedEncoding, WnafGroup};
+use pairing::Engine;
+use rand_core::RngCore;
+
+use super::progress::{CancellationToken, Phase, Progress, ProgressObserver};
+use super::{CommonParamter, CommonParamterInStorage, ParameterPair, TauParameterPair};
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"MPCCKPT1";
+
+/// Number of BLAKE2s iterations used to stretch a passphrase into a key.
+const PASSPHRASE_ROUNDS: usize = 1 << 16;
+
+/// Length in bytes of the serialized secret `(alpha, beta, tau)`.
+const SECRET_LEN: usize = 24;
+
+/// How the contributor's secret is kept in a checkpoint.
+#[derive(Clone, Copy, Debug)]
+pub enum SecretProtection<'a> {
+    /// The secret is not written at all. It has to be supplied again when
+    /// resuming, and is checked against the contribution's public elements.
+    Detached,
+    /// The secret is encrypted under a key derived from this passphrase.
+    Passphrase(&'a [u8]),
+}
+
+/// The secret-related part of a checkpoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SealedSecret {
+    salt: [u8; 32],
+    // Encrypted secret and its authentication tag, if a passphrase was used.
+    encrypted: Option<([u8; SECRET_LEN], [u8; 32])>,
+}
+
+impl SealedSecret {
+    fn seal<R: RngCore>(
+        secret: (u64, u64, u64),
+        protection: SecretProtection<'_>,
+        rng: &mut R,
+    ) -> Self {
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt);
+
+        let encrypted = match protection {
+            SecretProtection::Detached => None,
+            SecretProtection::Passphrase(passphrase) => {
+                let key = derive_key(&salt, passphrase);
+                let mut ciphertext = secret_to_bytes(secret);
+                apply_keystream(&key, &mut ciphertext);
+                let tag = authenticate(&key, &ciphertext);
+                Some((ciphertext, tag))
+            }
+        };
+
+        SealedSecret { salt, encrypted }
+    }
+
+    /// Returns `true` if the secret was encrypted under a passphrase, and
+    /// `false` if it has to be supplied by the contributor.
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    /// Decrypts the secret with the given passphrase.
+    pub fn unseal(&self, passphrase: &[u8]) -> io::Result<(u64, u64, u64)> {
+        let (ciphertext, tag) = self.encrypted.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "checkpoint does not contain an encrypted secret",
+            )
+        })?;
+
+        let key = derive_key(&self.salt, passphrase);
+        if authenticate(&key, &ciphertext) != tag {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "wrong passphrase or corrupted secret",
+            ));
+        }
+
+        let mut plaintext = ciphertext;
+        apply_keystream(&key, &mut plaintext);
+        Ok(secret_from_bytes(&plaintext))
+    }
+}
+
+fn secret_to_bytes((alpha, beta, tau): (u64, u64, u64)) -> [u8; SECRET_LEN] {
+    let mut bytes = [0u8; SECRET_LEN];
+    bytes[0..8].copy_from_slice(&alpha.to_le_bytes());
+    bytes[8..16].copy_from_slice(&beta.to_le_bytes());
+    bytes[16..24].copy_from_slice(&tau.to_le_bytes());
+    bytes
+}
+
+fn secret_from_bytes(bytes: &[u8; SECRET_LEN]) -> (u64, u64, u64) {
+    let mut word = [0u8; 8];
+    let mut read = |i: usize| {
+        word.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+        u64::from_le_bytes(word)
+    };
+    (read(0), read(1), read(2))
+}
+
+fn derive_key(salt: &[u8; 32], passphrase: &[u8]) -> [u8; 32] {
+    let mut key = *Blake2sParams::new()
+        .hash_length(32)
+        .personal(b"MPCck_KD")
+        .to_state()
+        .update(salt)
+        .update(passphrase)
+        .finalize()
+        .as_array();
+
+    for _ in 0..PASSPHRASE_ROUNDS {
+        key = *Blake2sParams::new()
+            .hash_length(32)
+            .personal(b"MPCck_KD")
+            .to_state()
+            .update(&key)
+            .update(passphrase)
+            .finalize()
+            .as_array();
+    }
+
+    key
+}
+
+fn apply_keystream(key: &[u8; 32], data: &mut [u8; SECRET_LEN]) {
+    let stream = Blake2sParams::new()
+        .hash_length(32)
+        .key(key)
+        .personal(b"MPCck_EN")
+        .hash(&[]);
+
+    for (d, s) in data.iter_mut().zip(stream.as_bytes()) {
+        *d ^= s;
+    }
+}
+
+fn authenticate(key: &[u8; 32], ciphertext: &[u8; SECRET_LEN]) -> [u8; 32] {
+    *Blake2sParams::new()
+        .hash_length(32)
+        .key(key)
+        .personal(b"MPCck_MA")
+        .hash(ciphertext)
+        .as_array()
+}
+
+/// Computes the BLAKE2s digest of a common parameter state. Checkpoints carry
+/// this digest so that they are never resumed against a different state.
+pub fn common_paramter_digest<E>(storage: &CommonParamterInStorage<E>) -> [u8; 32]
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    let mut h = Blake2sParams::new()
+        .hash_length(32)
+        .personal(b"MPCck_IN")
+        .to_state();
+
+    h.update(&(storage.tau_g1.len() as u64).to_be_bytes());
+    h.update(storage.alpha_g1.to_affine().to_uncompressed().as_ref());
+    h.update(storage.alpha_g2.to_affine().to_uncompressed().as_ref());
+    h.update(storage.beta_g1.to_affine().to_uncompressed().as_ref());
+    h.update(storage.beta_g2.to_affine().to_uncompressed().as_ref());
+    for (g1s, g2s) in [
+        (&storage.tau_g1, &storage.tau_g2),
+        (&storage.alpha_mul_tau_g1, &storage.alpha_mul_tau_g2),
+        (&storage.beta_mul_tau_g1, &storage.beta_mul_tau_g2),
+    ]
+    .iter()
+    {
+        for g in g1s.iter() {
+            h.update(g.to_affine().to_uncompressed().as_ref());
+        }
+        for g in g2s.iter() {
+            h.update(g.to_affine().to_uncompressed().as_ref());
+        }
+    }
+
+    *h.finalize().as_array()
+}
+
+/// A partially computed contribution to the common parameters.
+#[derive(Clone)]
+pub struct ContributionCheckpoint<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    /// Digest of the state this contribution is computed from.
+    pub input_digest: [u8; 32],
+    /// Length of the `tau` vectors in that state.
+    pub len: usize,
+    /// Number of `tau` positions that have been computed so far.
+    pub progress: usize,
+    pub alpha: ParameterPair<E>,
+    pub beta: ParameterPair<E>,
+    pub tau: Vec<ParameterPair<E>>,
+    pub alpha_mul_tau: Vec<ParameterPair<E>>,
+    pub beta_mul_tau: Vec<ParameterPair<E>>,
+    pub secret: SealedSecret,
+}
+
+impl<E> ContributionCheckpoint<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    /// Returns `true` once every position has been computed.
+    pub fn is_complete(&self) -> bool {
+        self.progress == self.len
+    }
+
+    /// Checks that `secret` is the one this contribution was started with, by
+    /// recomputing the public elements it already published.
+    pub fn matches_secret(&self, (alpha, beta, tau): (u64, u64, u64)) -> bool {
+        let g1 = E::G1::generator();
+        let tau_matches = match self.tau.get(1) {
+            Some(pair) => pair.g1_mine == Some(g1 * E::Fr::from(tau)),
+            // With fewer than two positions computed, tau is not yet bound
+            // by any public element.
+            None => true,
+        };
+
+        self.alpha.g1_mine == Some(g1 * E::Fr::from(alpha))
+            && self.beta.g1_mine == Some(g1 * E::Fr::from(beta))
+            && tau_matches
+    }
+
+    /// Turns a complete checkpoint into the contribution it describes.
+    pub fn into_contribution(self) -> io::Result<CommonParamter<E>> {
+        if !self.is_complete() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "contribution is not complete",
+            ));
+        }
+
+        Ok(CommonParamter {
+            alpha: self.alpha,
+            beta: self.beta,
+            tau: TauParameterPair { list: self.tau },
+            alpha_mul_tau: TauParameterPair {
+                list: self.alpha_mul_tau,
+            },
+            beta_mul_tau: TauParameterPair {
+                list: self.beta_mul_tau,
+            },
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut body = vec![];
+        body.write_all(CHECKPOINT_MAGIC)?;
+        body.write_all(&self.input_digest)?;
+        body.write_u32::<BigEndian>(self.len as u32)?;
+        body.write_u32::<BigEndian>(self.progress as u32)?;
+        write_pair::<E, _>(&mut body, &self.alpha)?;
+        write_pair::<E, _>(&mut body, &self.beta)?;
+        for i in 0..self.progress {
+            write_pair::<E, _>(&mut body, &self.tau[i])?;
+            write_pair::<E, _>(&mut body, &self.alpha_mul_tau[i])?;
+            write_pair::<E, _>(&mut body, &self.beta_mul_tau[i])?;
+        }
+        body.write_all(&self.secret.salt)?;
+        match self.secret.encrypted {
+            None => body.write_u8(0)?,
+            Some((ciphertext, tag)) => {
+                body.write_u8(1)?;
+                body.write_all(&ciphertext)?;
+                body.write_all(&tag)?;
+            }
+        }
+
+        writer.write_all(&body)?;
+        writer.write_all(&checkpoint_digest(&body))?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        if data.len() < 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "checkpoint is truncated",
+            ));
+        }
+        let (body, digest) = data.split_at(data.len() - 32);
+        if checkpoint_digest(body)[..] != digest[..] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checkpoint digest mismatch",
+            ));
+        }
+
+        let mut reader = body;
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a ceremony checkpoint",
+            ));
+        }
+
+        let mut input_digest = [0u8; 32];
+        reader.read_exact(&mut input_digest)?;
+        let len = reader.read_u32::<BigEndian>()? as usize;
+        let progress = reader.read_u32::<BigEndian>()? as usize;
+        if progress > len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checkpoint progress exceeds its length",
+            ));
+        }
+
+        let alpha = read_pair::<E, _>(&mut reader)?;
+        let beta = read_pair::<E, _>(&mut reader)?;
+        // The lengths are untrusted, so the vectors only grow as elements
+        // are actually read.
+        let mut tau = vec![];
+        let mut alpha_mul_tau = vec![];
+        let mut beta_mul_tau = vec![];
+        for _ in 0..progress {
+            tau.push(read_pair::<E, _>(&mut reader)?);
+            alpha_mul_tau.push(read_pair::<E, _>(&mut reader)?);
+            beta_mul_tau.push(read_pair::<E, _>(&mut reader)?);
+        }
+
+        let mut salt = [0u8; 32];
+        reader.read_exact(&mut salt)?;
+        let encrypted = match reader.read_u8()? {
+            0 => None,
+            1 => {
+                let mut ciphertext = [0u8; SECRET_LEN];
+                let mut tag = [0u8; 32];
+                reader.read_exact(&mut ciphertext)?;
+                reader.read_exact(&mut tag)?;
+                Some((ciphertext, tag))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unknown secret protection",
+                ))
+            }
+        };
+
+        Ok(ContributionCheckpoint {
+            input_digest,
+            len,
+            progress,
+            alpha,
+            beta,
+            tau,
+            alpha_mul_tau,
+            beta_mul_tau,
+            secret: SealedSecret { salt, encrypted },
+        })
+    }
+
+    /// Writes the checkpoint to `path`, replacing any previous checkpoint only
+    /// once the new one is fully on disk.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        {
+            let mut writer = BufWriter::new(File::create(&tmp)?);
+            self.write(&mut writer)?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+        fs::rename(tmp, path)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+}
+
+fn checkpoint_digest(body: &[u8]) -> [u8; 32] {
+    *Blake2sParams::new()
+        .hash_length(32)
+        .personal(b"MPCck_FL")
+        .hash(body)
+        .as_array()
+}
+
+fn write_pair<E, W>(writer: &mut W, pair: &ParameterPair<E>) -> io::Result<()>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    W: Write,
+{
+    let missing = || io::Error::new(io::ErrorKind::InvalidInput, "incomplete parameter pair");
+    let g1_result = pair.g1_result.ok_or_else(missing)?;
+    let g2_result = pair.g2_result.ok_or_else(missing)?;
+    let g1_mine = pair.g1_mine.ok_or_else(missing)?;
+    let g2_mine = pair.g2_mine.ok_or_else(missing)?;
+
+    writer.write_all(g1_result.to_affine().to_uncompressed().as_ref())?;
+    writer.write_all(g2_result.to_affine().to_uncompressed().as_ref())?;
+    writer.write_all(g1_mine.to_affine().to_uncompressed().as_ref())?;
+    writer.write_all(g2_mine.to_affine().to_uncompressed().as_ref())?;
+
+    Ok(())
+}
+
+fn read_pair<E, R>(reader: &mut R) -> io::Result<ParameterPair<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    R: Read,
+{
+    let read_g1 = |reader: &mut R| -> io::Result<E::G1> {
+        let mut repr = <E::G1Affine as UncompressedEncoding>::Uncompressed::default();
+        reader.read_exact(repr.as_mut())?;
+
+        let affine = E::G1Affine::from_uncompressed(&repr);
+        if affine.is_some().into() {
+            Ok(affine.unwrap().to_curve())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "invalid G1"))
+        }
+    };
+
+    let read_g2 = |reader: &mut R| -> io::Result<E::G2> {
+        let mut repr = <E::G2Affine as UncompressedEncoding>::Uncompressed::default();
+        reader.read_exact(repr.as_mut())?;
+
+        let affine = E::G2Affine::from_uncompressed(&repr);
+        if affine.is_some().into() {
+            Ok(affine.unwrap().to_curve())
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "invalid G2"))
+        }
+    };
+
+    Ok(ParameterPair {
+        g1_result: Some(read_g1(reader)?),
+        g2_result: Some(read_g2(reader)?),
+        g1_mine: Some(read_g1(reader)?),
+        g2_mine: Some(read_g2(reader)?),
+    })
+}
+
+fn contribute_pair<E>(result: &E::G1, result_g2: &E::G2, x: E::Fr) -> ParameterPair<E>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+{
+    ParameterPair {
+        g1_result: Some(*result * x),
+        g2_result: Some(*result_g2 * x),
+        g1_mine: Some(E::G1::generator() * x),
+        g2_mine: Some(E::G2::generator() * x),
+    }
+}
+
+/// Starts a checkpointed contribution to `storage` with the given secret.
+///
+/// `save` is called with the current checkpoint after every `interval` tau
+/// positions and once more when the contribution is complete. If it returns
+/// an error the contribution stops and the error is passed on; the last saved
+/// checkpoint can then be handed to [`resume_common_paramters_generator`].
+pub fn mpc_common_paramters_generator_checkpointed<E, R, F>(
+    storage: &CommonParamterInStorage<E>,
+    secret: (u64, u64, u64),
+    protection: SecretProtection<'_>,
+    interval: usize,
+    rng: &mut R,
+    save: F,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    R: RngCore,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    start_contribution(
+        storage,
+        secret,
+        protection,
+        interval,
+        rng,
+        save,
+        &mut Progress::silent(Phase::CommonContribution),
+    )
+}
+
+/// Like [`mpc_common_paramters_generator_checkpointed`], reporting every
+/// contributed element to `observer` and stopping once `cancel` is triggered.
+///
+/// Cancellation is reported as an [`io::ErrorKind::Interrupted`] error
+/// wrapping [`Cancelled`]; the checkpoint `save` last received can still be
+/// resumed from.
+///
+/// [`Cancelled`]: super::progress::Cancelled
+#[allow(clippy::too_many_arguments)]
+pub fn mpc_common_paramters_generator_checkpointed_with_progress<E, R, F>(
+    storage: &CommonParamterInStorage<E>,
+    secret: (u64, u64, u64),
+    protection: SecretProtection<'_>,
+    interval: usize,
+    rng: &mut R,
+    save: F,
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    R: RngCore,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    start_contribution(
+        storage,
+        secret,
+        protection,
+        interval,
+        rng,
+        save,
+        &mut Progress::new(Phase::CommonContribution, observer, cancel),
+    )
+}
+
+fn start_contribution<E, R, F>(
+    storage: &CommonParamterInStorage<E>,
+    secret: (u64, u64, u64),
+    protection: SecretProtection<'_>,
+    interval: usize,
+    rng: &mut R,
+    save: F,
+    progress: &mut Progress<'_>,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    R: RngCore,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    let (alpha, beta, _) = secret;
+    let len = storage.tau_g1.len();
+    progress.start(2 + 3 * len)?;
+    let alpha = contribute_pair::<E>(&storage.alpha_g1, &storage.alpha_g2, E::Fr::from(alpha));
+    progress.step(1)?;
+    let beta = contribute_pair::<E>(&storage.beta_g1, &storage.beta_g2, E::Fr::from(beta));
+    progress.step(1)?;
+
+    let checkpoint = ContributionCheckpoint {
+        input_digest: common_paramter_digest(storage),
+        len,
+        progress: 0,
+        alpha,
+        beta,
+        tau: vec![],
+        alpha_mul_tau: vec![],
+        beta_mul_tau: vec![],
+        secret: SealedSecret::seal(secret, protection, rng),
+    };
+
+    run_contribution(storage, checkpoint, secret, interval, save, progress)
+}
+
+/// Continues a contribution from a checkpoint written by
+/// [`mpc_common_paramters_generator_checkpointed`].
+///
+/// `secret` is the contributor's secret, either supplied again or obtained
+/// with [`SealedSecret::unseal`]. It fails if the checkpoint was computed
+/// from a different state or with a different secret.
+pub fn resume_common_paramters_generator<E, F>(
+    storage: &CommonParamterInStorage<E>,
+    checkpoint: ContributionCheckpoint<E>,
+    secret: (u64, u64, u64),
+    interval: usize,
+    save: F,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    resume_contribution(
+        storage,
+        checkpoint,
+        secret,
+        interval,
+        save,
+        &mut Progress::silent(Phase::CommonContribution),
+    )
+}
+
+/// Like [`resume_common_paramters_generator`], reporting every contributed
+/// element to `observer` and stopping once `cancel` is triggered.
+///
+/// The first report after the initial one already counts the elements
+/// contributed before the checkpoint was taken.
+pub fn resume_common_paramters_generator_with_progress<E, F>(
+    storage: &CommonParamterInStorage<E>,
+    checkpoint: ContributionCheckpoint<E>,
+    secret: (u64, u64, u64),
+    interval: usize,
+    save: F,
+    observer: &mut dyn ProgressObserver,
+    cancel: &CancellationToken,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    resume_contribution(
+        storage,
+        checkpoint,
+        secret,
+        interval,
+        save,
+        &mut Progress::new(Phase::CommonContribution, observer, cancel),
+    )
+}
+
+fn resume_contribution<E, F>(
+    storage: &CommonParamterInStorage<E>,
+    checkpoint: ContributionCheckpoint<E>,
+    secret: (u64, u64, u64),
+    interval: usize,
+    save: F,
+    progress: &mut Progress<'_>,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    if checkpoint.input_digest != common_paramter_digest(storage)
+        || checkpoint.len != storage.tau_g1.len()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "checkpoint was computed from a different ceremony state",
+        ));
+    }
+    if !checkpoint.matches_secret(secret) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "secret does not match the checkpoint",
+        ));
+    }
+
+    progress.start(2 + 3 * checkpoint.len)?;
+    progress.step(2 + 3 * checkpoint.progress)?;
+    run_contribution(storage, checkpoint, secret, interval, save, progress)
+}
+
+fn run_contribution<E, F>(
+    storage: &CommonParamterInStorage<E>,
+    mut checkpoint: ContributionCheckpoint<E>,
+    (alpha, beta, tau): (u64, u64, u64),
+    interval: usize,
+    mut save: F,
+    progress: &mut Progress<'_>,
+) -> io::Result<CommonParamter<E>>
+where
+    E: Engine,
+    E::G1: WnafGroup,
+    E::G2: WnafGroup,
+    F: FnMut(&ContributionCheckpoint<E>) -> io::Result<()>,
+{
+    let interval = interval.max(1);
+    let alpha = E::Fr::from(alpha);
+    let beta = E::Fr::from(beta);
+    let tau = E::Fr::from(tau);
+
+    let mut tau_power = tau.pow_vartime(&[checkpoint.progress as u64]);
+    while checkpoint.progress < checkpoint.len {
+        let end = (checkpoint.progress + interval).min(checkpoint.len);
+        for i in checkpoint.progress..end {
+            checkpoint.tau.push(contribute_pair::<E>(
+                &storage.tau_g1[i],
+                &storage.tau_g2[i],
+                tau_power,
+            ));
+            progress.step(1)?;
+            checkpoint.alpha_mul_tau.push(contribute_pair::<E>(
+                &storage.alpha_mul_tau_g1[i],
+                &storage.alpha_mul_tau_g2[i],
+                tau_power * alpha,
+            ));
+            progress.step(1)?;
+            checkpoint.beta_mul_tau.push(contribute_pair::<E>(
+                &storage.beta_mul_tau_g1[i],
+                &storage.beta_mul_tau_g2[i],
+                tau_power * beta,
+            ));
+            progress.step(1)?;
+            tau_power *= tau;
+        }
+        checkpoint.progress = end;
+
+        save(&checkpoint)?;
+    }
+
+    checkpoint.into_contribution()
+}
@@ -0,0 +1,157 @@
+//! Progress reporting and cooperative cancellation for ceremony operations.
+//!
+//! Contributions, verifications and the matrix step all walk over vectors as
+//! long as the ceremony state, so on real sizes they run for hours. The
+//! `*_with_progress` variants of those operations report to a
+//! [`ProgressObserver`] after every element and check a
+//! [`CancellationToken`] in between, returning [`Cancelled`] once it has been
+//! triggered. The plain variants are unchanged and report nothing.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::SynthesisError;
+
+/// The ceremony operation a progress report refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// Contributing to the common (phase-1) state.
+    CommonContribution,
+    /// Verifying a contribution to the common state.
+    CommonVerification,
+    /// Combining the common state with a circuit's constraint matrices.
+    Matrix,
+    /// Contributing to the circuit-specific (phase-2) state.
+    UncommonContribution,
+    /// Verifying a contribution to the circuit-specific state.
+    UncommonVerification,
+}
+
+/// Receives progress reports from long-running ceremony operations.
+pub trait ProgressObserver {
+    /// Called once with `done == 0` when `phase` starts, and then every time
+    /// an element has been processed. `total` is fixed for a given phase.
+    fn report(&mut self, phase: Phase, done: usize, total: usize);
+}
+
+/// Ignores all reports.
+impl ProgressObserver for () {
+    fn report(&mut self, _: Phase, _: usize, _: usize) {}
+}
+
+impl<F: FnMut(Phase, usize, usize)> ProgressObserver for F {
+    fn report(&mut self, phase: Phase, done: usize, total: usize) {
+        self(phase, done, total)
+    }
+}
+
+/// A flag shared between an operation and whoever may want to stop it.
+///
+/// Cloning the token yields a handle to the same flag, so one clone can be
+/// handed to the operation while another is kept by, e.g., a signal handler.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every operation holding this token to stop at the next element.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// The shared flag, for code outside the `groth16` module.
+    pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+        self.0.clone()
+    }
+}
+
+/// An operation was stopped through its [`CancellationToken`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Error for Cancelled {}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation cancelled")
+    }
+}
+
+impl From<Cancelled> for io::Error {
+    fn from(e: Cancelled) -> io::Error {
+        io::Error::new(io::ErrorKind::Interrupted, e)
+    }
+}
+
+impl From<Cancelled> for SynthesisError {
+    fn from(_: Cancelled) -> SynthesisError {
+        SynthesisError::Cancelled
+    }
+}
+
+/// Tracks the progress of a single phase.
+pub(super) struct Progress<'a> {
+    phase: Phase,
+    observer: Option<&'a mut dyn ProgressObserver>,
+    cancel: Option<&'a CancellationToken>,
+    done: usize,
+    total: usize,
+}
+
+impl<'a> Progress<'a> {
+    pub(super) fn new(
+        phase: Phase,
+        observer: &'a mut dyn ProgressObserver,
+        cancel: &'a CancellationToken,
+    ) -> Self {
+        Progress {
+            phase,
+            observer: Some(observer),
+            cancel: Some(cancel),
+            done: 0,
+            total: 0,
+        }
+    }
+
+    /// A tracker that reports nothing and can't be cancelled.
+    pub(super) fn silent(phase: Phase) -> Self {
+        Progress {
+            phase,
+            observer: None,
+            cancel: None,
+            done: 0,
+            total: 0,
+        }
+    }
+
+    pub(super) fn start(&mut self, total: usize) -> Result<(), Cancelled> {
+        self.done = 0;
+        self.total = total;
+        self.report()
+    }
+
+    pub(super) fn step(&mut self, n: usize) -> Result<(), Cancelled> {
+        self.done += n;
+        self.report()
+    }
+
+    fn report(&mut self) -> Result<(), Cancelled> {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.report(self.phase, self.done, self.total);
+        }
+        match self.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
@@ -2,8 +2,17 @@
 mod mpc_tests {
     use super::*;
 
+    use crate::groth16::mpc::progress::{CancellationToken, Cancelled, Phase};
     use crate::groth16::mpc::*;
+    use crate::groth16::tests::{CommittedSquare, CubeCircuit, MulCircuit};
+    use crate::groth16::{
+        circuit_common_paramter_matrix, compare_with_ceremony, generate_committed_parameters,
+        generate_parameters, generate_parameters_mpc, verify_parameters_against_ceremony,
+        ParameterCheckError, ParameterSection, Parameters, SimulatedCeremony,
+    };
+    use crate::{Circuit, ConstraintSystem, SynthesisError};
     use bls12_381::{Bls12, Scalar};
+    use group::{Curve, Group};
 
     #[test]
     fn all_test() {
@@ -34,7 +43,9 @@ mod mpc_tests {
         ];
         let ct_aux = vec![vec![], vec![]];
 
-        let common_paramter_matrix = paramter_in_storage.matrix(&at_aux, &bt_aux, &ct_aux, 2, 2, 4);
+        let common_paramter_matrix = paramter_in_storage
+            .matrix(&[], &[], &[], &at_aux, &bt_aux, &ct_aux, 4)
+            .unwrap();
         let mut paramter_in_storage = initial_uncommon_paramters::<Bls12>(&common_paramter_matrix);
         //under-chain
         let player1_common = mpc_uncommon_paramters_generator(&paramter_in_storage, (1, 2));
@@ -88,40 +99,19 @@ mod mpc_tests {
         let player3_common = mpc_common_paramters_generator(&paramter_in_storage, (3, 4, 5));
         //on-chain
         paramter_in_storage = verify_common_paramter(&paramter_in_storage, &player3_common);
-        /*let alpha_g1 = paramter_in_storage.alpha_g1;
-        let alpha_g2 = paramter_in_storage.alpha_g2;
-        let beta_g1 = paramter_in_storage.beta_g1;
-        let beta_g2 = paramter_in_storage.beta_g2;
-        let tau_g1 = paramter_in_storage.tau_g1;
-        let tau_g2 = paramter_in_storage.tau_g2;
-        let alpha_mul_tau_g1 = paramter_in_storage.alpha_mul_tau_g1;
-        let alpha_mul_tau_g2 = paramter_in_storage.alpha_mul_tau_g2;
-        let beta_mul_tau_g1 = paramter_in_storage.beta_mul_tau_g1;
-        let beta_mul_tau_g2 = paramter_in_storage.beta_mul_tau_g2;
-
-        assert_eq!(alpha_g1, g1 * Scalar::from(6));
-        assert_eq!(alpha_g2, g2 * Scalar::from(6));
-        assert_eq!(beta_g1, g1 * Scalar::from(24));
-        assert_eq!(beta_g2, g2 * Scalar::from(24));
-        assert_eq!(tau_g1.len(), 8);
-        assert_eq!(tau_g1[0], g1 * Scalar::from(1));
-        assert_eq!(tau_g2[0], g2 * Scalar::from(1));
-        assert_eq!(tau_g1[1], g1 * Scalar::from(60));
-        assert_eq!(tau_g2[1], g2 * Scalar::from(60));
-        assert_eq!(tau_g1[2], g1 * Scalar::from(3600));
-        assert_eq!(tau_g2[2], g2 * Scalar::from(3600));
-        assert_eq!(alpha_mul_tau_g1.len(), 8);
-        assert_eq!(alpha_mul_tau_g1[0], g1 * Scalar::from(1 * 6));
-        assert_eq!(alpha_mul_tau_g2[0], g2 * Scalar::from(1 * 6));
-        assert_eq!(alpha_mul_tau_g1[1], g1 * Scalar::from(60 * 6));
-        assert_eq!(alpha_mul_tau_g2[1], g2 * Scalar::from(60 * 6));
-        assert_eq!(alpha_mul_tau_g1[2], g1 * Scalar::from(3600 * 6));
-        assert_eq!(alpha_mul_tau_g2[2], g2 * Scalar::from(3600 * 6));
-        assert_eq!(beta_mul_tau_g1.len(), 8);
-        assert_eq!(beta_mul_tau_g1[0], g1 * Scalar::from(1 * 24));
-        assert_eq!(beta_mul_tau_g2[0], g2 * Scalar::from(1 * 24));
-        assert_eq!(beta_mul_tau_g1[1], g1 * Scalar::from(60 * 24));
-        assert_eq!(beta_mul_tau_g2[1], g2 * Scalar::from(60 * 24));*/
+        // alpha = 1 * 2 * 3, beta = 2 * 3 * 4 and tau = 3 * 4 * 5.
+        assert_eq!(paramter_in_storage.alpha_g1, g1 * Scalar::from(6));
+        assert_eq!(paramter_in_storage.beta_g2, g2 * Scalar::from(24));
+        assert_eq!(paramter_in_storage.tau_g1.len(), 8);
+        assert_eq!(paramter_in_storage.tau_g1[2], g1 * Scalar::from(3600));
+        assert_eq!(
+            paramter_in_storage.alpha_mul_tau_g1[1],
+            g1 * Scalar::from(60 * 6)
+        );
+        assert_eq!(
+            paramter_in_storage.beta_mul_tau_g2[1],
+            g2 * Scalar::from(60 * 24)
+        );
 
         let matrix1 = vec![
             vec![(Scalar::from(1), 0usize), (Scalar::from(1), 1usize)],
@@ -133,12 +123,602 @@ mod mpc_tests {
         ];
         let matrix3: Vec<Vec<(Scalar, usize)>> = Vec::new();
 
-        let cp_m = paramter_in_storage.matrix_test(&matrix1, &matrix2, &matrix3, 2, 2, 4);
+        let cp_m = paramter_in_storage
+            .matrix_test(&matrix1, &matrix2, &matrix3, 2, 2, 4)
+            .unwrap();
 
         assert_eq!(cp_m.matrixed_g1_front[0], g1 * Scalar::from(24 * 61 + 6));
         assert_eq!(cp_m.matrixed_g1_front[1], g1 * Scalar::from(6 * 60));
     }
 
+    #[test]
+    fn checkpointed_contribution_resumes() {
+        use crate::groth16::mpc::checkpoint::*;
+        use std::io;
+
+        let paramter_in_storage = initial_common_paramters::<Bls12>(8);
+        let secret = (2, 3, 5);
+        let expected =
+            mpc_common_paramters_generator(&paramter_in_storage, secret).to_storage_format();
+
+        // Crash right after the first checkpoint has been written.
+        let mut saved = vec![];
+        let interrupted = mpc_common_paramters_generator_checkpointed(
+            &paramter_in_storage,
+            secret,
+            SecretProtection::Passphrase(b"correct horse"),
+            3,
+            &mut rand::thread_rng(),
+            |checkpoint| {
+                checkpoint.write(&mut saved)?;
+                Err(io::Error::new(io::ErrorKind::Interrupted, "crash"))
+            },
+        );
+        assert!(interrupted.is_err());
+
+        let checkpoint = ContributionCheckpoint::<Bls12>::read(&saved[..]).unwrap();
+        assert_eq!(checkpoint.progress, 3);
+        assert!(checkpoint.secret.unseal(b"wrong horse").is_err());
+        let unsealed = checkpoint.secret.unseal(b"correct horse").unwrap();
+        assert_eq!(unsealed, secret);
+
+        // A different secret or a different state is rejected.
+        assert!(resume_common_paramters_generator(
+            &paramter_in_storage,
+            checkpoint.clone(),
+            (2, 3, 6),
+            3,
+            |_| Ok(())
+        )
+        .is_err());
+        assert!(resume_common_paramters_generator(
+            &initial_common_paramters::<Bls12>(8 + 1),
+            checkpoint.clone(),
+            secret,
+            3,
+            |_| Ok(())
+        )
+        .is_err());
+
+        let resumed = resume_common_paramters_generator(
+            &paramter_in_storage,
+            checkpoint,
+            unsealed,
+            3,
+            |_| Ok(()),
+        )
+        .unwrap();
+        // The resumed contribution verifies like a regular one.
+        let resumed = verify_common_paramter(&paramter_in_storage, &resumed);
+
+        assert_eq!(resumed.alpha_g1, expected.alpha_g1);
+        assert_eq!(resumed.beta_g2, expected.beta_g2);
+        assert_eq!(resumed.tau_g1, expected.tau_g1);
+        assert_eq!(resumed.tau_g2, expected.tau_g2);
+        assert_eq!(resumed.alpha_mul_tau_g1, expected.alpha_mul_tau_g1);
+        assert_eq!(resumed.beta_mul_tau_g2, expected.beta_mul_tau_g2);
+    }
+
+    #[test]
+    fn parameters_match_ceremony() {
+        let g1 = bls12_381::G1Projective::generator();
+        let g2 = bls12_381::G2Projective::generator();
+        let circuit = || MulCircuit { a: None, b: None };
+
+        let phase1_state = mpc_common_paramters_custom_all::<Bls12>();
+        let matrix = circuit_common_paramter_matrix(circuit(), &phase1_state).unwrap();
+        let phase2_state = mpc_uncommon_paramters_custom_all::<Bls12>(&matrix);
+        let verify = |params: &Parameters<Bls12>| {
+            verify_parameters_against_ceremony(params, circuit(), &phase1_state, &phase2_state)
+        };
+
+        let params = generate_parameters_mpc::<Bls12, _>(circuit(), g1, g2).unwrap();
+        assert!(!params.a.is_empty() && !params.b_g2.is_empty());
+        assert!(verify(&params).is_ok());
+
+        // Parameters generated directly with the ceremony's toxic waste pass
+        // too: mpc_common_paramters_custom_all and
+        // mpc_uncommon_paramters_custom_all contribute alpha = 6, beta = 24,
+        // tau = 2, gamma = 6 and delta = 24.
+        let direct = generate_parameters::<Bls12, _>(
+            circuit(),
+            g1,
+            g2,
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(2),
+        )
+        .unwrap();
+        assert!(verify(&direct).is_ok());
+
+        let mut tampered = params.clone();
+        tampered.vk.delta_g1 = (g1 * Scalar::from(5)).to_affine();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::DeltaG1))
+        ));
+
+        let mut tampered = params.clone();
+        let mut h = tampered.h.to_vec();
+        h[0] = (h[0] + g1).to_affine();
+        tampered.h = std::sync::Arc::new(h);
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::H))
+        ));
+
+        let mut tampered = params.clone();
+        let mut a = tampered.a.to_vec();
+        a[1] = (a[1] + g1).to_affine();
+        tampered.a = std::sync::Arc::new(a);
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::A))
+        ));
+
+        let mut tampered = params.clone();
+        let mut b_g2 = tampered.b_g2.to_vec();
+        b_g2[0] = (b_g2[0] + g2).to_affine();
+        tampered.b_g2 = std::sync::Arc::new(b_g2);
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::BG2))
+        ));
+
+        let mut tampered = params.clone();
+        tampered.b_g1 = std::sync::Arc::new(vec![]);
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Length(ParameterSection::BG1))
+        ));
+
+        let mut tampered = params.clone();
+        tampered.vk.ic[1] = (tampered.vk.ic[1] + g1).to_affine();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::Ic))
+        ));
+
+        let mut tampered = params.clone();
+        let mut l = tampered.l.to_vec();
+        l[1] = (l[1] + g1).to_affine();
+        tampered.l = std::sync::Arc::new(l);
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(ParameterSection::L))
+        ));
+
+        let mut tampered = params;
+        tampered.vk.ic.pop();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Length(ParameterSection::Ic))
+        ));
+    }
+
+    #[test]
+    fn committed_parameters_match_ceremony() {
+        let g1 = bls12_381::G1Projective::generator();
+        let g2 = bls12_381::G2Projective::generator();
+        let circuit = || CommittedSquare { x: None };
+
+        let phase1_state = mpc_common_paramters_custom_all::<Bls12>();
+        let matrix = circuit_common_paramter_matrix(circuit(), &phase1_state).unwrap();
+        let phase2_state = mpc_uncommon_paramters_custom_all::<Bls12>(&matrix);
+        let verify = |params: &Parameters<Bls12>| {
+            verify_parameters_against_ceremony(params, circuit(), &phase1_state, &phase2_state)
+        };
+
+        // The ceremony keeps the committed variable in L.
+        let params = generate_parameters_mpc::<Bls12, _>(circuit(), g1, g2).unwrap();
+        assert_eq!(params.l.len(), 1);
+        assert!(params.vk.commitment_key.is_empty());
+        assert!(verify(&params).is_ok());
+
+        // Directly generated parameters move it to the commitment key.
+        let params = generate_committed_parameters::<Bls12, _>(
+            circuit(),
+            g1,
+            g2,
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(2),
+            Scalar::from(7),
+            Scalar::from(11),
+        )
+        .unwrap();
+        assert!(params.l.is_empty());
+        assert_eq!(params.vk.commitment_key.len(), 1);
+        assert!(verify(&params).is_ok());
+
+        let mut tampered = params.clone();
+        tampered.vk.commitment_key[0] = (tampered.vk.commitment_key[0] + g1).to_affine();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(
+                ParameterSection::CommitmentKey
+            ))
+        ));
+
+        let mut tampered = params.clone();
+        tampered.vk.commitment_pok_key[0] = (tampered.vk.commitment_pok_key[0] + g1).to_affine();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(
+                ParameterSection::CommitmentKey
+            ))
+        ));
+
+        let mut tampered = params;
+        tampered.vk.eta_delta_inv_g1 = (tampered.vk.eta_delta_inv_g1 + g1).to_affine();
+        assert!(matches!(
+            verify(&tampered),
+            Err(ParameterCheckError::Inconsistent(
+                ParameterSection::CommitmentKey
+            ))
+        ));
+    }
+
+    struct RepeatedSquaring {
+        x: Option<Scalar>,
+        rounds: usize,
+    }
+
+    impl Circuit<Scalar> for RepeatedSquaring {
+        fn synthesize<CS: ConstraintSystem<Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let mut value = self.x;
+            let mut x = cs.alloc(|| "x", || value.ok_or(SynthesisError::AssignmentMissing))?;
+            for i in 0..self.rounds {
+                let squared = value.map(|v| v * v);
+                let y = cs.alloc(
+                    || format!("x^(2^{})", i + 1),
+                    || squared.ok_or(SynthesisError::AssignmentMissing),
+                )?;
+                cs.enforce(
+                    || format!("square {}", i),
+                    |lc| lc + x,
+                    |lc| lc + x,
+                    |lc| lc + y,
+                );
+                x = y;
+                value = squared;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn h_query_matches_generator() {
+        let g1 = bls12_381::G1Projective::generator();
+        let g2 = bls12_381::G2Projective::generator();
+        let circuit = || MulCircuit { a: None, b: None };
+
+        // mpc_common_paramters_custom_all and mpc_uncommon_paramters_custom_all
+        // contribute alpha = 6, beta = 24, tau = 2, gamma = 6 and delta = 24.
+        let expected = generate_parameters::<Bls12, _>(
+            circuit(),
+            g1,
+            g2,
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(6),
+            Scalar::from(24),
+            Scalar::from(2),
+        )
+        .unwrap();
+        let params = generate_parameters_mpc::<Bls12, _>(circuit(), g1, g2).unwrap();
+
+        // One constraint plus one per public input gives a domain of size 4.
+        assert_eq!(params.h.len(), 3);
+        assert_eq!(params.h, expected.h);
+    }
+
+    #[test]
+    fn matrix_rejects_too_few_powers() {
+        let phase1_state = mpc_common_paramters_custom_all::<Bls12>();
+
+        // 7 constraints need a domain of size 8, and so 15 powers of tau.
+        let result =
+            circuit_common_paramter_matrix(RepeatedSquaring { x: None, rounds: 6 }, &phase1_state);
+        assert!(matches!(
+            result,
+            Err(SynthesisError::PolynomialDegreeTooLarge)
+        ));
+
+        // 3 constraints fit in a domain of size 4, which needs 7 powers.
+        let matrix =
+            circuit_common_paramter_matrix(RepeatedSquaring { x: None, rounds: 2 }, &phase1_state)
+                .unwrap();
+        assert_eq!(matrix.matrixed_h_g1.len(), 3);
+        assert_eq!(matrix.matrixed_h_g2.len(), 3);
+
+        // The H query assumes the powers of tau start from the generator.
+        let mut malformed = phase1_state;
+        malformed.tau_g1[0] = malformed.tau_g1[0].double();
+        let result =
+            circuit_common_paramter_matrix(RepeatedSquaring { x: None, rounds: 2 }, &malformed);
+        assert!(matches!(
+            result,
+            Err(SynthesisError::MalformedCeremonyState)
+        ));
+    }
+
+    #[test]
+    fn ceremony_matches_generator() {
+        let ceremony = SimulatedCeremony {
+            common: vec![(3, 5, 2), (7, 1, 3), (2, 11, 5)],
+            uncommon: vec![(5, 3), (13, 2)],
+        };
+        let (alpha, beta, gamma, delta, tau) = ceremony.toxic_waste::<Scalar>();
+        assert_eq!(alpha, Scalar::from(42));
+        assert_eq!(beta, Scalar::from(55));
+        assert_eq!(gamma, Scalar::from(65));
+        assert_eq!(delta, Scalar::from(6));
+        assert_eq!(tau, Scalar::from(30));
+
+        let differences =
+            compare_with_ceremony::<Bls12, _, _>(|| MulCircuit { a: None, b: None }, &ceremony)
+                .unwrap();
+        assert_eq!(differences, vec![]);
+
+        let differences =
+            compare_with_ceremony::<Bls12, _, _>(|| CubeCircuit { x: None }, &ceremony).unwrap();
+        assert_eq!(differences, vec![]);
+    }
+
+    #[test]
+    fn contribution_reports_progress_and_cancels() {
+        let storage = initial_common_paramters::<Bls12>(4);
+        let cancel = CancellationToken::new();
+
+        let mut reports = vec![];
+        let contribution = mpc_common_paramters_generator_with_progress(
+            &storage,
+            (2, 3, 5),
+            &mut |phase: Phase, done: usize, total: usize| reports.push((phase, done, total)),
+            &cancel,
+        )
+        .unwrap();
+        // alpha, beta, and four elements each of tau, alpha * tau and beta * tau.
+        assert_eq!(reports.len(), 15);
+        assert_eq!(reports[0], (Phase::CommonContribution, 0, 14));
+        assert_eq!(reports[14], (Phase::CommonContribution, 14, 14));
+
+        let mut last = None;
+        let verified = verify_common_paramter_with_progress(
+            &storage,
+            &contribution,
+            &mut |phase: Phase, done: usize, total: usize| last = Some((phase, done, total)),
+            &cancel,
+        )
+        .unwrap();
+        assert_eq!(last, Some((Phase::CommonVerification, 6, 6)));
+        assert_eq!(
+            verified.tau_g1,
+            verify_common_paramter(&storage, &contribution).tau_g1
+        );
+
+        let stopper = cancel.clone();
+        let mut seen = 0;
+        let result = mpc_common_paramters_generator_with_progress(
+            &storage,
+            (2, 3, 5),
+            &mut |_: Phase, done: usize, _: usize| {
+                seen = done;
+                if done == 3 {
+                    stopper.cancel();
+                }
+            },
+            &cancel,
+        );
+        assert!(matches!(result, Err(Cancelled)));
+        assert_eq!(seen, 3);
+        assert!(cancel.is_cancelled());
+    }
+
+    #[test]
+    fn invalid_contribution_is_reported() {
+        let storage = initial_common_paramters::<Bls12>(4);
+        let mut contribution = mpc_common_paramters_generator(&storage, (2, 3, 5));
+        contribution.alpha_mul_tau.list[2].g1_result =
+            Some(bls12_381::G1Projective::generator() * Scalar::from(7));
+
+        let result = verify_common_paramter_with_progress(
+            &storage,
+            &contribution,
+            &mut (),
+            &CancellationToken::new(),
+        );
+        assert!(matches!(
+            result,
+            Err(VerificationError::InvalidContribution)
+        ));
+
+        // Truncated lists and missing elements are rejected rather than
+        // indexed or unwrapped.
+        let verify = |contribution: &CommonParamter<Bls12>| {
+            verify_common_paramter_with_progress(
+                &storage,
+                contribution,
+                &mut (),
+                &CancellationToken::new(),
+            )
+        };
+        let mut truncated = mpc_common_paramters_generator(&storage, (2, 3, 5));
+        truncated.alpha_mul_tau.list.pop();
+        assert!(matches!(
+            verify(&truncated),
+            Err(VerificationError::InvalidContribution)
+        ));
+        let mut truncated = mpc_common_paramters_generator(&storage, (2, 3, 5));
+        truncated.tau.list.pop();
+        truncated.alpha_mul_tau.list.pop();
+        truncated.beta_mul_tau.list.pop();
+        assert!(matches!(
+            verify(&truncated),
+            Err(VerificationError::InvalidContribution)
+        ));
+        let mut missing = mpc_common_paramters_generator(&storage, (2, 3, 5));
+        missing.beta.g2_mine = None;
+        assert!(matches!(
+            verify(&missing),
+            Err(VerificationError::InvalidContribution)
+        ));
+        let mut missing = mpc_common_paramters_generator(&storage, (2, 3, 5));
+        missing.tau.list[1].g2_result = None;
+        assert!(matches!(
+            verify(&missing),
+            Err(VerificationError::InvalidContribution)
+        ));
+
+        let columns = vec![vec![]; 2];
+        let matrix = mpc_common_paramters_custom_all::<Bls12>()
+            .matrix(&[], &[], &[], &columns, &columns, &columns, 2)
+            .unwrap();
+        let uncommon = initial_uncommon_paramters(&matrix);
+        let mut truncated = mpc_uncommon_paramters_generator(&uncommon, (2, 3));
+        truncated.h.list.pop();
+        assert!(matches!(
+            verify_uncommon_paramter_with_progress(
+                &matrix,
+                &uncommon,
+                &truncated,
+                &mut (),
+                &CancellationToken::new(),
+            ),
+            Err(VerificationError::InvalidContribution)
+        ));
+
+        let list = initParameterList::<Bls12>();
+        let first = mpc_common_paramters_custom_generator::<Bls12>(&list[0], Scalar::from(2));
+        let list = ParamterListExcute::<Bls12>(list, first.unwrap()).unwrap();
+        let mut bad =
+            mpc_common_paramters_custom_generator::<Bls12>(&list[1], Scalar::from(3)).unwrap();
+        bad.g1_result = Some(bls12_381::G1Projective::generator() * Scalar::from(5));
+        assert!(matches!(
+            ParamterListExcute::<Bls12>(list, bad),
+            Err(VerificationError::InvalidContribution)
+        ));
+    }
+
+    #[test]
+    fn checkpointed_contribution_reports_progress_and_cancels() {
+        use crate::groth16::mpc::checkpoint::*;
+        use std::io;
+
+        let storage = initial_common_paramters::<Bls12>(4);
+        let secret = (2, 3, 5);
+        let cancel = CancellationToken::new();
+
+        // Stop in the middle of the second slice.
+        let stopper = cancel.clone();
+        let mut saved = vec![];
+        let result = mpc_common_paramters_generator_checkpointed_with_progress(
+            &storage,
+            secret,
+            SecretProtection::Detached,
+            2,
+            &mut rand::thread_rng(),
+            |checkpoint| {
+                saved.clear();
+                checkpoint.write(&mut saved)
+            },
+            &mut |_: Phase, done: usize, _: usize| {
+                if done == 10 {
+                    stopper.cancel();
+                }
+            },
+            &cancel,
+        );
+        match result {
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::Interrupted),
+            Ok(_) => panic!("contribution was not cancelled"),
+        }
+
+        let checkpoint = ContributionCheckpoint::<Bls12>::read(&saved[..]).unwrap();
+        assert_eq!(checkpoint.progress, 2);
+
+        let mut reports = vec![];
+        let resumed = resume_common_paramters_generator_with_progress(
+            &storage,
+            checkpoint,
+            secret,
+            2,
+            |_| Ok(()),
+            &mut |phase: Phase, done: usize, total: usize| reports.push((phase, done, total)),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        // alpha, beta and two tau positions were done before the checkpoint.
+        assert_eq!(reports[0], (Phase::CommonContribution, 0, 14));
+        assert_eq!(reports[1], (Phase::CommonContribution, 8, 14));
+        assert_eq!(reports.last(), Some(&(Phase::CommonContribution, 14, 14)));
+
+        let expected = mpc_common_paramters_generator(&storage, secret).to_storage_format();
+        assert_eq!(
+            verify_common_paramter(&storage, &resumed).tau_g1,
+            expected.tau_g1
+        );
+    }
+
+    #[test]
+    fn matrix_reports_progress_and_cancels() {
+        let storage = mpc_common_paramters_custom_all::<Bls12>();
+        let at_aux = vec![vec![(Scalar::from(1), 0usize)], vec![]];
+        let bt_aux = vec![
+            vec![(Scalar::from(1), 0usize), (Scalar::from(2), 1usize)],
+            vec![(Scalar::from(3), 0usize)],
+        ];
+        let ct_aux = vec![vec![], vec![]];
+
+        let mut last = None;
+        let matrix = storage
+            .matrix_with_progress(
+                &[],
+                &[],
+                &[],
+                &at_aux,
+                &bt_aux,
+                &ct_aux,
+                3,
+                &mut |phase: Phase, done: usize, total: usize| last = Some((phase, done, total)),
+                &CancellationToken::new(),
+            )
+            .unwrap();
+        // The H query and the Lagrange basis, plus one step per variable.
+        assert_eq!(last, Some((Phase::Matrix, 3, 3)));
+        let expected = storage
+            .matrix(&[], &[], &[], &at_aux, &bt_aux, &ct_aux, 3)
+            .unwrap();
+        assert_eq!(matrix.matrixed_g1_back, expected.matrixed_g1_back);
+        assert_eq!(matrix.matrixed_h_g1, expected.matrixed_h_g1);
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        match storage.matrix_with_progress(
+            &[],
+            &[],
+            &[],
+            &at_aux,
+            &bt_aux,
+            &ct_aux,
+            3,
+            &mut (),
+            &cancel,
+        ) {
+            Err(SynthesisError::Cancelled) => {}
+            _ => panic!("matrix step was not cancelled"),
+        }
+    }
+
     #[test]
     fn matrix_works() {
         let g1 = bls12_381::G1Affine::generator();
@@ -155,6 +735,9 @@ mod mpc_tests {
         let g2 = bls12_381::G2Affine::generator();
 
         let common_paramter_matrix = CommonParamterMatrix::<Bls12> {
+            a_g1: vec![],
+            b_g1: vec![],
+            b_g2: vec![],
             matrixed_g1_front: vec![g1 * Scalar::from(6), g1 * Scalar::from(12)],
             matrixed_g2_front: vec![g2 * Scalar::from(6), g2 * Scalar::from(12)],
             matrixed_g1_back: vec![g1 * Scalar::from(24), g1 * Scalar::from(48)],
@@ -1,20 +1,40 @@
+use blake2s_simd::Params as Blake2sParams;
 use rand_core::RngCore;
-use std::ops::{AddAssign, MulAssign};
+use std::ops::{AddAssign, MulAssign, SubAssign};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use ff::{Field, PrimeField, PrimeFieldBits};
 use group::{prime::PrimeCurveAffine, Curve};
 use pairing::Engine;
 
-use super::{ParameterSource, Proof};
+use super::mpc::progress::CancellationToken;
+use super::witness::record_commitment;
+use super::{CommitmentOpening, ParameterSource, Parameters, Proof, R1cs, VerifyingKey, Witness};
 
 use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
 
-use crate::domain::{EvaluationDomain, Scalar};
+use crate::domain::{DomainTables, EvaluationDomain, Scalar};
 
-use crate::multiexp::{multiexp, DensityTracker, FullDensity};
+use crate::multiexp::{
+    multiexp_batch, multiexp_with_progress, DensityTracker, FullDensity, MultiexpProgress,
+};
 
-use crate::multicore::Worker;
+use crate::multicore::{Waiter, Worker};
+
+fn digest_lc<S: PrimeField>(state: &mut blake2s_simd::State, lc: &LinearCombination<S>) {
+    state.update(&(lc.0.len() as u64).to_le_bytes());
+    for &(var, coeff) in lc.0.iter() {
+        let (tag, index) = match var.get_unchecked() {
+            Index::Input(i) => (0u8, i),
+            Index::Aux(i) => (1u8, i),
+        };
+        state.update(&[tag]);
+        state.update(&(index as u64).to_le_bytes());
+        state.update(coeff.to_repr().as_ref());
+    }
+}
 
 fn eval<S: PrimeField>(
     lc: &LinearCombination<S>,
@@ -66,6 +86,43 @@ struct ProvingAssignment<S: PrimeField> {
     // Assignments of variables
     input_assignment: Vec<S>,
     aux_assignment: Vec<S>,
+
+    // Committed auxiliary variables, in the order they were committed
+    committed: Vec<usize>,
+
+    // Digest of the constraints, only kept when proving a batch
+    shape: Option<blake2s_simd::State>,
+}
+
+impl<S: PrimeField> ProvingAssignment<S> {
+    fn new() -> Self {
+        ProvingAssignment {
+            a_aux_density: DensityTracker::new(),
+            b_input_density: DensityTracker::new(),
+            b_aux_density: DensityTracker::new(),
+            a: vec![],
+            b: vec![],
+            c: vec![],
+            input_assignment: vec![],
+            aux_assignment: vec![],
+            committed: vec![],
+            shape: None,
+        }
+    }
+
+    /// Like `new`, but also digesting every constraint so that the constraint
+    /// systems of several circuits can be compared.
+    fn with_shape_digest() -> Self {
+        ProvingAssignment {
+            shape: Some(
+                Blake2sParams::new()
+                    .hash_length(32)
+                    .personal(b"G16shape")
+                    .to_state(),
+            ),
+            ..Self::new()
+        }
+    }
 }
 
 impl<S: PrimeField> ConstraintSystem<S> for ProvingAssignment<S> {
@@ -108,6 +165,12 @@ impl<S: PrimeField> ConstraintSystem<S> for ProvingAssignment<S> {
         let b = b(LinearCombination::zero());
         let c = c(LinearCombination::zero());
 
+        if let Some(shape) = self.shape.as_mut() {
+            for lc in [&a, &b, &c].iter() {
+                digest_lc(shape, lc);
+            }
+        }
+
         self.a.push(Scalar(eval(
             &a,
             // Inputs have full density in the A query
@@ -153,6 +216,153 @@ impl<S: PrimeField> ConstraintSystem<S> for ProvingAssignment<S> {
     fn get_root(&mut self) -> &mut Self::Root {
         self
     }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        record_commitment(&mut self.committed, self.aux_assignment.len(), variable)
+    }
+}
+
+/// A stage of proof generation, as reported to a [`ProvingObserver`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvingStage {
+    /// Synthesizing the circuit into its assignment.
+    Synthesis,
+    /// The FFTs computing the coefficients of the quotient polynomial `h`.
+    Fft,
+    /// The multiexp over the H query.
+    H,
+    /// The multiexp over the L query.
+    L,
+    /// The multiexps over the A query.
+    A,
+    /// The multiexps over the G1 part of the B query.
+    BG1,
+    /// The multiexps over the G2 part of the B query.
+    BG2,
+}
+
+/// Receives timings and progress from [`create_proof_with_context`].
+pub trait ProvingObserver {
+    /// Called once `stage` has finished, `elapsed` after it started.
+    ///
+    /// The multiexps all run concurrently: each of them is timed from the
+    /// moment they are launched, so their timings overlap.
+    fn stage_finished(&mut self, stage: ProvingStage, elapsed: Duration);
+
+    /// Called while the FFT and multiexp stages run, with the number of
+    /// elements `stage` has processed out of its `total`, and once more with
+    /// `done == total` just before [`stage_finished`](Self::stage_finished).
+    ///
+    /// The FFT stage counts the domain elements of the seven FFTs computing
+    /// `h` and reports after each of them. A multiexp stage counts the
+    /// exponents of its multiexps, and is reported every
+    /// [`PROGRESS_INTERVAL`] while the prover waits for it. Since the
+    /// multiexps are waited for one stage after the other, a stage may
+    /// already be done by the time it is first reported.
+    fn stage_progress(&mut self, stage: ProvingStage, done: usize, total: usize) {
+        let _ = (stage, done, total);
+    }
+}
+
+/// How often [`ProvingObserver::stage_progress`] is called while the prover
+/// waits for a multiexp stage.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Ignores all reports.
+impl ProvingObserver for () {
+    fn stage_finished(&mut self, _: ProvingStage, _: Duration) {}
+}
+
+impl<F: FnMut(ProvingStage, Duration)> ProvingObserver for F {
+    fn stage_finished(&mut self, stage: ProvingStage, elapsed: Duration) {
+        self(stage, elapsed)
+    }
+}
+
+/// Observation and cancellation for a single proof generation.
+///
+/// The [`CancellationToken`] is checked after synthesis, between the FFT
+/// rounds and periodically inside each multiexp; once it has been triggered,
+/// proof generation stops with `SynthesisError::Cancelled`.
+pub struct ProvingContext<'a> {
+    observer: Option<&'a mut dyn ProvingObserver>,
+    cancel: Option<&'a CancellationToken>,
+}
+
+impl<'a> ProvingContext<'a> {
+    pub fn new(observer: &'a mut dyn ProvingObserver, cancel: &'a CancellationToken) -> Self {
+        ProvingContext {
+            observer: Some(observer),
+            cancel: Some(cancel),
+        }
+    }
+
+    /// A context that reports nothing and can't be cancelled.
+    fn silent() -> Self {
+        ProvingContext {
+            observer: None,
+            cancel: None,
+        }
+    }
+
+    fn check(&self) -> Result<(), SynthesisError> {
+        match self.cancel {
+            Some(cancel) if cancel.is_cancelled() => Err(SynthesisError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    fn flag(&self) -> Option<Arc<AtomicBool>> {
+        self.cancel.map(|cancel| cancel.flag())
+    }
+
+    fn finished(&mut self, stage: ProvingStage, start: Instant) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.stage_finished(stage, start.elapsed());
+        }
+    }
+
+    fn progress(&mut self, stage: ProvingStage, done: usize, total: usize) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.stage_progress(stage, done, total);
+        }
+    }
+
+    /// Waits for the multiexps of `stage`, reporting their `progress` while
+    /// they run and once they are done.
+    fn wait<G: AddAssign<G>>(
+        &mut self,
+        stage: ProvingStage,
+        waiters: Vec<Waiter<Result<G, SynthesisError>>>,
+        progress: &MultiexpProgress,
+    ) -> Result<G, SynthesisError> {
+        let mut sum: Option<G> = None;
+        for mut waiter in waiters {
+            let part = loop {
+                if self.observer.is_none() {
+                    break waiter.wait()?;
+                }
+                match waiter.wait_timeout(PROGRESS_INTERVAL) {
+                    Ok(part) => break part?,
+                    Err(pending) => {
+                        let (done, total) = progress.exponents();
+                        self.progress(stage, done, total);
+                        waiter = pending;
+                    }
+                }
+            };
+            sum = Some(match sum {
+                Some(mut sum) => {
+                    sum += part;
+                    sum
+                }
+                None => part,
+            });
+        }
+        let (done, total) = progress.exponents();
+        self.progress(stage, done, total);
+        Ok(sum.expect("every stage has a multiexp"))
+    }
 }
 
 pub fn create_random_proof<E, C, R, P: ParameterSource<E>>(
@@ -172,10 +382,65 @@ where
     create_proof::<E, C, P>(circuit, params, r, s)
 }
 
+/// Re-randomizes `proof` into a fresh proof of the same statement, without
+/// knowledge of the witness.
+///
+/// Groth16 proofs are malleable: for random `r1 != 0` and `r2`, the proof
+/// `(A / r1, r1 * B + r1 * r2 * delta, C + r2 * A)` verifies whenever `proof`
+/// does, and is distributed like a proof created with fresh randomness.
+///
+/// The commitment `D` and its proof of knowledge are re-blinded with a fresh
+/// `v'` as well, with `C` compensating for the change of `D`, so that the
+/// result can't be linked to `proof` through them. An opening of the
+/// commitment of `proof` therefore doesn't open the commitment of the result.
+pub fn rerandomize_proof<E, R>(vk: &VerifyingKey<E>, proof: &Proof<E>, rng: &mut R) -> Proof<E>
+where
+    E: Engine,
+    R: RngCore,
+{
+    let r1 = loop {
+        let r1 = E::Fr::random(&mut *rng);
+        if !bool::from(r1.is_zero()) {
+            break r1;
+        }
+    };
+    let r2 = E::Fr::random(&mut *rng);
+    // Proofs under keys without a commitment key keep D the identity.
+    let v = if vk.commitment_key.is_empty() {
+        E::Fr::zero()
+    } else {
+        E::Fr::random(&mut *rng)
+    };
+
+    let a = proof.a * r1.invert().unwrap();
+
+    let mut b = proof.b * r1;
+    b.add_assign(&(vk.delta_g2 * (r1 * r2)));
+
+    let mut c = proof.c.to_curve();
+    c.add_assign(&(proof.a * r2));
+    c.sub_assign(&(vk.eta_delta_inv_g1 * v));
+
+    let d = proof.d.to_curve() + vk.eta_gamma_inv_g1 * v;
+    let d_pok = proof.d_pok.to_curve() + vk.sigma_eta_gamma_inv_g1 * v;
+
+    Proof {
+        a: a.to_affine(),
+        b: b.to_affine(),
+        c: c.to_affine(),
+        d: d.to_affine(),
+        d_pok: d_pok.to_affine(),
+    }
+}
+
+/// Creates a proof with the randomness `r` and `s`.
+///
+/// Returns `SynthesisError::MissingCommitmentBlinding` if the circuit commits
+/// to variables; use [`create_committed_proof`] for those.
 #[allow(clippy::many_single_char_names)]
 pub fn create_proof<E, C, P: ParameterSource<E>>(
     circuit: C,
-    mut params: P,
+    params: P,
     r: E::Fr,
     s: E::Fr,
 ) -> Result<Proof<E>, SynthesisError>
@@ -184,21 +449,458 @@ where
     E::Fr: PrimeFieldBits,
     C: Circuit<E::Fr>,
 {
-    let mut prover = ProvingAssignment {
-        a_aux_density: DensityTracker::new(),
-        b_input_density: DensityTracker::new(),
-        b_aux_density: DensityTracker::new(),
-        a: vec![],
-        b: vec![],
-        c: vec![],
-        input_assignment: vec![],
-        aux_assignment: vec![],
-    };
+    let mut prover = ProvingAssignment::<E::Fr>::new();
 
     prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
 
     circuit.synthesize(&mut prover)?;
 
+    prove(prover, params, r, s, None, &mut ProvingContext::silent()).map(|(proof, _)| proof)
+}
+
+/// Creates a proof with the randomness `r` and `s` of a circuit that may
+/// commit to variables, together with the opening of its commitment.
+///
+/// The proof's `d` is a Pedersen commitment to the values of the committed
+/// variables, blinded by `v`, which must be random and secret for the
+/// commitment to hide them. It is the point at infinity if the circuit
+/// commits to nothing.
+#[allow(clippy::many_single_char_names)]
+pub fn create_committed_proof<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    v: E::Fr,
+) -> Result<(Proof<E>, CommitmentOpening<E::Fr>), SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    let mut prover = ProvingAssignment::<E::Fr>::new();
+
+    prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut prover)?;
+
+    prove(prover, params, r, s, Some(v), &mut ProvingContext::silent())
+}
+
+/// Like [`create_proof`], but reports the duration of each stage to the
+/// observer of `ctx` and stops with `SynthesisError::Cancelled` once its
+/// token has been triggered.
+pub fn create_proof_with_context<E, C, P: ParameterSource<E>>(
+    circuit: C,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    ctx: &mut ProvingContext<'_>,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    let start = Instant::now();
+    let mut prover = ProvingAssignment::<E::Fr>::new();
+
+    prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut prover)?;
+    ctx.finished(ProvingStage::Synthesis, start);
+
+    prove(prover, params, r, s, None, ctx).map(|(proof, _)| proof)
+}
+
+/// Creates a proof with `r` and `s` derived from a digest of the proving key,
+/// the circuit's assignment and `extra_entropy`, in the spirit of RFC 6979, so
+/// that the same inputs always yield the same proof.
+///
+/// The digest covers every serialized section of `params`, so proving keys that
+/// share a verifying key, such as keys from different ceremony transcripts,
+/// derive different `r` and `s`. The derived values are a pseudorandom function of the witness, so proofs
+/// stay zero-knowledge as long as the witness can't be guessed. If it can,
+/// anyone could recompute the proof for each candidate and compare; pass
+/// secret random bytes as `extra_entropy` in that case.
+pub fn create_deterministic_proof<E, C>(
+    circuit: C,
+    params: &Parameters<E>,
+    extra_entropy: &[u8],
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+{
+    let mut prover = ProvingAssignment::<E::Fr>::new();
+
+    prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+    circuit.synthesize(&mut prover)?;
+
+    let mut key = Blake2sParams::new()
+        .hash_length(32)
+        .personal(b"G16_pkey")
+        .to_state();
+    params.write(&mut key)?;
+
+    let mut seed = Blake2sParams::new()
+        .hash_length(32)
+        .personal(b"G16_seed")
+        .to_state();
+    seed.update(key.finalize().as_bytes());
+    for assignment in [&prover.input_assignment, &prover.aux_assignment].iter() {
+        seed.update(&(assignment.len() as u64).to_le_bytes());
+        for value in assignment.iter() {
+            seed.update(value.to_repr().as_ref());
+        }
+    }
+    seed.update(&(extra_entropy.len() as u64).to_le_bytes());
+    seed.update(extra_entropy);
+    let seed = seed.finalize();
+
+    let derive = |label: &[u8]| -> E::Fr {
+        for counter in 0u32.. {
+            // Reduce 512 bits into the field, so that the bias is negligible.
+            let mut value = E::Fr::zero();
+            for half in 0..2u8 {
+                let digest = Blake2sParams::new()
+                    .hash_length(32)
+                    .personal(b"G16_rand")
+                    .to_state()
+                    .update(seed.as_bytes())
+                    .update(label)
+                    .update(&counter.to_le_bytes())
+                    .update(&[half])
+                    .finalize();
+                for byte in digest.as_bytes() {
+                    value = value * E::Fr::from(256) + E::Fr::from(*byte as u64);
+                }
+            }
+            if !value.is_zero_vartime() {
+                return value;
+            }
+        }
+        unreachable!()
+    };
+    let r = derive(b"r");
+    let s = derive(b"s");
+
+    prove(prover, params, r, s, None, &mut ProvingContext::silent()).map(|(proof, _)| proof)
+}
+
+/// Creates a proof from a witness generated with
+/// [`generate_witness`](super::generate_witness) and the constraint system of
+/// its circuit, without synthesizing the circuit.
+///
+/// Returns `SynthesisError::AssignmentMissing` if the witness doesn't assign
+/// exactly the variables of `r1cs`.
+pub fn create_proof_from_witness<E, P: ParameterSource<E>>(
+    r1cs: &R1cs<E::Fr>,
+    witness: &Witness<E::Fr>,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+) -> Result<Proof<E>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+{
+    prove_witness(r1cs, witness, params, r, s, None).map(|(proof, _)| proof)
+}
+
+/// Creates a proof from a witness like [`create_proof_from_witness`], with
+/// the commitment blinding `v` if given.
+pub(super) fn prove_witness<E, P: ParameterSource<E>>(
+    r1cs: &R1cs<E::Fr>,
+    witness: &Witness<E::Fr>,
+    params: P,
+    r: E::Fr,
+    s: E::Fr,
+    v: Option<E::Fr>,
+) -> Result<(Proof<E>, CommitmentOpening<E::Fr>), SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+{
+    if witness.input_assignment.len() != r1cs.num_inputs()
+        || witness.aux_assignment.len() != r1cs.num_aux()
+    {
+        return Err(SynthesisError::AssignmentMissing);
+    }
+
+    let mut prover = ProvingAssignment::<E::Fr>::new();
+
+    for &input in &witness.input_assignment {
+        prover.alloc_input(|| "", || Ok(input))?;
+    }
+    for &aux in &witness.aux_assignment {
+        prover.alloc(|| "", || Ok(aux))?;
+    }
+    for (a, b, c) in r1cs.constraints() {
+        prover.enforce(|| "", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+    }
+    for &i in r1cs.committed() {
+        prover.commit(Variable(Index::Aux(i)))?;
+    }
+
+    prove(prover, params, r, s, v, &mut ProvingContext::silent())
+}
+
+/// Creates a proof for each of `circuits` with fresh randomness, amortizing
+/// the work that [`create_proof`] repeats for every proof.
+///
+/// The circuits must be instances of the same circuit, i.e. synthesize to the
+/// same constraints with different assignments; otherwise this returns
+/// `SynthesisError::MismatchedCircuits`. The constraints are compared through
+/// a digest of their linear combinations, so the circuits must also build them
+/// term by term in the same order. Circuits that commit to variables are not
+/// supported and yield `SynthesisError::MissingCommitmentBlinding`, and a
+/// key with a commitment key yields `SynthesisError::InvalidCommitment`. The
+/// parameters are read once, the
+/// evaluation domain's roots of unity and coset powers are computed once and
+/// shared by every FFT, and each query is processed by a single
+/// [`multiexp_batch`](crate::multiexp::multiexp_batch) that reads every base
+/// once for all instances. The multiexps that don't depend on the quotient
+/// polynomial run while the FFTs of every instance are being computed.
+#[allow(clippy::many_single_char_names)]
+pub fn create_proofs_batch<E, C, I, P: ParameterSource<E>, R>(
+    circuits: I,
+    mut params: P,
+    rng: &mut R,
+) -> Result<Vec<Proof<E>>, SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+    C: Circuit<E::Fr>,
+    I: IntoIterator<Item = C>,
+    R: RngCore,
+{
+    let provers = circuits
+        .into_iter()
+        .map(|circuit| -> Result<_, SynthesisError> {
+            let mut prover = ProvingAssignment::<E::Fr>::with_shape_digest();
+
+            prover.alloc_input(|| "", || Ok(E::Fr::one()))?;
+
+            circuit.synthesize(&mut prover)?;
+
+            for i in 0..prover.input_assignment.len() {
+                prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
+            }
+
+            Ok(prover)
+        })
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+    let first = match provers.first() {
+        Some(first) => first,
+        None => return Ok(vec![]),
+    };
+    let num_inputs = first.input_assignment.len();
+    let num_aux = first.aux_assignment.len();
+    let num_constraints = first.a.len();
+    if provers.iter().any(|prover| !prover.committed.is_empty()) {
+        return Err(SynthesisError::MissingCommitmentBlinding);
+    }
+    let shape = first.shape.as_ref().map(|state| state.finalize());
+    if provers.iter().any(|prover| {
+        prover.input_assignment.len() != num_inputs
+            || prover.aux_assignment.len() != num_aux
+            || prover.a.len() != num_constraints
+            || prover.shape.as_ref().map(|state| state.finalize()) != shape
+    }) {
+        return Err(SynthesisError::MismatchedCircuits);
+    }
+
+    let worker = Worker::new();
+
+    let vk = params.get_vk(num_inputs)?;
+    // The circuits don't commit to anything, so neither may the key.
+    if !vk.commitment_key.is_empty() || !vk.commitment_pok_key.is_empty() {
+        return Err(SynthesisError::InvalidCommitment);
+    }
+
+    if bool::from(vk.delta_g1.is_identity() | vk.delta_g2.is_identity()) {
+        // If this element is zero, someone is trying to perform a
+        // subversion-CRS attack.
+        return Err(SynthesisError::UnexpectedIdentity);
+    }
+
+    let mut densities = None;
+    let mut polynomials = Vec::with_capacity(provers.len());
+    let mut input_assignments = Vec::with_capacity(provers.len());
+    let mut aux_assignments = Vec::with_capacity(provers.len());
+    for prover in provers {
+        polynomials.push((prover.a, prover.b, prover.c));
+        input_assignments.push(Arc::new(
+            prover
+                .input_assignment
+                .into_iter()
+                .map(|s| s.to_le_bits())
+                .collect::<Vec<_>>(),
+        ));
+        aux_assignments.push(Arc::new(
+            prover
+                .aux_assignment
+                .into_iter()
+                .map(|s| s.to_le_bits())
+                .collect::<Vec<_>>(),
+        ));
+        densities.get_or_insert((
+            prover.a_aux_density,
+            prover.b_input_density,
+            prover.b_aux_density,
+        ));
+    }
+    let (a_aux_density, b_input_density, b_aux_density) = densities.unwrap();
+
+    // Start the multiexps over the assignments before the FFTs, so that the
+    // two share the worker.
+    let l = multiexp_batch(
+        &worker,
+        params.get_l(num_aux)?,
+        FullDensity,
+        aux_assignments.clone(),
+    );
+
+    let (a_inputs_source, a_aux_source) =
+        params.get_a(num_inputs, a_aux_density.get_total_density())?;
+
+    let a_inputs = multiexp_batch(
+        &worker,
+        a_inputs_source,
+        FullDensity,
+        input_assignments.clone(),
+    );
+    let a_aux = multiexp_batch(
+        &worker,
+        a_aux_source,
+        Arc::new(a_aux_density),
+        aux_assignments.clone(),
+    );
+
+    let b_input_density = Arc::new(b_input_density);
+    let b_input_density_total = b_input_density.get_total_density();
+    let b_aux_density = Arc::new(b_aux_density);
+    let b_aux_density_total = b_aux_density.get_total_density();
+
+    let (b_g1_inputs_source, b_g1_aux_source) =
+        params.get_b_g1(b_input_density_total, b_aux_density_total)?;
+
+    let b_g1_inputs = multiexp_batch(
+        &worker,
+        b_g1_inputs_source,
+        b_input_density.clone(),
+        input_assignments.clone(),
+    );
+    let b_g1_aux = multiexp_batch(
+        &worker,
+        b_g1_aux_source,
+        b_aux_density.clone(),
+        aux_assignments.clone(),
+    );
+
+    let (b_g2_inputs_source, b_g2_aux_source) =
+        params.get_b_g2(b_input_density_total, b_aux_density_total)?;
+
+    let b_g2_inputs = multiexp_batch(
+        &worker,
+        b_g2_inputs_source,
+        b_input_density,
+        input_assignments,
+    );
+    let b_g2_aux = multiexp_batch(&worker, b_g2_aux_source, b_aux_density, aux_assignments);
+
+    let tables = Arc::new(DomainTables::new(&worker, num_constraints)?);
+    let h_exponents = polynomials
+        .into_iter()
+        .map(|(a, b, c)| -> Result<_, SynthesisError> {
+            let mut a = EvaluationDomain::from_coeffs_with_tables(a, tables.clone())?;
+            let mut b = EvaluationDomain::from_coeffs_with_tables(b, tables.clone())?;
+            let mut c = EvaluationDomain::from_coeffs_with_tables(c, tables.clone())?;
+            a.ifft(&worker);
+            a.coset_fft(&worker);
+            b.ifft(&worker);
+            b.coset_fft(&worker);
+            c.ifft(&worker);
+            c.coset_fft(&worker);
+
+            a.mul_assign(&worker, &b);
+            drop(b);
+            a.sub_assign(&worker, &c);
+            drop(c);
+            a.divide_by_z_on_coset(&worker);
+            a.icoset_fft(&worker);
+            let mut a = a.into_coeffs();
+            let a_len = a.len() - 1;
+            a.truncate(a_len);
+
+            Ok(Arc::new(
+                a.into_iter().map(|s| s.0.to_le_bits()).collect::<Vec<_>>(),
+            ))
+        })
+        .collect::<Result<Vec<_>, SynthesisError>>()?;
+    let h = multiexp_batch(
+        &worker,
+        params.get_h(tables.size() - 1)?,
+        FullDensity,
+        h_exponents,
+    );
+
+    let a_inputs: Vec<E::G1> = a_inputs.wait()?;
+    let a_aux: Vec<E::G1> = a_aux.wait()?;
+    let b_g1_inputs: Vec<E::G1> = b_g1_inputs.wait()?;
+    let b_g1_aux: Vec<E::G1> = b_g1_aux.wait()?;
+    let b_g2_inputs: Vec<E::G2> = b_g2_inputs.wait()?;
+    let b_g2_aux: Vec<E::G2> = b_g2_aux.wait()?;
+    let h: Vec<E::G1> = h.wait()?;
+    let l: Vec<E::G1> = l.wait()?;
+
+    let mut proofs = Vec::with_capacity(h.len());
+    for (i, (h, l)) in h.into_iter().zip(l).enumerate() {
+        let r = E::Fr::random(&mut *rng);
+        let s = E::Fr::random(&mut *rng);
+
+        proofs.push(assemble(
+            &vk,
+            r,
+            s,
+            a_inputs[i] + a_aux[i],
+            b_g1_inputs[i] + b_g1_aux[i],
+            b_g2_inputs[i] + b_g2_aux[i],
+            h,
+            l,
+            &[],
+            E::Fr::zero(),
+        ));
+    }
+
+    Ok(proofs)
+}
+
+#[allow(clippy::many_single_char_names)]
+fn prove<E, P: ParameterSource<E>>(
+    mut prover: ProvingAssignment<E::Fr>,
+    mut params: P,
+    r: E::Fr,
+    s: E::Fr,
+    v: Option<E::Fr>,
+    ctx: &mut ProvingContext<'_>,
+) -> Result<(Proof<E>, CommitmentOpening<E::Fr>), SynthesisError>
+where
+    E: Engine,
+    E::Fr: PrimeFieldBits,
+{
+    ctx.check()?;
+
+    let v = match v {
+        Some(v) => v,
+        None if prover.committed.is_empty() => E::Fr::zero(),
+        None => return Err(SynthesisError::MissingCommitmentBlinding),
+    };
+
     for i in 0..prover.input_assignment.len() {
         prover.enforce(|| "", |lc| lc + Variable(Index::Input(i)), |lc| lc, |lc| lc);
     }
@@ -206,17 +908,30 @@ where
     let worker = Worker::new();
 
     let vk = params.get_vk(prover.input_assignment.len())?;
+    if vk.commitment_key.len() != prover.committed.len()
+        || vk.commitment_pok_key.len() != prover.committed.len()
+    {
+        return Err(SynthesisError::InvalidCommitment);
+    }
+    let cancel = ctx.flag();
 
+    let fft_start = Instant::now();
+    let h_progress = Arc::new(MultiexpProgress::new());
     let h = {
         let mut a = EvaluationDomain::from_coeffs(prover.a)?;
         let mut b = EvaluationDomain::from_coeffs(prover.b)?;
         let mut c = EvaluationDomain::from_coeffs(prover.c)?;
-        a.ifft(&worker);
-        a.coset_fft(&worker);
-        b.ifft(&worker);
-        b.coset_fft(&worker);
-        c.ifft(&worker);
-        c.coset_fft(&worker);
+        // Three FFTs in and out of the coset for each of a, b and c, and one
+        // back out of the coset for h.
+        let m = a.as_ref().len();
+        let total = 7 * m;
+        for (i, p) in [&mut a, &mut b, &mut c].iter_mut().enumerate() {
+            p.ifft(&worker);
+            ctx.progress(ProvingStage::Fft, (2 * i + 1) * m, total);
+            p.coset_fft(&worker);
+            ctx.progress(ProvingStage::Fft, (2 * i + 2) * m, total);
+            ctx.check()?;
+        }
 
         a.mul_assign(&worker, &b);
         drop(b);
@@ -224,14 +939,31 @@ where
         drop(c);
         a.divide_by_z_on_coset(&worker);
         a.icoset_fft(&worker);
+        ctx.progress(ProvingStage::Fft, total, total);
         let mut a = a.into_coeffs();
         let a_len = a.len() - 1;
         a.truncate(a_len);
         // TODO: parallelize if it's even helpful
         let a = Arc::new(a.into_iter().map(|s| s.0.to_le_bits()).collect::<Vec<_>>());
+        ctx.finished(ProvingStage::Fft, fft_start);
 
-        multiexp(&worker, params.get_h(a.len())?, FullDensity, a)
+        multiexp_with_progress(
+            &worker,
+            params.get_h(a.len())?,
+            FullDensity,
+            a,
+            cancel.clone(),
+            h_progress.clone(),
+        )
     };
+    let multiexp_start = Instant::now();
+
+    let aux_values = &prover.aux_assignment;
+    let committed_values = prover
+        .committed
+        .iter()
+        .map(|&i| aux_values[i])
+        .collect::<Vec<_>>();
 
     // TODO: parallelize if it's even helpful
     let input_assignment = Arc::new(
@@ -249,11 +981,32 @@ where
             .collect::<Vec<_>>(),
     );
 
-    let l = multiexp(
+    // The committed variables are covered by the commitment instead of the
+    // L query.
+    let l_assignment = if prover.committed.is_empty() {
+        aux_assignment.clone()
+    } else {
+        let mut committed = vec![false; aux_assignment.len()];
+        for &i in &prover.committed {
+            committed[i] = true;
+        }
+        Arc::new(
+            aux_assignment
+                .iter()
+                .zip(committed)
+                .filter(|(_, committed)| !committed)
+                .map(|(s, _)| s.clone())
+                .collect::<Vec<_>>(),
+        )
+    };
+    let l_progress = Arc::new(MultiexpProgress::new());
+    let l = multiexp_with_progress(
         &worker,
-        params.get_l(aux_assignment.len())?,
+        params.get_l(l_assignment.len())?,
         FullDensity,
-        aux_assignment.clone(),
+        l_assignment,
+        cancel.clone(),
+        l_progress.clone(),
     );
 
     let a_aux_density_total = prover.a_aux_density.get_total_density();
@@ -261,17 +1014,22 @@ where
     let (a_inputs_source, a_aux_source) =
         params.get_a(input_assignment.len(), a_aux_density_total)?;
 
-    let a_inputs = multiexp(
+    let a_progress = Arc::new(MultiexpProgress::new());
+    let a_inputs = multiexp_with_progress(
         &worker,
         a_inputs_source,
         FullDensity,
         input_assignment.clone(),
+        cancel.clone(),
+        a_progress.clone(),
     );
-    let a_aux = multiexp(
+    let a_aux = multiexp_with_progress(
         &worker,
         a_aux_source,
         Arc::new(prover.a_aux_density),
         aux_assignment.clone(),
+        cancel.clone(),
+        a_progress.clone(),
     );
 
     let b_input_density = Arc::new(prover.b_input_density);
@@ -282,29 +1040,44 @@ where
     let (b_g1_inputs_source, b_g1_aux_source) =
         params.get_b_g1(b_input_density_total, b_aux_density_total)?;
 
-    let b_g1_inputs = multiexp(
+    let b_g1_progress = Arc::new(MultiexpProgress::new());
+    let b_g1_inputs = multiexp_with_progress(
         &worker,
         b_g1_inputs_source,
         b_input_density.clone(),
         input_assignment.clone(),
+        cancel.clone(),
+        b_g1_progress.clone(),
     );
-    let b_g1_aux = multiexp(
+    let b_g1_aux = multiexp_with_progress(
         &worker,
         b_g1_aux_source,
         b_aux_density.clone(),
         aux_assignment.clone(),
+        cancel.clone(),
+        b_g1_progress.clone(),
     );
 
     let (b_g2_inputs_source, b_g2_aux_source) =
         params.get_b_g2(b_input_density_total, b_aux_density_total)?;
 
-    let b_g2_inputs = multiexp(
+    let b_g2_progress = Arc::new(MultiexpProgress::new());
+    let b_g2_inputs = multiexp_with_progress(
         &worker,
         b_g2_inputs_source,
         b_input_density,
         input_assignment,
+        cancel.clone(),
+        b_g2_progress.clone(),
+    );
+    let b_g2_aux = multiexp_with_progress(
+        &worker,
+        b_g2_aux_source,
+        b_aux_density,
+        aux_assignment,
+        cancel,
+        b_g2_progress.clone(),
     );
-    let b_g2_aux = multiexp(&worker, b_g2_aux_source, b_aux_density, aux_assignment);
 
     if bool::from(vk.delta_g1.is_identity() | vk.delta_g2.is_identity()) {
         // If this element is zero, someone is trying to perform a
@@ -312,6 +1085,62 @@ where
         return Err(SynthesisError::UnexpectedIdentity);
     }
 
+    let a_answer: E::G1 = ctx.wait(ProvingStage::A, vec![a_inputs, a_aux], &a_progress)?;
+    ctx.finished(ProvingStage::A, multiexp_start);
+
+    let b1_answer: E::G1 = ctx.wait(
+        ProvingStage::BG1,
+        vec![b_g1_inputs, b_g1_aux],
+        &b_g1_progress,
+    )?;
+    ctx.finished(ProvingStage::BG1, multiexp_start);
+    let b2_answer: E::G2 = ctx.wait(
+        ProvingStage::BG2,
+        vec![b_g2_inputs, b_g2_aux],
+        &b_g2_progress,
+    )?;
+    ctx.finished(ProvingStage::BG2, multiexp_start);
+
+    let h: E::G1 = ctx.wait(ProvingStage::H, vec![h], &h_progress)?;
+    ctx.finished(ProvingStage::H, multiexp_start);
+    let l: E::G1 = ctx.wait(ProvingStage::L, vec![l], &l_progress)?;
+    ctx.finished(ProvingStage::L, multiexp_start);
+
+    let proof = assemble(
+        &vk,
+        r,
+        s,
+        a_answer,
+        b1_answer,
+        b2_answer,
+        h,
+        l,
+        &committed_values,
+        v,
+    );
+    let opening = CommitmentOpening {
+        values: committed_values,
+        blinding: v,
+    };
+
+    Ok((proof, opening))
+}
+
+/// Combines the results of the multiexps of a proof with its randomness `r`
+/// and `s`, and commits to `committed_values` with the blinding `v`.
+#[allow(clippy::too_many_arguments)]
+fn assemble<E: Engine>(
+    vk: &VerifyingKey<E>,
+    r: E::Fr,
+    s: E::Fr,
+    mut a_answer: E::G1,
+    mut b1_answer: E::G1,
+    b2_answer: E::G2,
+    h: E::G1,
+    l: E::G1,
+    committed_values: &[E::Fr],
+    v: E::Fr,
+) -> Proof<E> {
     let mut g_a = vk.delta_g1 * r;
     AddAssign::<&E::G1Affine>::add_assign(&mut g_a, &vk.alpha_g1);
     let mut g_b = vk.delta_g2 * s;
@@ -325,26 +1154,36 @@ where
         AddAssign::<&E::G1>::add_assign(&mut g_c, &(vk.alpha_g1 * s));
         AddAssign::<&E::G1>::add_assign(&mut g_c, &(vk.beta_g1 * r));
     }
-    let mut a_answer = a_inputs.wait()?;
-    AddAssign::<&E::G1>::add_assign(&mut a_answer, &a_aux.wait()?);
     AddAssign::<&E::G1>::add_assign(&mut g_a, &a_answer);
     MulAssign::<E::Fr>::mul_assign(&mut a_answer, s);
     AddAssign::<&E::G1>::add_assign(&mut g_c, &a_answer);
 
-    let mut b1_answer: E::G1 = b_g1_inputs.wait()?;
-    AddAssign::<&E::G1>::add_assign(&mut b1_answer, &b_g1_aux.wait()?);
-    let mut b2_answer = b_g2_inputs.wait()?;
-    AddAssign::<&E::G2>::add_assign(&mut b2_answer, &b_g2_aux.wait()?);
-
     AddAssign::<&E::G2>::add_assign(&mut g_b, &b2_answer);
     MulAssign::<E::Fr>::mul_assign(&mut b1_answer, r);
     AddAssign::<&E::G1>::add_assign(&mut g_c, &b1_answer);
-    AddAssign::<&E::G1>::add_assign(&mut g_c, &h.wait()?);
-    AddAssign::<&E::G1>::add_assign(&mut g_c, &l.wait()?);
+    AddAssign::<&E::G1>::add_assign(&mut g_c, &h);
+    AddAssign::<&E::G1>::add_assign(&mut g_c, &l);
 
-    Ok(Proof {
+    // The blinding of the commitment is paired with gamma by the verifier,
+    // and cancelled here by pairing with delta. The proof of knowledge is the
+    // same combination of the sigma-scaled key.
+    let mut g_d = vk.eta_gamma_inv_g1 * v;
+    let mut g_d_pok = vk.sigma_eta_gamma_inv_g1 * v;
+    for ((value, base), pok_base) in committed_values
+        .iter()
+        .zip(vk.commitment_key.iter())
+        .zip(vk.commitment_pok_key.iter())
+    {
+        AddAssign::<&E::G1>::add_assign(&mut g_d, &(*base * *value));
+        AddAssign::<&E::G1>::add_assign(&mut g_d_pok, &(*pok_base * *value));
+    }
+    SubAssign::<&E::G1>::sub_assign(&mut g_c, &(vk.eta_delta_inv_g1 * v));
+
+    Proof {
         a: g_a.to_affine(),
         b: g_b.to_affine(),
         c: g_c.to_affine(),
-    })
+        d: g_d.to_affine(),
+        d_pok: g_d_pok.to_affine(),
+    }
 }
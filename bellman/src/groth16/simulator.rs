@@ -0,0 +1,144 @@
+//! Simulation of proofs from the toxic waste of a circuit's parameters.
+//!
+//! Anyone who knows the toxic waste can forge proofs of arbitrary statements,
+//! which is exactly what makes it useful for testing verifiers without
+//! building real witnesses. This module is only compiled with the
+//! `test-only-proof-simulator` feature, which must never be enabled in
+//! production builds.
+
+use ff::Field;
+use group::{prime::PrimeCurveAffine, Curve};
+use pairing::Engine;
+use rand_core::RngCore;
+use std::ops::AddAssign;
+
+use super::{Proof, VerifyingKey};
+use crate::VerificationError;
+
+/// The part of the toxic waste passed to
+/// [`generate_parameters`](super::generate_parameters) that is needed to
+/// simulate proofs. `tau` isn't.
+#[derive(Clone, Copy, Debug)]
+pub struct Trapdoor<S: Field> {
+    pub alpha: S,
+    pub beta: S,
+    pub gamma: S,
+    pub delta: S,
+}
+
+/// Creates a proof that `vk` accepts for `public_inputs`, without a witness.
+///
+/// `A` and `B` are random multiples of `alpha` and `beta`, and `C` is the
+/// unique element satisfying the verification equation, so the proof is
+/// distributed like an honest one. Fails with
+/// `VerificationError::InvalidVerifyingKey` if the number of inputs doesn't
+/// match `vk`, or if `trapdoor` has a zero `alpha`, `beta` or `delta`.
+pub fn simulate_proof<E, R>(
+    trapdoor: &Trapdoor<E::Fr>,
+    vk: &VerifyingKey<E>,
+    public_inputs: &[E::Fr],
+    rng: &mut R,
+) -> Result<Proof<E>, VerificationError>
+where
+    E: Engine,
+    R: RngCore,
+{
+    if (public_inputs.len() + 1) != vk.ic.len() {
+        return Err(VerificationError::InvalidVerifyingKey);
+    }
+
+    let alpha_beta = trapdoor.alpha * trapdoor.beta;
+    if bool::from(alpha_beta.is_zero()) {
+        return Err(VerificationError::InvalidVerifyingKey);
+    }
+    let delta_inv = Option::<E::Fr>::from(trapdoor.delta.invert())
+        .ok_or(VerificationError::InvalidVerifyingKey)?;
+
+    let nonzero = |rng: &mut R| loop {
+        let s = E::Fr::random(&mut *rng);
+        if !bool::from(s.is_zero()) {
+            break s;
+        }
+    };
+    let x = nonzero(rng);
+    let y = nonzero(rng);
+
+    let mut acc = vk.ic[0].to_curve();
+    for (i, b) in public_inputs.iter().zip(vk.ic.iter().skip(1)) {
+        AddAssign::<&E::G1>::add_assign(&mut acc, &(*b * i));
+    }
+
+    // A * B = x * y * alpha * beta must equal
+    // alpha * beta + acc * gamma + C * delta, so
+    // C = ((x * y - 1) * alpha * beta / delta^2) * delta - acc * gamma / delta.
+    let c_delta = (x * y - E::Fr::one()) * alpha_beta * delta_inv.square();
+    let c = vk.delta_g1 * c_delta - acc * (trapdoor.gamma * delta_inv);
+
+    Ok(Proof {
+        a: (vk.alpha_g1 * x).to_affine(),
+        b: (vk.beta_g2 * y).to_affine(),
+        c: c.to_affine(),
+        d: E::G1Affine::identity(),
+        d_pok: E::G1Affine::identity(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bls12_381::{Bls12, G1Projective, G2Projective, Scalar};
+    use ff::Field;
+    use group::Group;
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    use super::{simulate_proof, Trapdoor};
+    use crate::groth16::{generate_parameters, prepare_verifying_key, verify_proof};
+    use crate::mimc_mod::{MiMCDemo, MIMC_ROUNDS};
+    use crate::VerificationError;
+
+    #[test]
+    fn simulated_proofs_verify() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let constants = (0..MIMC_ROUNDS)
+            .map(|_| Scalar::random(&mut rng))
+            .collect::<Vec<_>>();
+        let trapdoor = Trapdoor {
+            alpha: Scalar::random(&mut rng),
+            beta: Scalar::random(&mut rng),
+            gamma: Scalar::random(&mut rng),
+            delta: Scalar::random(&mut rng),
+        };
+        let params = generate_parameters::<Bls12, _>(
+            MiMCDemo {
+                xl: None,
+                xr: None,
+                constants: &constants,
+            },
+            G1Projective::generator(),
+            G2Projective::generator(),
+            trapdoor.alpha,
+            trapdoor.beta,
+            trapdoor.gamma,
+            trapdoor.delta,
+            Scalar::random(&mut rng),
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        // No preimage is known for any of these images.
+        for _ in 0..4 {
+            let image = Scalar::random(&mut rng);
+            let proof = simulate_proof(&trapdoor, &params.vk, &[image], &mut rng).unwrap();
+            assert!(verify_proof(&pvk, &proof, &[image]).is_ok());
+            assert!(verify_proof(&pvk, &proof, &[image + Scalar::one()]).is_err());
+        }
+
+        assert!(matches!(
+            simulate_proof(&trapdoor, &params.vk, &[], &mut rng),
+            Err(VerificationError::InvalidVerifyingKey)
+        ));
+    }
+}
@@ -7,10 +7,19 @@ use self::dummy_engine::*;
 use std::marker::PhantomData;
 use std::ops::{AddAssign, MulAssign, SubAssign};
 use std::result;
+use std::time::Duration;
 //use bls12_381::{Bls12, Scalar};
-use crate::{Circuit, ConstraintSystem, Index, SynthesisError, Variable};
-
-use super::{create_proof, generate_parameters, prepare_verifying_key, verify_proof};
+use crate::{Circuit, ConstraintSystem, Index, SynthesisError, Variable, VerificationError};
+
+use super::mpc::progress::CancellationToken;
+use super::{
+    create_committed_proof, create_deterministic_proof, create_proof, create_proof_with_context,
+    create_proofs_batch, generate_committed_parameters, generate_parameters, prepare_verifying_key,
+    prepare_verifying_key_with_tables, rerandomize_proof, validate_parameters, verify_commitment,
+    verify_proof, verify_proof_with_diagnostics, CommitmentOpening, PairingTerm,
+    ParameterCheckError, ParameterSection, Parameters, Proof, ProvingContext, ProvingObserver,
+    ProvingStage, VerificationFailure,
+};
 
 struct AndDemo<Scalar: PrimeField> {
     a: Option<bool>,
@@ -729,3 +738,823 @@ pub fn testless() {
 
     assert!(verify_proof(&pvk, &proof, &[c_except]).is_ok());
 }
+
+#[test]
+fn rerandomized_proofs_verify() {
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    for _ in 0..10 {
+        let params = {
+            let c = AndDemo {
+                a: None,
+                b: None,
+                _marker: PhantomData,
+            };
+            let mut random = || Fr::random(&mut rng);
+
+            generate_parameters::<DummyEngine, _>(
+                c,
+                Fr::one(),
+                Fr::one(),
+                random(),
+                random(),
+                random(),
+                random(),
+                random(),
+            )
+            .unwrap()
+        };
+        let pvk = prepare_verifying_key(&params.vk);
+
+        for &(a, b) in &[(false, false), (false, true), (true, false), (true, true)] {
+            let proof = {
+                let c = AndDemo {
+                    a: Some(a),
+                    b: Some(b),
+                    _marker: PhantomData,
+                };
+                let r = Fr::random(&mut rng);
+                let s = Fr::random(&mut rng);
+
+                create_proof(c, &params, r, s).unwrap()
+            };
+            let inputs = [if a && b { Fr::one() } else { Fr::zero() }];
+            assert!(verify_proof(&pvk, &proof, &inputs).is_ok());
+
+            let mut previous = proof.clone();
+            for _ in 0..5 {
+                let rerandomized = rerandomize_proof(&params.vk, &previous, &mut rng);
+                assert!(verify_proof(&pvk, &rerandomized, &inputs).is_ok());
+                assert!(rerandomized != previous);
+                previous = rerandomized;
+            }
+
+            // Re-randomizing does not make a proof valid for another statement.
+            let flipped = [Fr::one() - inputs[0]];
+            assert!(verify_proof(&pvk, &previous, &flipped).is_err());
+        }
+    }
+}
+
+#[test]
+fn deterministic_proofs() {
+    // The seed hashes the serialized proving key, which the dummy engine
+    // can't produce.
+    use bls12_381::{Bls12, Scalar};
+    use std::sync::Arc;
+
+    let params = {
+        let c = AndDemo {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_parameters::<Bls12, _>(
+            c,
+            bls12_381::G1Projective::generator(),
+            bls12_381::G2Projective::generator(),
+            Scalar::from(48577),
+            Scalar::from(22580),
+            Scalar::from(53332),
+            Scalar::from(5481),
+            Scalar::from(3673),
+        )
+        .unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let prove = |params: &Parameters<Bls12>, a, b, extra_entropy: &[u8]| {
+        let c = AndDemo {
+            a: Some(a),
+            b: Some(b),
+            _marker: PhantomData,
+        };
+
+        create_deterministic_proof(c, params, extra_entropy).unwrap()
+    };
+
+    let proof = prove(&params, true, true, b"");
+    assert!(verify_proof(&pvk, &proof, &[Scalar::one()]).is_ok());
+    assert!(proof == prove(&params, true, true, b""));
+
+    let with_entropy = prove(&params, true, true, b"entropy");
+    assert!(verify_proof(&pvk, &with_entropy, &[Scalar::one()]).is_ok());
+    assert!(with_entropy != proof);
+
+    let other_witness = prove(&params, true, false, b"");
+    assert!(verify_proof(&pvk, &other_witness, &[Scalar::zero()]).is_ok());
+    assert!(other_witness != prove(&params, false, true, b""));
+
+    // A proving key sharing the verifying key but not the H query derives
+    // another r, which shows in A, the one element H doesn't enter.
+    let mut other_key = params.clone();
+    let mut h = (*params.h).clone();
+    h[0] = bls12_381::G1Affine::from(
+        bls12_381::G1Projective::from(h[0]) + bls12_381::G1Projective::generator(),
+    );
+    other_key.h = Arc::new(h);
+    assert!(other_key.vk == params.vk);
+    assert!(prove(&other_key, true, true, b"").a != proof.a);
+}
+
+#[test]
+fn proving_context_reports_and_cancels() {
+    let params = {
+        let c = AndDemo {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_parameters::<DummyEngine, _>(
+            c,
+            Fr::one(),
+            Fr::one(),
+            Fr::from(48577),
+            Fr::from(22580),
+            Fr::from(53332),
+            Fr::from(5481),
+            Fr::from(3673),
+        )
+        .unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+    let circuit = || AndDemo {
+        a: Some(true),
+        b: Some(true),
+        _marker: PhantomData,
+    };
+    let r = Fr::from(27134);
+    let s = Fr::from(17146);
+
+    let mut stages = vec![];
+    let mut observer = |stage: ProvingStage, _: Duration| stages.push(stage);
+    let cancel = CancellationToken::new();
+    let proof = create_proof_with_context(
+        circuit(),
+        &params,
+        r,
+        s,
+        &mut ProvingContext::new(&mut observer, &cancel),
+    )
+    .unwrap();
+    assert!(verify_proof(&pvk, &proof, &[Fr::one()]).is_ok());
+    assert!(proof == create_proof(circuit(), &params, r, s).unwrap());
+    assert_eq!(
+        stages,
+        vec![
+            ProvingStage::Synthesis,
+            ProvingStage::Fft,
+            ProvingStage::A,
+            ProvingStage::BG1,
+            ProvingStage::BG2,
+            ProvingStage::H,
+            ProvingStage::L,
+        ]
+    );
+
+    // Progress is reported within the FFT and multiexp stages, and each of
+    // them ends with every element done.
+    #[derive(Default)]
+    struct Recorder {
+        progress: Vec<(ProvingStage, usize, usize)>,
+    }
+    impl ProvingObserver for Recorder {
+        fn stage_finished(&mut self, stage: ProvingStage, _: Duration) {
+            let last = self.progress.iter().rev().find(|p| p.0 == stage);
+            if stage != ProvingStage::Synthesis {
+                assert!(matches!(last, Some(&(_, done, total)) if done == total));
+            }
+        }
+        fn stage_progress(&mut self, stage: ProvingStage, done: usize, total: usize) {
+            assert!(done <= total);
+            self.progress.push((stage, done, total));
+        }
+    }
+    let mut recorder = Recorder::default();
+    create_proof_with_context(
+        circuit(),
+        &params,
+        r,
+        s,
+        &mut ProvingContext::new(&mut recorder, &cancel),
+    )
+    .unwrap();
+    // The domain of the three constraints and the input constraint has four
+    // elements, and each of the seven FFTs is reported.
+    let fft: Vec<_> = recorder
+        .progress
+        .iter()
+        .filter(|p| p.0 == ProvingStage::Fft)
+        .map(|p| p.1)
+        .collect();
+    assert_eq!(fft, vec![4, 8, 12, 16, 20, 24, 28]);
+    // One input and three aux variables for A, and the H query of the
+    // domain's size minus one.
+    assert!(recorder.progress.contains(&(ProvingStage::A, 4, 4)));
+    assert!(recorder.progress.contains(&(ProvingStage::H, 3, 3)));
+
+    cancel.cancel();
+    let result = create_proof_with_context(
+        circuit(),
+        &params,
+        r,
+        s,
+        &mut ProvingContext::new(&mut (), &cancel),
+    );
+    assert!(matches!(result, Err(SynthesisError::Cancelled)));
+}
+
+#[test]
+fn batch_proofs_verify() {
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = {
+        let c = AndDemo {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_parameters::<DummyEngine, _>(
+            c,
+            Fr::one(),
+            Fr::one(),
+            Fr::from(48577),
+            Fr::from(22580),
+            Fr::from(53332),
+            Fr::from(5481),
+            Fr::from(3673),
+        )
+        .unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let witnesses = [
+        (true, true),
+        (true, false),
+        (false, true),
+        (false, false),
+        (true, true),
+    ];
+    let circuits = witnesses.iter().map(|&(a, b)| AndDemo {
+        a: Some(a),
+        b: Some(b),
+        _marker: PhantomData,
+    });
+    let proofs = create_proofs_batch(circuits, &params, &mut rng).unwrap();
+
+    assert_eq!(proofs.len(), witnesses.len());
+    for (proof, &(a, b)) in proofs.iter().zip(witnesses.iter()) {
+        let input = if a && b { Fr::one() } else { Fr::zero() };
+        assert!(verify_proof(&pvk, proof, &[input]).is_ok());
+    }
+    assert!(proofs[0] != proofs[4]);
+
+    let none: Vec<AndDemo<Fr>> = vec![];
+    let proofs = create_proofs_batch(none, &params, &mut rng).unwrap();
+    assert!(proofs.is_empty());
+}
+
+/// Proves knowledge of `x` such that `coeff * x` is the public input.
+struct Scaled {
+    coeff: u64,
+    x: Option<Fr>,
+}
+
+impl Circuit<Fr> for Scaled {
+    fn synthesize<CS: ConstraintSystem<Fr>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let coeff = Fr::from(self.coeff);
+        let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+        let y = cs.alloc_input(
+            || "y",
+            || {
+                let mut y = self.x.ok_or(SynthesisError::AssignmentMissing)?;
+                y.mul_assign(&coeff);
+                Ok(y)
+            },
+        )?;
+        cs.enforce(
+            || "coeff * x = y",
+            |lc| lc + (coeff, x),
+            |lc| lc + CS::one(),
+            |lc| lc + y,
+        );
+
+        Ok(())
+    }
+}
+
+#[test]
+fn batch_rejects_different_circuits() {
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let params = generate_parameters::<DummyEngine, _>(
+        Scaled { coeff: 2, x: None },
+        Fr::one(),
+        Fr::one(),
+        Fr::from(48577),
+        Fr::from(22580),
+        Fr::from(53332),
+        Fr::from(5481),
+        Fr::from(3673),
+    )
+    .unwrap();
+
+    // Both circuits have the same number of variables and constraints, and
+    // the same densities, but a different constraint.
+    let circuits = vec![
+        Scaled {
+            coeff: 2,
+            x: Some(Fr::from(5)),
+        },
+        Scaled {
+            coeff: 3,
+            x: Some(Fr::from(5)),
+        },
+    ];
+    assert!(matches!(
+        create_proofs_batch(circuits, &params, &mut rng),
+        Err(SynthesisError::MismatchedCircuits)
+    ));
+
+    let circuits = vec![
+        Scaled {
+            coeff: 2,
+            x: Some(Fr::from(5)),
+        },
+        Scaled {
+            coeff: 2,
+            x: Some(Fr::from(7)),
+        },
+    ];
+    let proofs = create_proofs_batch(circuits, &params, &mut rng).unwrap();
+    let pvk = prepare_verifying_key(&params.vk);
+    assert!(verify_proof(&pvk, &proofs[1], &[Fr::from(14)]).is_ok());
+
+    // A key committing to a wire the circuits don't commit to is rejected,
+    // as it is when proving one circuit.
+    let mut committed = params.clone();
+    committed.vk.commitment_key.push(params.vk.ic[0]);
+    committed.vk.commitment_pok_key.push(params.vk.ic[0]);
+    let circuits = vec![Scaled {
+        coeff: 2,
+        x: Some(Fr::from(5)),
+    }];
+    assert!(matches!(
+        create_proofs_batch(circuits, &committed, &mut rng),
+        Err(SynthesisError::InvalidCommitment)
+    ));
+}
+
+#[test]
+fn verify_with_ic_tables() {
+    let params = {
+        let c = AndDemo {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_parameters::<DummyEngine, _>(
+            c,
+            Fr::one(),
+            Fr::one(),
+            Fr::from(48577),
+            Fr::from(22580),
+            Fr::from(53332),
+            Fr::from(5481),
+            Fr::from(3673),
+        )
+        .unwrap()
+    };
+    let pvk = prepare_verifying_key_with_tables(&params.vk);
+    assert!(pvk.ic_tables.is_some());
+
+    for &(a, b) in [(true, true), (true, false)].iter() {
+        let c = AndDemo {
+            a: Some(a),
+            b: Some(b),
+            _marker: PhantomData,
+        };
+        let proof = create_proof(c, &params, Fr::from(27134), Fr::from(17146)).unwrap();
+
+        let (input, wrong) = if a && b {
+            (Fr::one(), Fr::zero())
+        } else {
+            (Fr::zero(), Fr::one())
+        };
+        assert!(verify_proof(&pvk, &proof, &[input]).is_ok());
+        assert!(verify_proof(&pvk, &proof, &[wrong]).is_err());
+        assert!(verify_proof(&pvk, &proof, &[Fr::from(12345)]).is_err());
+    }
+}
+
+#[test]
+fn verification_diagnostics() {
+    let params = {
+        let c = AndDemo {
+            a: None,
+            b: None,
+            _marker: PhantomData,
+        };
+
+        generate_parameters::<DummyEngine, _>(
+            c,
+            Fr::one(),
+            Fr::one(),
+            Fr::from(48577),
+            Fr::from(22580),
+            Fr::from(53332),
+            Fr::from(5481),
+            Fr::from(3673),
+        )
+        .unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+    let proof = {
+        let c = AndDemo {
+            a: Some(true),
+            b: Some(true),
+            _marker: PhantomData,
+        };
+
+        create_proof(c, &params, Fr::from(27134), Fr::from(17146)).unwrap()
+    };
+
+    let report = verify_proof_with_diagnostics(&pvk, &proof, &[Fr::one()]);
+    assert!(report.result().is_ok());
+    assert_eq!(report.failure, None);
+    assert_eq!((report.expected_inputs, report.supplied_inputs), (1, 1));
+    assert_eq!(
+        report.terms.iter().map(|t| t.term).collect::<Vec<_>>(),
+        vec![
+            PairingTerm::Proof,
+            PairingTerm::PublicInputs,
+            PairingTerm::C
+        ]
+    );
+    assert_eq!(report.terms[0].g1, proof.a);
+    assert!(report.lhs.is_some() && report.lhs == report.rhs);
+
+    let report = verify_proof_with_diagnostics(&pvk, &proof, &[Fr::zero()]);
+    assert_eq!(report.failure, Some(VerificationFailure::Mismatch));
+    assert!(report.lhs != report.rhs);
+    assert_eq!(
+        report.result().is_err(),
+        verify_proof(&pvk, &proof, &[Fr::zero()]).is_err()
+    );
+
+    let report = verify_proof_with_diagnostics(&pvk, &proof, &[]);
+    assert_eq!(report.failure, Some(VerificationFailure::InputCount));
+    assert_eq!((report.expected_inputs, report.supplied_inputs), (1, 0));
+    assert!(report.ic_accumulator.is_none() && report.terms.is_empty());
+
+    // The G1 elements of the dummy engine are field elements.
+    let trivial = Proof::<DummyEngine> {
+        a: Fr::zero(),
+        ..proof
+    };
+    let report = verify_proof_with_diagnostics(&pvk, &trivial, &[Fr::one()]);
+    assert_eq!(report.failure, Some(VerificationFailure::TrivialLeftSide));
+}
+
+#[test]
+fn validate_parameters_reports_sections() {
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+    use std::sync::Arc;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let circuit = |a, b| AndDemo {
+        a,
+        b,
+        _marker: PhantomData,
+    };
+    let params = generate_parameters::<DummyEngine, _>(
+        circuit(None, None),
+        Fr::one(),
+        Fr::one(),
+        Fr::from(48577),
+        Fr::from(22580),
+        Fr::from(53332),
+        Fr::from(5481),
+        Fr::from(3673),
+    )
+    .unwrap();
+
+    let mut validate = |params: &Parameters<DummyEngine>, witness: bool| {
+        let c = if witness {
+            circuit(Some(true), Some(true))
+        } else {
+            circuit(None, None)
+        };
+        validate_parameters(params, c, &mut rng)
+    };
+
+    // The public input c appears in C, and in A through its input
+    // constraint, and the H query needs tau.
+    let unchecked = vec![
+        ParameterSection::Ic,
+        ParameterSection::H,
+        ParameterSection::A,
+    ];
+    assert_eq!(validate(&params, true).unwrap(), unchecked);
+    assert_eq!(validate(&params, false).unwrap(), unchecked);
+
+    let mut truncated = params.clone();
+    truncated.l = Arc::new(params.l[1..].to_vec());
+    assert!(matches!(
+        validate(&truncated, true),
+        Err(ParameterCheckError::Length(ParameterSection::L))
+    ));
+
+    let mut bad_delta = params.clone();
+    bad_delta.vk.delta_g1.add_assign(&Fr::one());
+    assert!(matches!(
+        validate(&bad_delta, true),
+        Err(ParameterCheckError::Inconsistent(ParameterSection::DeltaG1))
+    ));
+
+    let mut bad_b = params.clone();
+    let mut b_g2 = (*params.b_g2).clone();
+    b_g2[0].add_assign(&Fr::one());
+    bad_b.b_g2 = Arc::new(b_g2);
+    assert!(matches!(
+        validate(&bad_b, true),
+        Err(ParameterCheckError::Inconsistent(ParameterSection::BG2))
+    ));
+
+    // Neither aux variable of the circuit appears in C, so every L element
+    // is checked directly.
+    let mut bad_l = params.clone();
+    let mut l = (*params.l).clone();
+    l[0].add_assign(&Fr::one());
+    bad_l.l = Arc::new(l);
+    assert!(matches!(
+        validate(&bad_l, false),
+        Err(ParameterCheckError::Inconsistent(ParameterSection::L))
+    ));
+
+    // The H query can only be exercised through a proof.
+    let mut bad_h = params.clone();
+    bad_h.h = Arc::new(params.h.iter().map(|h| h.double()).collect());
+    assert_eq!(validate(&bad_h, false).unwrap(), unchecked);
+    match validate(&bad_h, true) {
+        Err(ParameterCheckError::ProofRejected(sections)) => assert_eq!(sections, unchecked),
+        _ => panic!("the proof should have been rejected"),
+    }
+}
+
+/// Proves knowledge of `a` and `b` whose product is the public input.
+pub(crate) struct MulCircuit<Scalar: PrimeField> {
+    pub(crate) a: Option<Scalar>,
+    pub(crate) b: Option<Scalar>,
+}
+
+impl<Scalar: PrimeField> Circuit<Scalar> for MulCircuit<Scalar> {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let c = cs.alloc_input(
+            || "c",
+            || {
+                let a = self.a.ok_or(SynthesisError::AssignmentMissing)?;
+                let b = self.b.ok_or(SynthesisError::AssignmentMissing)?;
+                Ok(a * b)
+            },
+        )?;
+
+        cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+        Ok(())
+    }
+}
+
+/// Proves knowledge of `x` such that `x^3 + x + 5 = out`, with the squaring
+/// in its own namespace.
+#[derive(Clone)]
+pub(crate) struct CubeCircuit<Scalar: PrimeField> {
+    pub(crate) x: Option<Scalar>,
+}
+
+impl<Scalar: PrimeField> Circuit<Scalar> for CubeCircuit<Scalar> {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let five = Scalar::from(5);
+        let x_val = self.x;
+        let x = cs.alloc(|| "x", || x_val.ok_or(SynthesisError::AssignmentMissing))?;
+        let x2_val = x_val.map(|x| x.square());
+        let x2 = {
+            let mut cs = cs.namespace(|| "square");
+            let x2 = cs.alloc(|| "x^2", || x2_val.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce(|| "x * x = x^2", |lc| lc + x, |lc| lc + x, |lc| lc + x2);
+            x2
+        };
+        let x3_val = x2_val.and_then(|x2| x_val.map(|x| x2 * x));
+        let x3 = cs.alloc(|| "x^3", || x3_val.ok_or(SynthesisError::AssignmentMissing))?;
+        cs.enforce(|| "x^2 * x = x^3", |lc| lc + x2, |lc| lc + x, |lc| lc + x3);
+        let out = cs.alloc_input(
+            || "out",
+            || {
+                x3_val
+                    .and_then(|x3| x_val.map(|x| x3 + x + five))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+        )?;
+        cs.enforce(
+            || "x^3 + x + 5 = out",
+            |lc| lc + x3 + x + (five, CS::one()),
+            |lc| lc + CS::one(),
+            |lc| lc + out,
+        );
+
+        Ok(())
+    }
+}
+
+/// Proves knowledge of a square root `x` of the public input, committing to
+/// `x`.
+pub(crate) struct CommittedSquare<Scalar: PrimeField> {
+    pub(crate) x: Option<Scalar>,
+}
+
+impl<Scalar: PrimeField> Circuit<Scalar> for CommittedSquare<Scalar> {
+    fn synthesize<CS: ConstraintSystem<Scalar>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        let x = cs.alloc(|| "x", || self.x.ok_or(SynthesisError::AssignmentMissing))?;
+        let y = cs.alloc_input(
+            || "y",
+            || {
+                self.x
+                    .map(|x| x.square())
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+        )?;
+        cs.enforce(|| "x * x = y", |lc| lc + x, |lc| lc + x, |lc| lc + y);
+        cs.commit(x)
+    }
+}
+
+#[test]
+fn committed_proof() {
+    use rand_core::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+    let toxic = [
+        Fr::from(48577),
+        Fr::from(22580),
+        Fr::from(53332),
+        Fr::from(5481),
+        Fr::from(3673),
+    ];
+
+    // Committing circuits need a nonzero eta and sigma.
+    assert!(matches!(
+        generate_parameters::<DummyEngine, _>(
+            CommittedSquare { x: None },
+            Fr::one(),
+            Fr::one(),
+            toxic[0],
+            toxic[1],
+            toxic[2],
+            toxic[3],
+            toxic[4],
+        ),
+        Err(SynthesisError::UnexpectedIdentity)
+    ));
+
+    let params = generate_committed_parameters::<DummyEngine, _>(
+        CommittedSquare { x: None },
+        Fr::one(),
+        Fr::one(),
+        toxic[0],
+        toxic[1],
+        toxic[2],
+        toxic[3],
+        toxic[4],
+        Fr::from(7),
+        Fr::from(11),
+    )
+    .unwrap();
+    assert_eq!(params.vk.commitment_key.len(), 1);
+    assert!(params.l.is_empty());
+    let witness = CommittedSquare {
+        x: Some(Fr::from(3)),
+    };
+    assert_eq!(
+        validate_parameters(&params, witness, &mut rng).unwrap(),
+        vec![
+            ParameterSection::Ic,
+            ParameterSection::H,
+            ParameterSection::A
+        ]
+    );
+
+    let pvk = prepare_verifying_key(&params.vk);
+    let (proof, opening) = create_committed_proof(
+        CommittedSquare {
+            x: Some(Fr::from(3)),
+        },
+        &params,
+        Fr::from(27134),
+        Fr::from(17146),
+        Fr::from(5),
+    )
+    .unwrap();
+    assert_eq!(
+        opening,
+        CommitmentOpening {
+            values: vec![Fr::from(3)],
+            blinding: Fr::from(5),
+        }
+    );
+    assert!(verify_proof(&pvk, &proof, &[Fr::from(9)]).is_ok());
+    assert!(verify_proof(&pvk, &proof, &[Fr::from(4)]).is_err());
+    assert!(verify_commitment(&params.vk, &proof, &opening).is_ok());
+
+    // Re-randomizing re-blinds the commitment too, so the result can't be
+    // linked to the original proof through D.
+    let rerandomized = rerandomize_proof(&params.vk, &proof, &mut rng);
+    assert!(verify_proof(&pvk, &rerandomized, &[Fr::from(9)]).is_ok());
+    assert!(rerandomized.d != proof.d);
+    assert!(rerandomized.d_pok != proof.d_pok);
+    assert!(matches!(
+        verify_commitment(&params.vk, &rerandomized, &opening),
+        Err(VerificationError::InvalidProof)
+    ));
+
+    let mut wrong = opening.clone();
+    wrong.values[0] = Fr::from(4);
+    assert!(matches!(
+        verify_commitment(&params.vk, &proof, &wrong),
+        Err(VerificationError::InvalidProof)
+    ));
+    wrong.values.push(Fr::from(3));
+    assert!(matches!(
+        verify_commitment(&params.vk, &proof, &wrong),
+        Err(VerificationError::InvalidVerifyingKey)
+    ));
+
+    // Dropping the commitment breaks the proof.
+    let uncommitted = Proof {
+        d: Fr::zero(),
+        d_pok: Fr::zero(),
+        ..proof.clone()
+    };
+    assert!(verify_proof(&pvk, &uncommitted, &[Fr::from(9)]).is_err());
+
+    // So does shifting the public inputs into it, which breaks the proof of
+    // knowledge.
+    let shifted = Proof {
+        d: proof.d + params.vk.ic[1] * Fr::from(5),
+        ..proof.clone()
+    };
+    assert!(matches!(
+        verify_proof(&pvk, &shifted, &[Fr::from(4)]),
+        Err(VerificationError::InvalidProof)
+    ));
+    let report = verify_proof_with_diagnostics(&pvk, &shifted, &[Fr::from(4)]);
+    assert_eq!(report.failure, Some(VerificationFailure::Commitment));
+
+    let mut bytes = vec![];
+    opening.write(&mut bytes).unwrap();
+    assert_eq!(CommitmentOpening::read(&bytes[..]).unwrap(), opening);
+
+    // Plain proofs can't open the commitment, so they aren't created.
+    assert!(matches!(
+        create_proof(
+            CommittedSquare {
+                x: Some(Fr::from(3)),
+            },
+            &params,
+            Fr::from(27134),
+            Fr::from(17146),
+        ),
+        Err(SynthesisError::MissingCommitmentBlinding)
+    ));
+}
@@ -14,11 +14,16 @@
 //! need to refer to or lookup a particular `VerifyingKey`. In practice, with
 //! large enough batches, it's manageable and not much worse performance-wise to
 //! keep batches of each statement type, vs one large adaptive batch.
+//!
+//! When proofs for several statements arrive together anyway, `MultiVerifier`
+//! groups them by `VerifyingKey` and checks all groups with a single final
+//! exponentiation.
 
+use std::fmt;
 use std::ops::AddAssign;
 
 use ff::Field;
-use group::{Curve, Group};
+use group::{prime::PrimeCurveAffine, Curve, Group};
 use pairing::{MillerLoopResult, MultiMillerLoop};
 use rand_core::{CryptoRng, RngCore};
 
@@ -92,7 +97,6 @@ where
 
     /// Perform batch verification with a particular `VerifyingKey`, returning
     /// `Ok(())` if all proofs were verified and `VerificationError` otherwise.
-    #[allow(non_snake_case)]
     pub fn verify<R: RngCore + CryptoRng>(
         self,
         mut rng: R,
@@ -105,66 +109,299 @@ where
         {
             return Err(VerificationError::InvalidVerifyingKey);
         }
+        if self.items.iter().any(|item| !is_well_formed(vk, item)) {
+            return Err(VerificationError::InvalidProof);
+        }
 
-        let mut ml_terms = Vec::<(E::G1Affine, E::G2Prepared)>::new();
-        let mut acc_Gammas = vec![E::Fr::zero(); vk.ic.len()];
-        let mut acc_Delta = E::G1::identity();
-        let mut acc_Y = E::Fr::zero();
-
-        for Item { proof, inputs } in self.items.into_iter() {
-            // The spec is explicit that z != 0.  Field::random is defined to
-            // return a uniformly-random field element (which may be 0), so we
-            // loop until it's not, avoiding needing an assert or throwing an
-            // error through no fault of the batch items. This will likely never
-            // actually loop, but handles the edge case.
-            let z = loop {
-                let z = E::Fr::random(&mut rng);
-                if !z.is_zero_vartime() {
-                    break z;
-                }
-            };
-
-            ml_terms.push(((proof.a * z).into(), (-proof.b).into()));
-
-            acc_Gammas[0] += &z; // a_0 is implicitly set to 1
-            for (a_i, acc_Gamma_i) in Iterator::zip(inputs.iter(), acc_Gammas.iter_mut().skip(1)) {
-                *acc_Gamma_i += &(z * a_i);
-            }
-            acc_Delta += proof.c * z;
-            acc_Y += &z;
+        let mut ml_terms = Vec::new();
+        push_terms(&mut ml_terms, &mut rng, vk, self.items.iter());
+
+        if check_terms::<E>(&ml_terms) {
+            Ok(())
+        } else {
+            Err(VerificationError::InvalidProof)
         }
+    }
 
-        ml_terms.push((acc_Delta.to_affine(), E::G2Prepared::from(vk.delta_g2)));
+    /// Perform batch verification with a particular `VerifyingKey` like
+    /// [`Verifier::verify`], but if the batch fails, return the queue indices
+    /// of the failing items in ascending order.
+    ///
+    /// The failing items are found by bisecting the queue with batched
+    /// checks, whose number grows with the number of failing items times the
+    /// logarithm of the batch size. Items whose number of inputs doesn't match
+    /// `vk`, or that carry a commitment although `vk` has no commitment key,
+    /// are reported as failing.
+    pub fn verify_with_blame<R: RngCore + CryptoRng>(
+        self,
+        mut rng: R,
+        vk: &VerifyingKey<E>,
+    ) -> Result<(), Vec<usize>> {
+        let mut failures = vec![];
+        let items = split_malformed(&mut failures, vk, self.items.iter().enumerate());
+        bisect(&mut failures, &mut rng, vk, &items, false);
 
-        let Psi = vk
-            .ic
+        into_result(failures)
+    }
+}
+
+/// A batch verification context for proofs under several `VerifyingKey`s.
+///
+/// Items are grouped by their key, and all groups share a single Miller loop
+/// and final exponentiation. If that combined check fails, the groups are
+/// bisected like in [`Verifier::verify_with_blame`] to find the failing items.
+pub struct MultiVerifier<E: MultiMillerLoop> {
+    groups: Vec<(VerifyingKey<E>, Vec<(usize, Item<E>)>)>,
+    len: usize,
+}
+
+// `VerifyingKey` isn't `Debug`, so only report the shape of the queue.
+impl<E: MultiMillerLoop> fmt::Debug for MultiVerifier<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiVerifier")
+            .field("keys", &self.groups.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+// Need to impl Default by hand to avoid a derived E: Default bound
+impl<E: MultiMillerLoop> Default for MultiVerifier<E> {
+    fn default() -> Self {
+        Self {
+            groups: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<E: MultiMillerLoop> MultiVerifier<E>
+where
+    E::G1: AddAssign<E::G1>,
+{
+    /// Construct a new multi-key batch verifier.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a (proof, inputs) tuple for verification with `vk`, returning its
+    /// index in the queue.
+    pub fn queue<I: Into<Item<E>>>(&mut self, vk: &VerifyingKey<E>, item: I) -> usize {
+        let index = self.len;
+        self.len += 1;
+
+        let entry = (index, item.into());
+        match self.groups.iter_mut().find(|(group_vk, _)| group_vk == vk) {
+            Some((_, items)) => items.push(entry),
+            None => self.groups.push((vk.clone(), vec![entry])),
+        }
+
+        index
+    }
+
+    /// Perform batch verification of all queued items, returning `Ok(())` if
+    /// all proofs were verified, and otherwise the queue indices of the
+    /// failing items in ascending order.
+    ///
+    /// Items whose number of inputs doesn't match their `VerifyingKey`, or that
+    /// carry a commitment although it has no commitment key, are reported as
+    /// failing.
+    pub fn verify<R: RngCore + CryptoRng>(self, mut rng: R) -> Result<(), Vec<usize>> {
+        let mut failures = vec![];
+        let groups: Vec<_> = self
+            .groups
             .iter()
-            .zip(acc_Gammas.iter())
-            .map(|(&Psi_i, acc_Gamma_i)| Psi_i * acc_Gamma_i)
-            .sum();
-
-        ml_terms.push((E::G1Affine::from(Psi), E::G2Prepared::from(vk.gamma_g2)));
-
-        // Covers the [acc_Y]⋅e(alpha_g1, beta_g2) component
-        //
-        // The multiplication by acc_Y is expensive -- it involves
-        // exponentiating by acc_Y because the result of the pairing is an
-        // element of a multiplicative subgroup of a large extension field.
-        // Instead, we add
-        //     ([acc_Y]⋅alpha_g1, beta_g2)
-        // to our Miller loop terms because
-        //     [acc_Y]⋅e(alpha_g1, beta_g2) = e([acc_Y]⋅alpha_g1, beta_g2)
-        ml_terms.push((
-            E::G1Affine::from(vk.alpha_g1 * acc_Y),
-            E::G2Prepared::from(vk.beta_g2),
-        ));
+            .map(|(vk, items)| {
+                let items = items.iter().map(|(index, item)| (*index, item));
+                (vk, split_malformed(&mut failures, vk, items))
+            })
+            .collect();
 
-        let ml_terms = ml_terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+        let mut ml_terms = Vec::new();
+        for (vk, items) in &groups {
+            push_terms(
+                &mut ml_terms,
+                &mut rng,
+                vk,
+                items.iter().map(|(_, item)| *item),
+            );
+        }
 
-        if E::multi_miller_loop(&ml_terms[..]).final_exponentiation() == E::Gt::identity() {
-            Ok(())
-        } else {
-            Err(VerificationError::InvalidProof)
+        if !check_terms::<E>(&ml_terms) {
+            for (vk, items) in &groups {
+                bisect(&mut failures, &mut rng, vk, items, false);
+            }
         }
+
+        into_result(failures)
     }
 }
+
+/// Returns whether the inputs of `item` match `vk.ic`, and whether it carries
+/// a commitment only if `vk` has a commitment key with a nontrivial sigma.
+fn is_well_formed<E: MultiMillerLoop>(vk: &VerifyingKey<E>, item: &Item<E>) -> bool {
+    let committed = !vk.commitment_key.is_empty() && !bool::from(vk.sigma_g2.is_identity());
+
+    item.inputs.len() + 1 == vk.ic.len()
+        && (committed || bool::from(item.proof.d.is_identity() & item.proof.d_pok.is_identity()))
+}
+
+/// Removes the malformed items from `items`, adding their indices to
+/// `failures`.
+fn split_malformed<'a, E: MultiMillerLoop>(
+    failures: &mut Vec<usize>,
+    vk: &VerifyingKey<E>,
+    items: impl Iterator<Item = (usize, &'a Item<E>)>,
+) -> Vec<(usize, &'a Item<E>)> {
+    let (valid, malformed): (Vec<_>, Vec<_>) =
+        items.partition(|(_, item)| is_well_formed(vk, item));
+    failures.extend(malformed.into_iter().map(|(index, _)| index));
+    valid
+}
+
+/// Adds the indices of the invalid items among `items` to `failures`.
+///
+/// The items are bisected with batched checks, skipping halves that verify,
+/// so finding `k` invalid items out of `n` takes `O(k log n)` checks. If
+/// `known_invalid` is set, `items` is already known to contain an invalid
+/// item and isn't checked as a whole.
+fn bisect<E, R>(
+    failures: &mut Vec<usize>,
+    rng: &mut R,
+    vk: &VerifyingKey<E>,
+    items: &[(usize, &Item<E>)],
+    known_invalid: bool,
+) where
+    E: MultiMillerLoop,
+    E::G1: AddAssign<E::G1>,
+    R: RngCore + CryptoRng,
+{
+    if items.is_empty() {
+        return;
+    }
+    if !known_invalid {
+        let mut ml_terms = Vec::new();
+        push_terms(&mut ml_terms, rng, vk, items.iter().map(|(_, item)| *item));
+        if check_terms::<E>(&ml_terms) {
+            return;
+        }
+    }
+    if let [(index, _)] = items {
+        failures.push(*index);
+        return;
+    }
+
+    let (left, right) = items.split_at(items.len() / 2);
+    let found = failures.len();
+    bisect(failures, rng, vk, left, false);
+    // If the left half verifies, the invalid items are all on the right.
+    bisect(failures, rng, vk, right, failures.len() == found);
+}
+
+fn into_result(mut failures: Vec<usize>) -> Result<(), Vec<usize>> {
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        failures.sort_unstable();
+        Err(failures)
+    }
+}
+
+/// Appends to `ml_terms` the Miller loop terms of the batched Groth16
+/// equation for `items` under `vk`, each item weighted by a random scalar.
+///
+/// Every item must be well-formed under `vk`. The proofs of knowledge of the
+/// items' commitments are checked along with the Groth16 equation, each
+/// weighted by its own random scalar.
+#[allow(non_snake_case)]
+fn push_terms<'a, E, R>(
+    ml_terms: &mut Vec<(E::G1Affine, E::G2Prepared)>,
+    rng: &mut R,
+    vk: &VerifyingKey<E>,
+    items: impl Iterator<Item = &'a Item<E>>,
+) where
+    E: MultiMillerLoop,
+    E::G1: AddAssign<E::G1>,
+    R: RngCore + CryptoRng,
+{
+    let mut acc_Gammas = vec![E::Fr::zero(); vk.ic.len()];
+    let mut acc_Delta = E::G1::identity();
+    let mut acc_D = E::G1::identity();
+    let mut acc_Pok = E::G1::identity();
+    let mut acc_Sigma = E::G1::identity();
+    let mut acc_Y = E::Fr::zero();
+
+    for Item { proof, inputs } in items {
+        // The spec is explicit that z != 0.  Field::random is defined to
+        // return a uniformly-random field element (which may be 0), so we
+        // loop until it's not, avoiding needing an assert or throwing an
+        // error through no fault of the batch items. This will likely never
+        // actually loop, but handles the edge case.
+        let mut nonzero = || loop {
+            let z = E::Fr::random(&mut *rng);
+            if !z.is_zero_vartime() {
+                break z;
+            }
+        };
+        let z = nonzero();
+
+        ml_terms.push(((proof.a * z).into(), (-proof.b).into()));
+
+        acc_Gammas[0] += &z; // a_0 is implicitly set to 1
+        for (a_i, acc_Gamma_i) in Iterator::zip(inputs.iter(), acc_Gammas.iter_mut().skip(1)) {
+            *acc_Gamma_i += &(z * a_i);
+        }
+        acc_Delta += proof.c * z;
+        acc_D += proof.d * z;
+        acc_Y += &z;
+
+        if !vk.commitment_key.is_empty() {
+            let w = nonzero();
+            acc_Pok += proof.d_pok * w;
+            acc_Sigma += proof.d * w;
+        }
+    }
+
+    ml_terms.push((acc_Delta.to_affine(), E::G2Prepared::from(vk.delta_g2)));
+
+    // The commitments are paired with gamma along with the public inputs.
+    let Psi: E::G1 = vk
+        .ic
+        .iter()
+        .zip(acc_Gammas.iter())
+        .map(|(&Psi_i, acc_Gamma_i)| Psi_i * acc_Gamma_i)
+        .sum::<E::G1>()
+        + acc_D;
+
+    ml_terms.push((E::G1Affine::from(Psi), E::G2Prepared::from(vk.gamma_g2)));
+
+    // Covers the proofs of knowledge e(d_pok, g2) = e(D, sigma).
+    if !vk.commitment_key.is_empty() {
+        ml_terms.push((
+            acc_Pok.to_affine(),
+            E::G2Prepared::from(E::G2Affine::generator()),
+        ));
+        ml_terms.push((acc_Sigma.to_affine(), E::G2Prepared::from(-vk.sigma_g2)));
+    }
+
+    // Covers the [acc_Y]⋅e(alpha_g1, beta_g2) component
+    //
+    // The multiplication by acc_Y is expensive -- it involves
+    // exponentiating by acc_Y because the result of the pairing is an
+    // element of a multiplicative subgroup of a large extension field.
+    // Instead, we add
+    //     ([acc_Y]⋅alpha_g1, beta_g2)
+    // to our Miller loop terms because
+    //     [acc_Y]⋅e(alpha_g1, beta_g2) = e([acc_Y]⋅alpha_g1, beta_g2)
+    ml_terms.push((
+        E::G1Affine::from(vk.alpha_g1 * acc_Y),
+        E::G2Prepared::from(vk.beta_g2),
+    ));
+}
+
+/// Returns whether the Miller loop terms multiply out to the identity.
+fn check_terms<E: MultiMillerLoop>(ml_terms: &[(E::G1Affine, E::G2Prepared)]) -> bool {
+    let ml_terms = ml_terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+
+    E::multi_miller_loop(&ml_terms[..]).final_exponentiation() == E::Gt::identity()
+}
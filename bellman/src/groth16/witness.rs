@@ -0,0 +1,545 @@
+//! Witnesses and constraint systems that can be stored apart from their
+//! circuit, so that witness generation and proving can run in different
+//! processes.
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use std::io::{self, Read, Write};
+
+use crate::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+
+/// The assignment of every variable of a circuit.
+///
+/// `input_assignment[0]` is the "one" input variable, followed by the public
+/// inputs in allocation order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Witness<S: PrimeField> {
+    pub input_assignment: Vec<S>,
+    pub aux_assignment: Vec<S>,
+}
+
+impl<S: PrimeField> Witness<S> {
+    /// Returns the public inputs, i.e. the input assignment without the "one"
+    /// input variable.
+    pub fn public_inputs(&self) -> &[S] {
+        &self.input_assignment[1..]
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_scalars(&mut writer, &self.input_assignment)?;
+        write_scalars(&mut writer, &self.aux_assignment)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let input_assignment = read_scalars(&mut reader)?;
+        if input_assignment.first() != Some(&S::one()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "first input is not one",
+            ));
+        }
+        let aux_assignment = read_scalars(&mut reader)?;
+
+        Ok(Witness {
+            input_assignment,
+            aux_assignment,
+        })
+    }
+}
+
+struct WitnessAssembly<S: PrimeField> {
+    input_assignment: Vec<S>,
+    aux_assignment: Vec<S>,
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for WitnessAssembly<S> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux_assignment.push(f()?);
+
+        Ok(Variable(Index::Aux(self.aux_assignment.len() - 1)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, f: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.input_assignment.push(f()?);
+
+        Ok(Variable(Index::Input(self.input_assignment.len() - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        // Constraints are recorded by `R1cs`, not by the witness.
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Synthesizes `circuit` and returns the assignment of its variables, without
+/// evaluating its constraints.
+pub fn generate_witness<S, C>(circuit: C) -> Result<Witness<S>, SynthesisError>
+where
+    S: PrimeField,
+    C: Circuit<S>,
+{
+    let mut assembly = WitnessAssembly {
+        input_assignment: vec![],
+        aux_assignment: vec![],
+    };
+
+    assembly.alloc_input(|| "", || Ok(S::one()))?;
+
+    circuit.synthesize(&mut assembly)?;
+
+    Ok(Witness {
+        input_assignment: assembly.input_assignment,
+        aux_assignment: assembly.aux_assignment,
+    })
+}
+
+/// The magic that starts a serialized [`R1cs`], followed by its version.
+const R1CS_MAGIC: &[u8; 4] = b"G16R";
+/// The current version of the [`R1cs`] layout. Version 0 has no magic and no
+/// committed variables.
+const R1CS_VERSION: u8 = 1;
+
+/// The rank-1 constraint system of a circuit: its number of variables and
+/// its constraints `a * b = c`.
+///
+/// Like in a [`Witness`], input 0 is the "one" input variable. The input
+/// constraints added by the prover and the parameter generator are not
+/// included.
+#[derive(Clone)]
+pub struct R1cs<S: PrimeField> {
+    num_inputs: usize,
+    num_aux: usize,
+    constraints: Vec<(
+        LinearCombination<S>,
+        LinearCombination<S>,
+        LinearCombination<S>,
+    )>,
+    committed: Vec<usize>,
+}
+
+impl<S: PrimeField> ConstraintSystem<S> for R1cs<S> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // There is no assignment, so we don't even invoke the
+        // function for obtaining one.
+
+        let index = self.num_aux;
+        self.num_aux += 1;
+
+        Ok(Variable(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<S, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // There is no assignment, so we don't even invoke the
+        // function for obtaining one.
+
+        let index = self.num_inputs;
+        self.num_inputs += 1;
+
+        Ok(Variable(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LB: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+        LC: FnOnce(LinearCombination<S>) -> LinearCombination<S>,
+    {
+        self.constraints.push((
+            a(LinearCombination::zero()),
+            b(LinearCombination::zero()),
+            c(LinearCombination::zero()),
+        ));
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn pop_namespace(&mut self) {
+        // Do nothing; we don't care about namespaces in this context.
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        record_commitment(&mut self.committed, self.num_aux, variable)
+    }
+}
+
+impl<S: PrimeField> R1cs<S> {
+    /// Synthesizes `circuit` without assignments and records its constraints.
+    pub fn synthesize<C: Circuit<S>>(circuit: C) -> Result<Self, SynthesisError> {
+        let mut r1cs = R1cs {
+            num_inputs: 0,
+            num_aux: 0,
+            constraints: vec![],
+            committed: vec![],
+        };
+
+        r1cs.alloc_input(|| "", || Ok(S::one()))?;
+
+        circuit.synthesize(&mut r1cs)?;
+
+        Ok(r1cs)
+    }
+
+    /// Assembles a constraint system whose variables have already been
+    /// checked against `num_inputs` and `num_aux`.
+    pub(super) fn from_parts(
+        num_inputs: usize,
+        num_aux: usize,
+        constraints: Vec<(
+            LinearCombination<S>,
+            LinearCombination<S>,
+            LinearCombination<S>,
+        )>,
+    ) -> Self {
+        R1cs {
+            num_inputs,
+            num_aux,
+            constraints,
+            committed: vec![],
+        }
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.num_inputs
+    }
+
+    pub fn num_aux(&self) -> usize {
+        self.num_aux
+    }
+
+    /// Returns the indices of the committed auxiliary variables, in the order
+    /// they were committed.
+    pub fn committed(&self) -> &[usize] {
+        &self.committed
+    }
+
+    pub fn constraints(
+        &self,
+    ) -> &[(
+        LinearCombination<S>,
+        LinearCombination<S>,
+        LinearCombination<S>,
+    )] {
+        &self.constraints
+    }
+
+    /// Returns whether `witness` satisfies every constraint.
+    pub fn is_satisfied(&self, witness: &Witness<S>) -> bool {
+        if witness.input_assignment.len() != self.num_inputs
+            || witness.aux_assignment.len() != self.num_aux
+        {
+            return false;
+        }
+
+        let eval = |lc: &LinearCombination<S>| {
+            lc.as_ref()
+                .iter()
+                .fold(S::zero(), |acc, (var, coeff)| match var.get_unchecked() {
+                    Index::Input(i) => acc + witness.input_assignment[i] * coeff,
+                    Index::Aux(i) => acc + witness.aux_assignment[i] * coeff,
+                })
+        };
+        self.constraints
+            .iter()
+            .all(|(a, b, c)| eval(a) * eval(b) == eval(c))
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(R1CS_MAGIC)?;
+        writer.write_u8(R1CS_VERSION)?;
+        writer.write_u32::<BigEndian>(self.num_inputs as u32)?;
+        writer.write_u32::<BigEndian>(self.num_aux as u32)?;
+        writer.write_u32::<BigEndian>(self.constraints.len() as u32)?;
+        for (a, b, c) in &self.constraints {
+            for lc in [a, b, c].iter() {
+                writer.write_u32::<BigEndian>(lc.as_ref().len() as u32)?;
+                for (var, coeff) in lc.as_ref() {
+                    match var.get_unchecked() {
+                        Index::Input(i) => {
+                            writer.write_u8(0)?;
+                            writer.write_u32::<BigEndian>(i as u32)?;
+                        }
+                        Index::Aux(i) => {
+                            writer.write_u8(1)?;
+                            writer.write_u32::<BigEndian>(i as u32)?;
+                        }
+                    }
+                    writer.write_all(coeff.to_repr().as_ref())?;
+                }
+            }
+        }
+        writer.write_u32::<BigEndian>(self.committed.len() as u32)?;
+        for &i in &self.committed {
+            writer.write_u32::<BigEndian>(i as u32)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a constraint system written by [`Self::write`], or by an earlier
+    /// version without a magic, whose variables are all uncommitted.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut prefix = io::Cursor::new([0u8; 4]);
+        reader.read_exact(prefix.get_mut())?;
+        let version = if prefix.get_ref() == R1CS_MAGIC {
+            prefix.set_position(4);
+            match reader.read_u8()? {
+                version @ 1..=R1CS_VERSION => version,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unsupported version",
+                    ))
+                }
+            }
+        } else {
+            0
+        };
+
+        Self::read_layout(prefix.chain(reader), version)
+    }
+
+    fn read_layout<R: Read>(mut reader: R, version: u8) -> io::Result<Self> {
+        let num_inputs = reader.read_u32::<BigEndian>()? as usize;
+        let num_aux = reader.read_u32::<BigEndian>()? as usize;
+        let num_constraints = reader.read_u32::<BigEndian>()? as usize;
+        if num_inputs == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "missing the one input",
+            ));
+        }
+
+        let read_lc = |reader: &mut R| -> io::Result<LinearCombination<S>> {
+            let len = reader.read_u32::<BigEndian>()?;
+            let mut lc = LinearCombination::zero();
+            for _ in 0..len {
+                let tag = reader.read_u8()?;
+                let i = reader.read_u32::<BigEndian>()? as usize;
+                let var = match tag {
+                    0 if i < num_inputs => Variable(Index::Input(i)),
+                    1 if i < num_aux => Variable(Index::Aux(i)),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "invalid variable",
+                        ))
+                    }
+                };
+                lc = lc + (read_scalar(reader)?, var);
+            }
+
+            Ok(lc)
+        };
+
+        let mut constraints = vec![];
+        for _ in 0..num_constraints {
+            let a = read_lc(&mut reader)?;
+            let b = read_lc(&mut reader)?;
+            let c = read_lc(&mut reader)?;
+            constraints.push((a, b, c));
+        }
+
+        let mut committed = vec![];
+        let num_committed = match version {
+            0 => 0,
+            _ => reader.read_u32::<BigEndian>()?,
+        };
+        for _ in 0..num_committed {
+            let i = reader.read_u32::<BigEndian>()? as usize;
+            record_commitment(&mut committed, num_aux, Variable(Index::Aux(i))).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "invalid committed variable")
+            })?;
+        }
+
+        Ok(R1cs {
+            num_inputs,
+            num_aux,
+            constraints,
+            committed,
+        })
+    }
+}
+
+/// Appends `variable` to the committed auxiliary variables `committed`, out
+/// of `num_aux` allocated ones.
+pub(super) fn record_commitment(
+    committed: &mut Vec<usize>,
+    num_aux: usize,
+    variable: Variable,
+) -> Result<(), SynthesisError> {
+    match variable.get_unchecked() {
+        Index::Aux(i) if i < num_aux && !committed.contains(&i) => {
+            committed.push(i);
+            Ok(())
+        }
+        _ => Err(SynthesisError::InvalidCommitment),
+    }
+}
+
+/// The opening of the commitment carried by a proof: the values of the
+/// committed variables, in the order they were committed, and the blinding
+/// factor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitmentOpening<S: PrimeField> {
+    pub values: Vec<S>,
+    pub blinding: S,
+}
+
+impl<S: PrimeField> CommitmentOpening<S> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_scalars(&mut writer, &self.values)?;
+        writer.write_all(self.blinding.to_repr().as_ref())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let values = read_scalars(&mut reader)?;
+        let blinding = read_scalar(&mut reader)?;
+
+        Ok(CommitmentOpening { values, blinding })
+    }
+}
+
+fn write_scalars<S: PrimeField, W: Write>(writer: &mut W, scalars: &[S]) -> io::Result<()> {
+    writer.write_u32::<BigEndian>(scalars.len() as u32)?;
+    for scalar in scalars {
+        writer.write_all(scalar.to_repr().as_ref())?;
+    }
+
+    Ok(())
+}
+
+fn read_scalars<S: PrimeField, R: Read>(reader: &mut R) -> io::Result<Vec<S>> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    (0..len).map(|_| read_scalar(reader)).collect()
+}
+
+fn read_scalar<S: PrimeField, R: Read>(reader: &mut R) -> io::Result<S> {
+    let mut repr = S::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+
+    Option::from(S::from_repr(repr))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid field element"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::tests::CubeCircuit;
+    use crate::groth16::{
+        create_proof, create_proof_from_witness, generate_random_parameters, prepare_verifying_key,
+        verify_proof,
+    };
+
+    use bls12_381::{Bls12, Scalar};
+    use ff::Field;
+    use rand::thread_rng;
+
+    #[test]
+    fn proves_from_serialized_witness() {
+        let mut rng = thread_rng();
+        let params =
+            generate_random_parameters::<Bls12, _, _>(CubeCircuit { x: None }, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let circuit = CubeCircuit {
+            x: Some(Scalar::from(3)),
+        };
+        let witness = generate_witness(circuit.clone()).unwrap();
+        assert_eq!(witness.public_inputs(), &[Scalar::from(35)]);
+
+        let r1cs = R1cs::<Scalar>::synthesize(CubeCircuit { x: None }).unwrap();
+        assert_eq!(r1cs.num_inputs(), 2);
+        assert_eq!(r1cs.num_aux(), 3);
+        assert_eq!(r1cs.constraints().len(), 3);
+        assert!(r1cs.is_satisfied(&witness));
+
+        // Move both stages through their serialized forms.
+        let mut witness_bytes = vec![];
+        witness.write(&mut witness_bytes).unwrap();
+        let witness = Witness::<Scalar>::read(&witness_bytes[..]).unwrap();
+        let mut r1cs_bytes = vec![];
+        r1cs.write(&mut r1cs_bytes).unwrap();
+        let r1cs = R1cs::<Scalar>::read(&r1cs_bytes[..]).unwrap();
+        assert!(r1cs.is_satisfied(&witness));
+
+        // Without the magic, the layout has no committed variables.
+        let legacy = R1cs::<Scalar>::read(&r1cs_bytes[5..r1cs_bytes.len() - 4]).unwrap();
+        assert!(legacy.is_satisfied(&witness));
+        r1cs_bytes[4] = 2;
+        assert!(R1cs::<Scalar>::read(&r1cs_bytes[..]).is_err());
+
+        let r = Scalar::random(&mut rng);
+        let s = Scalar::random(&mut rng);
+        let proof = create_proof_from_witness(&r1cs, &witness, &params, r, s).unwrap();
+        assert!(verify_proof(&pvk, &proof, witness.public_inputs()).is_ok());
+        assert!(proof == create_proof(circuit, &params, r, s).unwrap());
+
+        let mut wrong = witness.clone();
+        wrong.aux_assignment[0] += Scalar::one();
+        assert!(!r1cs.is_satisfied(&wrong));
+
+        wrong.aux_assignment.pop();
+        assert!(matches!(
+            create_proof_from_witness(&r1cs, &wrong, &params, r, s),
+            Err(SynthesisError::AssignmentMissing)
+        ));
+    }
+}
@@ -0,0 +1,436 @@
+//! [KZG] polynomial commitments over the powers of tau of a common ceremony.
+//!
+//! The `tau_g1` and `tau_g2` powers of a [`CommonParamterInStorage`] form a
+//! KZG structured reference string. A polynomial `p` with fewer coefficients
+//! than there are powers in G1 is committed to as `C = [p(tau)]_1`, and the
+//! opening of `p` at a point `z` is the commitment to the quotient
+//! `(p(X) - p(z)) / (X - z)`, checked with
+//! `e(C - [p(z)]_1, [1]_2) = e(proof, [tau - z]_2)`.
+//!
+//! A polynomial can also be opened at several points with a single proof, the
+//! commitment to `(p(X) - I(X)) / Z(X)` for the interpolant `I` of its values
+//! and the vanishing polynomial `Z` of the points. Verifying it takes the
+//! commitment to `Z` in G2, so the number of points is bounded by the number
+//! of powers in G2.
+//!
+//! Polynomials are [`Polynomial`]s, i.e. [`EvaluationDomain`]s of scalars
+//! holding the coefficients, lowest degree first.
+//!
+//! [KZG]: https://www.iacr.org/archive/asiacrypt2010/6477178/6477178.pdf
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use ff::{Field, PrimeField, PrimeFieldBits};
+use group::{prime::PrimeCurveAffine, Curve, Group, WnafGroup};
+use pairing::{Engine, MillerLoopResult, MultiMillerLoop};
+
+use crate::domain::{EvaluationDomain, Scalar};
+use crate::groth16::mpc::CommonParamterInStorage;
+use crate::multicore::Worker;
+use crate::multiexp::{multiexp, FullDensity};
+use crate::{SynthesisError, VerificationError};
+
+/// A polynomial in coefficient form.
+pub type Polynomial<S> = EvaluationDomain<S, Scalar<S>>;
+
+/// Errors that can occur while committing to or opening polynomials.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KzgError {
+    /// The powers passed to [`KzgSrs`] don't form a usable SRS.
+    MalformedSrs,
+    /// The polynomial has more coefficients than the SRS has powers in G1.
+    DegreeTooLarge,
+    /// There are more opening points than the SRS supports.
+    TooManyPoints,
+    /// The same point appears twice among the opening points.
+    DuplicatePoint,
+}
+
+impl fmt::Display for KzgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            KzgError::MalformedSrs => write!(f, "malformed KZG SRS"),
+            KzgError::DegreeTooLarge => write!(f, "polynomial degree is too large for the SRS"),
+            KzgError::TooManyPoints => write!(f, "too many opening points for the SRS"),
+            KzgError::DuplicatePoint => write!(f, "duplicate opening point"),
+        }
+    }
+}
+
+impl Error for KzgError {}
+
+/// Returns the polynomial with the coefficients `coeffs`, lowest degree
+/// first.
+pub fn polynomial_from_coeffs<S: PrimeField>(
+    coeffs: Vec<S>,
+) -> Result<Polynomial<S>, SynthesisError> {
+    EvaluationDomain::from_coeffs(coeffs.into_iter().map(Scalar).collect())
+}
+
+/// Returns the polynomial taking the values `evaluations` over the powers of
+/// the root of unity of the smallest domain holding them. The domain is
+/// padded with zero evaluations.
+pub fn polynomial_from_evaluations<S: PrimeField>(
+    worker: &Worker,
+    evaluations: Vec<S>,
+) -> Result<Polynomial<S>, SynthesisError> {
+    let mut poly = polynomial_from_coeffs(evaluations)?;
+    poly.ifft(worker);
+    Ok(poly)
+}
+
+/// Evaluates `poly` at `point`.
+pub fn evaluate<S: PrimeField>(poly: &Polynomial<S>, point: S) -> S {
+    evaluate_coeffs(&coeffs(poly), point)
+}
+
+/// Returns the coefficients of `poly` up to its last nonzero one.
+fn coeffs<S: PrimeField>(poly: &Polynomial<S>) -> Vec<S> {
+    let coeffs = poly.as_ref();
+    let len = coeffs
+        .iter()
+        .rposition(|c| !c.0.is_zero_vartime())
+        .map_or(0, |i| i + 1);
+    coeffs[..len].iter().map(|c| c.0).collect()
+}
+
+fn evaluate_coeffs<S: PrimeField>(coeffs: &[S], point: S) -> S {
+    coeffs
+        .iter()
+        .rev()
+        .fold(S::zero(), |acc, coeff| acc * point + coeff)
+}
+
+/// Returns the coefficients of `prod(X - points[i])`.
+fn vanishing_polynomial<S: PrimeField>(points: &[S]) -> Vec<S> {
+    let mut z = vec![S::one()];
+    for point in points {
+        // z * (X - point)
+        z.push(S::zero());
+        for i in (0..z.len()).rev() {
+            let lower = if i > 0 { z[i - 1] } else { S::zero() };
+            z[i] = lower - z[i] * point;
+        }
+    }
+    z
+}
+
+/// Divides `dividend` by the monic polynomial `divisor`, returning the
+/// quotient and the remainder.
+fn divide<S: PrimeField>(dividend: &[S], divisor: &[S]) -> (Vec<S>, Vec<S>) {
+    let d = divisor.len() - 1;
+    if dividend.len() <= d {
+        return (vec![], dividend.to_vec());
+    }
+
+    let mut remainder = dividend.to_vec();
+    let mut quotient = vec![S::zero(); dividend.len() - d];
+    for i in (0..quotient.len()).rev() {
+        let coeff = remainder[i + d];
+        quotient[i] = coeff;
+        for (r, c) in remainder[i..i + d].iter_mut().zip(divisor.iter()) {
+            *r -= coeff * c;
+        }
+    }
+    remainder.truncate(d);
+    (quotient, remainder)
+}
+
+/// Returns the coefficients of the polynomial of degree less than
+/// `points.len()` taking `values[i]` at `points[i]`.
+fn interpolate<S: PrimeField>(points: &[S], values: &[S]) -> Result<Vec<S>, KzgError> {
+    let z = vanishing_polynomial(points);
+    let mut interpolant = vec![S::zero(); points.len()];
+    for (point, value) in points.iter().zip(values.iter()) {
+        // The Lagrange basis polynomial of `point`, up to its denominator.
+        let (basis, _) = divide(&z, &[-*point, S::one()]);
+        let denominator = evaluate_coeffs(&basis, *point);
+        let scale = Option::<S>::from(denominator.invert()).ok_or(KzgError::DuplicatePoint)?;
+        let scale = scale * value;
+        for (i, b) in interpolant.iter_mut().zip(basis.iter()) {
+            *i += scale * b;
+        }
+    }
+    Ok(interpolant)
+}
+
+/// A KZG structured reference string: the powers `[tau^i]_1` and
+/// `[tau^i]_2` of a secret `tau`.
+#[derive(Clone, Debug)]
+pub struct KzgSrs<E: Engine> {
+    g1: Arc<Vec<E::G1Affine>>,
+    g2: Vec<E::G2Affine>,
+}
+
+impl<E: Engine> KzgSrs<E> {
+    /// Builds an SRS from the powers `[tau^i]_1` and `[tau^i]_2`, which must
+    /// start at the generators.
+    ///
+    /// The powers are assumed to be well-formed, e.g. because they come out
+    /// of a verified ceremony; only their first elements are checked.
+    pub fn from_powers(g1: Vec<E::G1Affine>, g2: Vec<E::G2Affine>) -> Result<Self, KzgError> {
+        if g1.is_empty() || g2.len() < 2 {
+            return Err(KzgError::MalformedSrs);
+        }
+        if g1[0] != E::G1Affine::generator() || g2[0] != E::G2Affine::generator() {
+            return Err(KzgError::MalformedSrs);
+        }
+
+        Ok(KzgSrs {
+            g1: Arc::new(g1),
+            g2,
+        })
+    }
+
+    /// Builds an SRS from the tau powers of the final state of a common
+    /// ceremony.
+    pub fn from_ceremony(state: &CommonParamterInStorage<E>) -> Result<Self, KzgError>
+    where
+        E::G1: WnafGroup,
+        E::G2: WnafGroup,
+    {
+        let mut g1 = vec![E::G1Affine::identity(); state.tau_g1.len()];
+        E::G1::batch_normalize(&state.tau_g1, &mut g1);
+        let mut g2 = vec![E::G2Affine::identity(); state.tau_g2.len()];
+        E::G2::batch_normalize(&state.tau_g2, &mut g2);
+
+        Self::from_powers(g1, g2)
+    }
+
+    /// Returns the largest degree of the polynomials this SRS can commit to.
+    pub fn max_degree(&self) -> usize {
+        self.g1.len() - 1
+    }
+
+    /// Returns the largest number of points a polynomial can be opened at
+    /// with [`KzgSrs::open_multi`].
+    pub fn max_points(&self) -> usize {
+        (self.g2.len() - 1).min(self.g1.len())
+    }
+}
+
+impl<E: Engine> KzgSrs<E>
+where
+    E::Fr: PrimeFieldBits,
+{
+    fn commit_coeffs(&self, coeffs: &[E::Fr]) -> Result<E::G1Affine, KzgError> {
+        if coeffs.len() > self.g1.len() {
+            return Err(KzgError::DegreeTooLarge);
+        }
+
+        let exponents = Arc::new(coeffs.iter().map(|c| c.to_le_bits()).collect::<Vec<_>>());
+        let commitment: E::G1 =
+            multiexp(&Worker::new(), (self.g1.clone(), 0), FullDensity, exponents)
+                .wait()
+                .map_err(|_| KzgError::MalformedSrs)?;
+
+        Ok(commitment.to_affine())
+    }
+
+    /// Commits to `poly`.
+    pub fn commit(&self, poly: &Polynomial<E::Fr>) -> Result<E::G1Affine, KzgError> {
+        self.commit_coeffs(&coeffs(poly))
+    }
+
+    /// Opens `poly` at `point`, returning `poly(point)` and the proof.
+    pub fn open(
+        &self,
+        poly: &Polynomial<E::Fr>,
+        point: E::Fr,
+    ) -> Result<(E::Fr, E::G1Affine), KzgError> {
+        let coeffs = coeffs(poly);
+        let (quotient, remainder) = divide(&coeffs, &[-point, E::Fr::one()]);
+        let value = remainder.first().copied().unwrap_or_else(E::Fr::zero);
+
+        Ok((value, self.commit_coeffs(&quotient)?))
+    }
+
+    /// Opens `poly` at every one of `points` with a single proof, returning
+    /// the values of `poly` at `points` and the proof.
+    pub fn open_multi(
+        &self,
+        poly: &Polynomial<E::Fr>,
+        points: &[E::Fr],
+    ) -> Result<(Vec<E::Fr>, E::G1Affine), KzgError> {
+        if points.len() > self.max_points() {
+            return Err(KzgError::TooManyPoints);
+        }
+        if (1..points.len()).any(|i| points[..i].contains(&points[i])) {
+            return Err(KzgError::DuplicatePoint);
+        }
+
+        let coeffs = coeffs(poly);
+        let values = points
+            .iter()
+            .map(|z| evaluate_coeffs(&coeffs, *z))
+            .collect();
+
+        let (quotient, _) = divide(&coeffs, &vanishing_polynomial(points));
+        Ok((values, self.commit_coeffs(&quotient)?))
+    }
+}
+
+impl<E: MultiMillerLoop> KzgSrs<E> {
+    /// Checks that `proof` opens `commitment` to `value` at `point`.
+    pub fn verify(
+        &self,
+        commitment: &E::G1Affine,
+        point: E::Fr,
+        value: E::Fr,
+        proof: &E::G1Affine,
+    ) -> Result<(), VerificationError> {
+        // e(C - [value]_1 + point * proof, [1]_2) = e(proof, [tau]_2)
+        let lhs = commitment.to_curve() - self.g1[0] * value + *proof * point;
+
+        if bool::from(
+            E::multi_miller_loop(&[
+                (&lhs.to_affine(), &self.g2[0].into()),
+                (&(-*proof), &self.g2[1].into()),
+            ])
+            .final_exponentiation()
+            .is_identity(),
+        ) {
+            Ok(())
+        } else {
+            Err(VerificationError::InvalidProof)
+        }
+    }
+
+    /// Checks that `proof` opens `commitment` to `values[i]` at every
+    /// `points[i]`, as returned by [`KzgSrs::open_multi`].
+    pub fn verify_multi(
+        &self,
+        commitment: &E::G1Affine,
+        points: &[E::Fr],
+        values: &[E::Fr],
+        proof: &E::G1Affine,
+    ) -> Result<(), VerificationError> {
+        if points.len() != values.len() || points.len() > self.max_points() {
+            return Err(VerificationError::InvalidVerifyingKey);
+        }
+        let interpolant =
+            interpolate(points, values).map_err(|_| VerificationError::InvalidProof)?;
+
+        let mut lhs = commitment.to_curve();
+        for (base, coeff) in self.g1.iter().zip(interpolant.iter()) {
+            lhs -= *base * coeff;
+        }
+        let mut vanishing = E::G2::identity();
+        for (base, coeff) in self.g2.iter().zip(vanishing_polynomial(points).iter()) {
+            vanishing += *base * coeff;
+        }
+
+        // e(C - [I(tau)]_1, [1]_2) = e(proof, [Z(tau)]_2)
+        if bool::from(
+            E::multi_miller_loop(&[
+                (&lhs.to_affine(), &self.g2[0].into()),
+                (&(-*proof), &vanishing.to_affine().into()),
+            ])
+            .final_exponentiation()
+            .is_identity(),
+        ) {
+            Ok(())
+        } else {
+            Err(VerificationError::InvalidProof)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16::mpc::{
+        initial_common_paramters, mpc_common_paramters_generator, verify_common_paramter,
+    };
+
+    use bls12_381::{Bls12, Scalar as Fr};
+    use rand::thread_rng;
+
+    fn srs() -> KzgSrs<Bls12> {
+        let initial = initial_common_paramters::<Bls12>(16);
+        let contribution = mpc_common_paramters_generator(&initial, (2, 3, 5));
+        KzgSrs::from_ceremony(&verify_common_paramter(&initial, &contribution)).unwrap()
+    }
+
+    #[test]
+    fn opens_at_a_point() {
+        let mut rng = thread_rng();
+        let srs = srs();
+        let poly = polynomial_from_coeffs((0..10).map(|_| Fr::random(&mut rng)).collect()).unwrap();
+        let commitment = srs.commit(&poly).unwrap();
+
+        let point = Fr::random(&mut rng);
+        let (value, proof) = srs.open(&poly, point).unwrap();
+        assert_eq!(value, evaluate(&poly, point));
+        assert!(srs.verify(&commitment, point, value, &proof).is_ok());
+        assert!(srs
+            .verify(&commitment, point, value + Fr::one(), &proof)
+            .is_err());
+        assert!(srs
+            .verify(&commitment, point + Fr::one(), value, &proof)
+            .is_err());
+
+        let too_large = polynomial_from_coeffs(vec![Fr::one(); 17]).unwrap();
+        assert_eq!(
+            srs.commit(&too_large).unwrap_err(),
+            KzgError::DegreeTooLarge
+        );
+    }
+
+    #[test]
+    fn opens_at_several_points() {
+        let mut rng = thread_rng();
+        let srs = srs();
+        let worker = Worker::new();
+        let evaluations: Vec<_> = (0..8).map(|_| Fr::random(&mut rng)).collect();
+        let poly = polynomial_from_evaluations(&worker, evaluations.clone()).unwrap();
+        assert_eq!(evaluate(&poly, Fr::one()), evaluations[0]);
+        let commitment = srs.commit(&poly).unwrap();
+
+        let points: Vec<_> = (0..5).map(|_| Fr::random(&mut rng)).collect();
+        let (values, proof) = srs.open_multi(&poly, &points).unwrap();
+        assert!(srs
+            .verify_multi(&commitment, &points, &values, &proof)
+            .is_ok());
+
+        let mut wrong = values.clone();
+        wrong[3] += Fr::one();
+        assert!(srs
+            .verify_multi(&commitment, &points, &wrong, &proof)
+            .is_err());
+        assert!(srs
+            .verify_multi(&commitment, &points[..4], &values[..4], &proof)
+            .is_err());
+
+        // A single point is an ordinary opening.
+        let (value, single) = srs.open(&poly, points[0]).unwrap();
+        assert!(srs
+            .verify_multi(&commitment, &points[..1], &[value], &single)
+            .is_ok());
+
+        let duplicates = [points[0], points[1], points[0]];
+        assert_eq!(
+            srs.open_multi(&poly, &duplicates).unwrap_err(),
+            KzgError::DuplicatePoint
+        );
+        assert_eq!(
+            srs.open_multi(&poly, &[Fr::one(); 16]).unwrap_err(),
+            KzgError::TooManyPoints
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_powers() {
+        let srs = srs();
+        assert_eq!(
+            KzgSrs::<Bls12>::from_powers(srs.g1[1..].to_vec(), srs.g2.clone()).unwrap_err(),
+            KzgError::MalformedSrs
+        );
+        assert_eq!(
+            KzgSrs::<Bls12>::from_powers(srs.g1.to_vec(), srs.g2[..1].to_vec()).unwrap_err(),
+            KzgError::MalformedSrs
+        );
+    }
+}
@@ -141,6 +141,8 @@ pub mod domain;
 pub mod gadgets;
 #[cfg(feature = "groth16")]
 pub mod groth16;
+#[cfg(feature = "groth16")]
+pub mod kzg;
 pub mod multicore;
 pub mod multiexp;
 
@@ -164,6 +166,8 @@ pub extern "C" fn test_create_parameters() -> groth16::Parameters<Bls12> {
 }
 
 pub mod batch;
+pub mod batch_prove;
+pub mod prepared_verify;
 pub mod slow;
 use bls12_381::Bls12;
 use ff::PrimeField;
@@ -367,6 +371,21 @@ pub enum SynthesisError {
     IoError(io::Error),
     /// During CRS generation, we observed an unconstrained auxiliary variable
     UnconstrainedVariable,
+    /// The operation was stopped through its cancellation flag
+    Cancelled,
+    /// During synthesis, a variable was committed that is not an auxiliary
+    /// variable or was already committed, or the committed variables did not
+    /// match the commitment key of the CRS
+    InvalidCommitment,
+    /// During proof generation, the circuit committed to variables but no
+    /// blinding factor for the commitment was given
+    MissingCommitmentBlinding,
+    /// During CRS generation, the powers of tau of the common ceremony state
+    /// did not start from the group generators
+    MalformedCeremonyState,
+    /// During batch proof generation, the circuits did not all synthesize to
+    /// the same constraint system
+    MismatchedCircuits,
 }
 
 impl From<io::Error> for SynthesisError {
@@ -387,6 +406,11 @@ impl Error for SynthesisError {
             SynthesisError::UnexpectedIdentity => "encountered an identity element in the CRS",
             SynthesisError::IoError(_) => "encountered an I/O error",
             SynthesisError::UnconstrainedVariable => "auxiliary variable was unconstrained",
+            SynthesisError::Cancelled => "operation was cancelled",
+            SynthesisError::InvalidCommitment => "invalid committed variable",
+            SynthesisError::MissingCommitmentBlinding => "missing commitment blinding factor",
+            SynthesisError::MalformedCeremonyState => "malformed common ceremony state",
+            SynthesisError::MismatchedCircuits => "circuits in the batch differ",
         }
     }
 }
@@ -481,6 +505,15 @@ pub trait ConstraintSystem<Scalar: PrimeField>: Sized {
     /// Not intended for downstream use; use `namespace` instead.
     fn get_root(&mut self) -> &mut Self::Root;
 
+    /// Marks the auxiliary variable `variable` as committed. Proofs of the
+    /// circuit then carry a Pedersen commitment to the values of the committed
+    /// variables, in the order they were marked. Constraint systems that don't
+    /// produce proofs ignore this.
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        let _ = variable;
+        Ok(())
+    }
+
     /// Begin a namespace for this constraint system.
     fn namespace<NR, N>(&mut self, name_fn: N) -> Namespace<'_, Scalar, Self::Root>
     where
@@ -557,6 +590,10 @@ impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Sca
     fn get_root(&mut self) -> &mut Self::Root {
         self.0.get_root()
     }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        self.0.commit(variable)
+    }
 }
 
 impl<'a, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> Drop for Namespace<'a, Scalar, CS> {
@@ -620,4 +657,8 @@ impl<'cs, Scalar: PrimeField, CS: ConstraintSystem<Scalar>> ConstraintSystem<Sca
     fn get_root(&mut self) -> &mut Self::Root {
         (**self).get_root()
     }
+
+    fn commit(&mut self, variable: Variable) -> Result<(), SynthesisError> {
+        (**self).commit(variable)
+    }
 }
@@ -233,3 +233,127 @@ fn batch_verify() {
         batch_amortized
     );
 }
+
+#[test]
+fn multi_key_batch_verify() {
+    let mut rng = thread_rng();
+
+    // Two circuits, differing only in their round constants.
+    let circuits = (0..2)
+        .map(|_| {
+            let constants = (0..MIMC_ROUNDS)
+                .map(|_| Scalar::random(&mut rng))
+                .collect::<Vec<_>>();
+            let params = {
+                let c = MiMCDemo {
+                    xl: None,
+                    xr: None,
+                    constants: &constants,
+                };
+
+                generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+            };
+            (constants, params)
+        })
+        .collect::<Vec<_>>();
+
+    let mut items = vec![];
+    for i in 0..6 {
+        let (constants, params) = &circuits[i % 2];
+        let xl = Scalar::random(&mut rng);
+        let xr = Scalar::random(&mut rng);
+        let image = mimc(xl, xr, constants);
+        let c = MiMCDemo {
+            xl: Some(xl),
+            xr: Some(xr),
+            constants,
+        };
+        let proof = create_random_proof(c, params, &mut rng).unwrap();
+        items.push((i % 2, proof, image));
+    }
+
+    let mut batch = batch::MultiVerifier::new();
+    for (i, (circuit, proof, image)) in items.iter().enumerate() {
+        assert_eq!(
+            batch.queue(&circuits[*circuit].1.vk, (proof.clone(), vec![*image])),
+            i
+        );
+    }
+    assert!(batch.verify(&mut rng).is_ok());
+
+    // A wrong input and a proof checked against the wrong key are both found.
+    let mut batch = batch::MultiVerifier::new();
+    for (i, (circuit, proof, image)) in items.iter().enumerate() {
+        let circuit = if i == 1 { 1 - circuit } else { *circuit };
+        let image = if i == 4 {
+            *image + Scalar::one()
+        } else {
+            *image
+        };
+        batch.queue(&circuits[circuit].1.vk, (proof.clone(), vec![image]));
+    }
+    assert_eq!(batch.verify(&mut rng), Err(vec![1, 4]));
+}
+
+#[test]
+fn batch_verify_with_blame() {
+    let mut rng = thread_rng();
+
+    let constants = (0..MIMC_ROUNDS)
+        .map(|_| Scalar::random(&mut rng))
+        .collect::<Vec<_>>();
+    let params = {
+        let c = MiMCDemo {
+            xl: None,
+            xr: None,
+            constants: &constants,
+        };
+
+        generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+    };
+
+    let items = (0..16)
+        .map(|_| {
+            let xl = Scalar::random(&mut rng);
+            let xr = Scalar::random(&mut rng);
+            let image = mimc(xl, xr, &constants);
+            let c = MiMCDemo {
+                xl: Some(xl),
+                xr: Some(xr),
+                constants: &constants,
+            };
+            (create_random_proof(c, &params, &mut rng).unwrap(), image)
+        })
+        .collect::<Vec<_>>();
+
+    let mut batch = batch::Verifier::new();
+    for (proof, image) in &items {
+        batch.queue((proof.clone(), vec![*image]));
+    }
+    assert!(batch.verify_with_blame(&mut rng, &params.vk).is_ok());
+
+    let mut batch = batch::Verifier::new();
+    for (i, (proof, image)) in items.iter().enumerate() {
+        let inputs = match i {
+            3 => vec![*image + Scalar::one()],
+            6 => vec![*image - Scalar::one()],
+            9 => vec![*image, *image],
+            14 => vec![Scalar::zero()],
+            _ => vec![*image],
+        };
+        // The key has no commitment key, so a commitment can't make up for
+        // the wrong input.
+        let proof = match i {
+            6 => Proof {
+                d: params.vk.ic[1],
+                ..proof.clone()
+            },
+            _ => proof.clone(),
+        };
+        batch.queue((proof, inputs));
+    }
+    assert_eq!(
+        batch.verify_with_blame(&mut rng, &params.vk),
+        Err(vec![3, 6, 9, 14])
+    );
+}
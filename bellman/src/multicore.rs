@@ -5,8 +5,9 @@
 #[cfg(feature = "multicore")]
 mod implementation {
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
-    use crossbeam_channel::{bounded, Receiver};
+    use crossbeam_channel::{bounded, Receiver, RecvTimeoutError};
     use lazy_static::lazy_static;
     use log::{error, trace};
     use rayon::current_num_threads;
@@ -108,6 +109,21 @@ mod implementation {
             self.receiver.recv().unwrap()
         }
 
+        /// Blocks until the result is ready or `timeout` has passed, handing
+        /// the waiter back in the latter case.
+        pub fn wait_timeout(self, timeout: Duration) -> Result<T, Self> {
+            if rayon::current_thread_index().is_some() {
+                let msg = "wait_timeout() cannot be called from within a thread pool since that would lead to deadlocks";
+                error!("{}", msg);
+                panic!("{}", msg);
+            }
+            match self.receiver.recv_timeout(timeout) {
+                Ok(val) => Ok(val),
+                Err(RecvTimeoutError::Timeout) => Err(self),
+                Err(RecvTimeoutError::Disconnected) => panic!("worker dropped its result"),
+            }
+        }
+
         /// One-off sending.
         pub fn done(val: T) -> Self {
             let (sender, receiver) = bounded(1);
@@ -182,6 +198,11 @@ mod implementation {
             self.val.take().expect("unmet data dependency")
         }
 
+        /// Returns the result, which is always ready.
+        pub fn wait_timeout(self, _: std::time::Duration) -> Result<T, Self> {
+            Ok(self.wait())
+        }
+
         /// One-off sending.
         pub fn done(val: T) -> Self {
             Waiter { val: Some(val) }
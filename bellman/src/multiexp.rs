@@ -5,6 +5,7 @@ use group::prime::{PrimeCurve, PrimeCurveAffine};
 use std::io;
 use std::iter;
 use std::ops::AddAssign;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 #[cfg(feature = "multicore")]
@@ -114,6 +115,7 @@ impl<'a> QueryDensity for &'a FullDensity {
     }
 }
 
+#[derive(PartialEq)]
 pub struct DensityTracker {
     bv: BitVec,
 }
@@ -156,11 +158,100 @@ impl DensityTracker {
     }
 }
 
+/// How many exponents a multiexp window processes between checks of its
+/// cancellation flag.
+const CANCEL_CHECK_INTERVAL: usize = 1024;
+
+/// How far the multiexps started with [`multiexp_with_progress`] have got.
+///
+/// Each window of a multiexp goes over every exponent, so progress is counted
+/// in exponents processed per window and scaled back to exponents when read.
+/// Several multiexps can share one, in which case their progress is summed.
+#[derive(Debug, Default)]
+pub struct MultiexpProgress {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    exponents: AtomicUsize,
+}
+
+impl MultiexpProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of exponents processed so far and the number of
+    /// exponents of every multiexp started with this progress.
+    pub fn exponents(&self) -> (usize, usize) {
+        let exponents = self.exponents.load(Ordering::Relaxed);
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return (0, exponents);
+        }
+        let done = self.done.load(Ordering::Relaxed).min(total);
+        let done = (done as u128 * exponents as u128 / total as u128) as usize;
+        (done, exponents)
+    }
+}
+
+/// Where a base goes in the window of `c` bits starting at bit `skip`.
+enum Slot {
+    Skip,
+    Accumulator,
+    Bucket(usize),
+}
+
+fn slot<F: PrimeFieldBits>(
+    exp: &FieldBits<F::ReprBits>,
+    skip: u32,
+    c: u32,
+    handle_trivial: bool,
+) -> Slot {
+    let (exp_is_zero, exp_is_one) = {
+        let (first, rest) = exp.split_first().unwrap();
+        let rest_unset = rest.not_any();
+        (!*first && rest_unset, *first && rest_unset)
+    };
+
+    if exp_is_zero {
+        Slot::Skip
+    } else if exp_is_one {
+        if handle_trivial {
+            Slot::Accumulator
+        } else {
+            Slot::Skip
+        }
+    } else {
+        let exp = exp
+            .into_iter()
+            .by_val()
+            .skip(skip as usize)
+            .take(c as usize)
+            .enumerate()
+            .fold(0u64, |acc, (i, b)| acc + ((b as u64) << i));
+
+        if exp != 0 {
+            Slot::Bucket((exp - 1) as usize)
+        } else {
+            Slot::Skip
+        }
+    }
+}
+
+fn window_size(num_exponents: usize) -> u32 {
+    if num_exponents < 32 {
+        3u32
+    } else {
+        (f64::from(num_exponents as u32)).ln().ceil() as u32
+    }
+}
+
 fn multiexp_inner<Q, D, G, S>(
     bases: S,
     density_map: D,
     exponents: Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>,
     c: u32,
+    cancel: Option<Arc<AtomicBool>>,
+    progress: Option<Arc<MultiexpProgress>>,
 ) -> Result<G, SynthesisError>
 where
     for<'a> &'a Q: QueryDensity,
@@ -175,6 +266,14 @@ where
                      exponents: Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>,
                      skip: u32|
           -> Result<_, SynthesisError> {
+        let cancelled = || cancel.as_ref().map_or(false, |c| c.load(Ordering::Relaxed));
+        let report = |n: usize| {
+            if let Some(progress) = progress.as_ref() {
+                progress.done.fetch_add(n, Ordering::Relaxed);
+            }
+        };
+        let mut reported = 0;
+
         // Accumulate the result
         let mut acc = G::identity();
 
@@ -188,36 +287,24 @@ where
         let handle_trivial = skip == 0;
 
         // Sort the bases into buckets
-        for (exp, density) in exponents.iter().zip(density_map.as_ref().iter()) {
+        for (i, (exp, density)) in exponents
+            .iter()
+            .zip(density_map.as_ref().iter())
+            .enumerate()
+        {
+            if i % CANCEL_CHECK_INTERVAL == 0 {
+                if cancelled() {
+                    return Err(SynthesisError::Cancelled);
+                }
+                report(i - reported);
+                reported = i;
+            }
+
             if density {
-                let (exp_is_zero, exp_is_one) = {
-                    let (first, rest) = exp.split_first().unwrap();
-                    let rest_unset = rest.not_any();
-                    (!*first && rest_unset, *first && rest_unset)
-                };
-
-                if exp_is_zero {
-                    bases.skip(1)?;
-                } else if exp_is_one {
-                    if handle_trivial {
-                        acc.add_assign_from_source(&mut bases)?;
-                    } else {
-                        bases.skip(1)?;
-                    }
-                } else {
-                    let exp = exp
-                        .into_iter()
-                        .by_val()
-                        .skip(skip as usize)
-                        .take(c as usize)
-                        .enumerate()
-                        .fold(0u64, |acc, (i, b)| acc + ((b as u64) << i));
-
-                    if exp != 0 {
-                        (&mut buckets[(exp - 1) as usize]).add_assign_from_source(&mut bases)?;
-                    } else {
-                        bases.skip(1)?;
-                    }
+                match slot::<G::Scalar>(exp, skip, c, handle_trivial) {
+                    Slot::Skip => bases.skip(1)?,
+                    Slot::Accumulator => acc.add_assign_from_source(&mut bases)?,
+                    Slot::Bucket(i) => (&mut buckets[i]).add_assign_from_source(&mut bases)?,
                 }
             }
         }
@@ -231,6 +318,7 @@ where
             running_sum.add_assign(&exp);
             acc.add_assign(&running_sum);
         }
+        report(exponents.len() - reported);
 
         Ok(acc)
     };
@@ -264,11 +352,65 @@ where
     G::Scalar: PrimeFieldBits,
     S: SourceBuilder<<G as PrimeCurve>::Affine>,
 {
-    let c = if exponents.len() < 32 {
-        3u32
-    } else {
-        (f64::from(exponents.len() as u32)).ln().ceil() as u32
-    };
+    multiexp_inner_async(pool, bases, density_map, exponents, None, None)
+}
+
+/// Perform multi-exponentiation like [`multiexp`], but stop with
+/// `SynthesisError::Cancelled` once `cancel` is set. The flag is checked
+/// periodically while each window sorts its bases into buckets.
+pub fn multiexp_cancellable<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>,
+    cancel: Option<Arc<AtomicBool>>,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    multiexp_inner_async(pool, bases, density_map, exponents, cancel, None)
+}
+
+/// Perform multi-exponentiation like [`multiexp_cancellable`], counting the
+/// exponents it processes in `progress`.
+pub fn multiexp_with_progress<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>,
+    cancel: Option<Arc<AtomicBool>>,
+    progress: Arc<MultiexpProgress>,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    multiexp_inner_async(pool, bases, density_map, exponents, cancel, Some(progress))
+}
+
+fn multiexp_inner_async<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>,
+    cancel: Option<Arc<AtomicBool>>,
+    progress: Option<Arc<MultiexpProgress>>,
+) -> Waiter<Result<G, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    let c = window_size(exponents.len());
 
     if let Some(query_size) = density_map.as_ref().get_query_size() {
         // If the density map has a known query size, it should not be
@@ -277,7 +419,134 @@ where
         assert!(query_size == exponents.len());
     }
 
-    pool.compute(move || multiexp_inner(bases, density_map, exponents, c))
+    if let Some(progress) = progress.as_ref() {
+        let windows = (G::Scalar::NUM_BITS + c - 1) / c;
+        progress
+            .total
+            .fetch_add(exponents.len() * windows as usize, Ordering::Relaxed);
+        progress
+            .exponents
+            .fetch_add(exponents.len(), Ordering::Relaxed);
+    }
+
+    pool.compute(move || multiexp_inner(bases, density_map, exponents, c, cancel, progress))
+}
+
+fn multiexp_batch_inner<Q, D, G, S>(
+    bases: S,
+    density_map: D,
+    exponents: Arc<Vec<Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>>>,
+    c: u32,
+) -> Result<Vec<G>, SynthesisError>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    let len = exponents.first().map_or(0, |exponents| exponents.len());
+    let count = exponents.len();
+
+    // Perform this region of every multiexp
+    let this = move |skip: u32| -> Result<Vec<G>, SynthesisError> {
+        let mut accs = vec![G::identity(); exponents.len()];
+        let mut buckets = vec![vec![G::identity(); (1 << c) - 1]; exponents.len()];
+        let mut bases = bases.clone().build();
+        let handle_trivial = skip == 0;
+
+        // Each base is read once, and added to the buckets of every
+        // multiexp that needs it.
+        let mut slots = Vec::with_capacity(exponents.len());
+        for (i, density) in density_map.as_ref().iter().take(len).enumerate() {
+            if !density {
+                continue;
+            }
+
+            slots.clear();
+            slots.extend(
+                exponents
+                    .iter()
+                    .map(|exponents| slot::<G::Scalar>(&exponents[i], skip, c, handle_trivial)),
+            );
+            if slots.iter().all(|slot| matches!(slot, Slot::Skip)) {
+                bases.skip(1)?;
+                continue;
+            }
+
+            let base = *bases.next()?;
+            for ((slot, acc), buckets) in slots.iter().zip(accs.iter_mut()).zip(buckets.iter_mut())
+            {
+                match *slot {
+                    Slot::Skip => {}
+                    Slot::Accumulator => {
+                        AddAssign::<&<G as PrimeCurve>::Affine>::add_assign(acc, &base)
+                    }
+                    Slot::Bucket(j) => {
+                        AddAssign::<&<G as PrimeCurve>::Affine>::add_assign(&mut buckets[j], &base)
+                    }
+                }
+            }
+        }
+
+        // Summation by parts, for each multiexp
+        for (acc, buckets) in accs.iter_mut().zip(buckets.into_iter()) {
+            let mut running_sum = G::identity();
+            for exp in buckets.into_iter().rev() {
+                running_sum.add_assign(&exp);
+                acc.add_assign(&running_sum);
+            }
+        }
+
+        Ok(accs)
+    };
+
+    let parts = (0..G::Scalar::NUM_BITS)
+        .into_par_iter()
+        .step_by(c as usize)
+        .map(this)
+        .collect::<Vec<Result<_, _>>>();
+
+    parts
+        .into_iter()
+        .rev()
+        .try_fold(vec![G::identity(); count], |accs, part| {
+            part.map(|part| {
+                accs.into_iter()
+                    .zip(part.into_iter())
+                    .map(|(acc, part)| (0..c).fold(acc, |acc, _| acc.double()) + part)
+                    .collect()
+            })
+        })
+}
+
+/// Perform one multi-exponentiation over the same bases and density map for
+/// each vector of `exponents`, reading every base once for all of them.
+/// Every vector must have the same length, which must match the query size.
+pub fn multiexp_batch<Q, D, G, S>(
+    pool: &Worker,
+    bases: S,
+    density_map: D,
+    exponents: Vec<Arc<Vec<FieldBits<<G::Scalar as PrimeFieldBits>::ReprBits>>>>,
+) -> Waiter<Result<Vec<G>, SynthesisError>>
+where
+    for<'a> &'a Q: QueryDensity,
+    D: Send + Sync + 'static + Clone + AsRef<Q>,
+    G: PrimeCurve,
+    G::Scalar: PrimeFieldBits,
+    S: SourceBuilder<<G as PrimeCurve>::Affine>,
+{
+    let len = exponents.first().map_or(0, |exponents| exponents.len());
+    assert!(exponents.iter().all(|exponents| exponents.len() == len));
+
+    if let Some(query_size) = density_map.as_ref().get_query_size() {
+        assert!(query_size == len);
+    }
+
+    let c = window_size(len);
+    let exponents = Arc::new(exponents);
+
+    pool.compute(move || multiexp_batch_inner(bases, density_map, exponents, c))
 }
 
 #[cfg(feature = "pairing")]
@@ -321,7 +590,86 @@ fn test_with_bls12() {
     let naive: <Bls12 as Engine>::G1 = naive_multiexp(g.clone(), v);
 
     let pool = Worker::new();
-    let fast = multiexp(&pool, (g, 0), FullDensity, v_bits).wait().unwrap();
+    let fast = multiexp(&pool, (g.clone(), 0), FullDensity, v_bits.clone())
+        .wait()
+        .unwrap();
 
     assert_eq!(naive, fast);
+
+    // Two multiexps sharing a progress count every exponent of both.
+    let progress = Arc::new(MultiexpProgress::new());
+    let first = multiexp_with_progress(
+        &pool,
+        (g.clone(), 0),
+        FullDensity,
+        v_bits.clone(),
+        None,
+        progress.clone(),
+    );
+    let second = multiexp_with_progress(
+        &pool,
+        (g, SAMPLES / 2),
+        FullDensity,
+        Arc::new(v_bits[..SAMPLES / 2].to_vec()),
+        None,
+        progress.clone(),
+    );
+    assert_eq!(naive, first.wait().unwrap());
+    let _: <Bls12 as Engine>::G1 = second.wait().unwrap();
+    let exponents = SAMPLES + SAMPLES / 2;
+    assert_eq!(progress.exponents(), (exponents, exponents));
+}
+
+#[cfg(feature = "pairing")]
+#[test]
+fn batch_matches_multiexp() {
+    use bls12_381::{Bls12, Scalar};
+    use ff::Field;
+    use group::{Curve, Group};
+    use pairing::Engine;
+
+    const SAMPLES: usize = 1 << 10;
+
+    let mut rng = rand::thread_rng();
+    let g = Arc::new(
+        (0..SAMPLES)
+            .map(|_| <Bls12 as Engine>::G1::random(&mut rng).to_affine())
+            .collect::<Vec<_>>(),
+    );
+    let mut density = DensityTracker::new();
+    for i in 0..SAMPLES {
+        density.add_element();
+        if i % 3 != 0 {
+            density.inc(i);
+        }
+    }
+    let density = Arc::new(density);
+    let exponents = (0..4)
+        .map(|k| {
+            Arc::new(
+                (0..SAMPLES)
+                    .map(|i| match (k + i) % 7 {
+                        0 => Scalar::zero(),
+                        1 => Scalar::one(),
+                        _ => Scalar::random(&mut rng),
+                    })
+                    .map(|e| e.to_le_bits())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let pool = Worker::new();
+    let batch: Vec<<Bls12 as Engine>::G1> =
+        multiexp_batch(&pool, (g.clone(), 0), density.clone(), exponents.clone())
+            .wait()
+            .unwrap();
+
+    assert_eq!(batch.len(), exponents.len());
+    for (batch, exponents) in batch.into_iter().zip(exponents.into_iter()) {
+        let single = multiexp(&pool, (g.clone(), 0), density.clone(), exponents)
+            .wait()
+            .unwrap();
+        assert_eq!(batch, single);
+    }
 }
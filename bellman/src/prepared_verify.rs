@@ -0,0 +1,84 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use bls12_381::{Bls12, Scalar};
+use ff::{Field, PrimeField};
+use rand::thread_rng;
+
+use crate::groth16::{
+    create_random_proof, generate_random_parameters, prepare_verifying_key,
+    prepare_verifying_key_with_tables, verify_proof,
+};
+use crate::{Circuit, ConstraintSystem, SynthesisError};
+
+/// A circuit exposing `roots.len()` public inputs, each of them the square
+/// of a private value.
+struct Squares<S: PrimeField> {
+    roots: Vec<Option<S>>,
+}
+
+impl<S: PrimeField> Circuit<S> for Squares<S> {
+    fn synthesize<CS: ConstraintSystem<S>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        for (i, root) in self.roots.into_iter().enumerate() {
+            let x = cs.alloc(
+                || format!("root {}", i),
+                || root.ok_or(SynthesisError::AssignmentMissing),
+            )?;
+            let x2 = cs.alloc_input(
+                || format!("square {}", i),
+                || {
+                    root.map(|r| r.square())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?;
+            cs.enforce(
+                || format!("square {}", i),
+                |lc| lc + x,
+                |lc| lc + x,
+                |lc| lc + x2,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn bench_prepared_verify(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Verification with IC tables");
+
+    let mut rng = thread_rng();
+
+    for &n in [1usize, 16, 64, 256].iter() {
+        let params = {
+            let c = Squares::<Scalar> {
+                roots: vec![None; n],
+            };
+
+            generate_random_parameters::<Bls12, _, _>(c, &mut rng).unwrap()
+        };
+
+        let roots = (0..n).map(|_| Scalar::random(&mut rng)).collect::<Vec<_>>();
+        let inputs = roots.iter().map(|r| r.square()).collect::<Vec<_>>();
+        let proof = {
+            let c = Squares {
+                roots: roots.into_iter().map(Some).collect(),
+            };
+
+            create_random_proof(c, &params, &mut rng).unwrap()
+        };
+
+        let pvk = prepare_verifying_key(&params.vk);
+        group.bench_with_input(BenchmarkId::new("Plain", n), &inputs, |b, inputs| {
+            b.iter(|| verify_proof(&pvk, &proof, inputs))
+        });
+
+        let pvk = prepare_verifying_key_with_tables(&params.vk);
+        group.bench_with_input(BenchmarkId::new("Tables", n), &inputs, |b, inputs| {
+            b.iter(|| verify_proof(&pvk, &proof, inputs))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_prepared_verify);
+criterion_main!(benches);